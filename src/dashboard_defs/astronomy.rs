@@ -0,0 +1,387 @@
+use std::{
+	borrow::Cow,
+	f64::consts::TAU
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+	utility_types::{
+		vec2f::Vec2f,
+		update_rate::UpdateRate
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This computes the sunrise, sunset, civil twilight, and moon phase for the station's location, without any network access.
+
+- The sun times come from the sunrise equation (https://en.wikipedia.org/wiki/Sunrise_equation), which is accurate to about a minute
+away from the poles. Sunrise and sunset are when the sun's center is 0.833 degrees below the horizon (which accounts for refraction
+and the sun's radius), and civil twilight is when it's 6 degrees below.
+- The moon phase is based on the mean length of a lunar month, measured from a known new moon, which is off by at most half a day or so.
+- The sun times are only recomputed when the local date changes, while the moon phase is recomputed on every update. */
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct AstronomyConfig {
+	pub latitude: f64,
+	pub longitude: f64 // This is positive to the east of Greenwich
+}
+
+// Near the poles, the sun may stay above or below a given altitude for the whole day
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SunCrossing {
+	RisesAndSets {rise: DateTime<Utc>, set: DateTime<Utc>},
+	AlwaysAbove,
+	AlwaysBelow
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunTimes {
+	pub daylight: SunCrossing, // This is sunrise and sunset
+	pub civil_twilight: SunCrossing // And this is civil dawn and dusk
+}
+
+// The fraction is how far along the lunar month the moon is (0 is a new moon, and 0.5 is a full moon)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoonPhase {
+	pub fraction: f64
+}
+
+#[derive(Clone)]
+pub struct AstronomyState {
+	config: AstronomyConfig,
+	date: NaiveDate,
+	sun_times: SunTimes,
+	moon_phase: MoonPhase
+}
+
+#[derive(Clone)]
+struct AstronomyWindowState {
+	text: String
+}
+
+//////////
+
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+const J2000_JULIAN_DAY: f64 = 2451545.0;
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+const SUNRISE_ALTITUDE_DEGREES: f64 = -0.833;
+const CIVIL_TWILIGHT_ALTITUDE_DEGREES: f64 = -6.0;
+
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+const REFERENCE_NEW_MOON_JULIAN_DAY: f64 = 2451550.1; // This is January 6th, 2000
+
+fn to_julian_day(time: DateTime<Utc>) -> f64 {
+	time.timestamp_millis() as f64 / 1000.0 / SECONDS_PER_DAY + UNIX_EPOCH_JULIAN_DAY
+}
+
+fn from_julian_day(julian_day: f64) -> DateTime<Utc> {
+	let millis = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * SECONDS_PER_DAY * 1000.0).round() as i64;
+	DateTime::from_timestamp_millis(millis).expect("A computed sun time was out of range")
+}
+
+// This finds when the sun crosses the given altitude on the given date (where the date is the local one at the given longitude)
+fn get_sun_crossing(date: NaiveDate, latitude: f64, longitude: f64, altitude_degrees: f64) -> SunCrossing {
+	let j2000_date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+	let days_since_j2000 = date.signed_duration_since(j2000_date).num_days() as f64;
+
+	// This is the mean solar noon, as days since J2000
+	let mean_solar_noon = days_since_j2000 - longitude / 360.0;
+
+	let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_noon).rem_euclid(360.0).to_radians();
+
+	let equation_of_center = 1.9148 * mean_anomaly.sin()
+		+ 0.02 * (2.0 * mean_anomaly).sin()
+		+ 0.0003 * (3.0 * mean_anomaly).sin();
+
+	let ecliptic_longitude = (mean_anomaly.to_degrees() + equation_of_center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+
+	let solar_transit = J2000_JULIAN_DAY + mean_solar_noon
+		+ 0.0053 * mean_anomaly.sin()
+		- 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+	let sin_declination = ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin();
+	let cos_declination = sin_declination.asin().cos();
+	let latitude = latitude.to_radians();
+
+	let cos_hour_angle = (altitude_degrees.to_radians().sin() - latitude.sin() * sin_declination)
+		/ (latitude.cos() * cos_declination);
+
+	if cos_hour_angle > 1.0 {return SunCrossing::AlwaysBelow;}
+	if cos_hour_angle < -1.0 {return SunCrossing::AlwaysAbove;}
+
+	let hour_angle_days = cos_hour_angle.acos().to_degrees() / 360.0;
+
+	SunCrossing::RisesAndSets {
+		rise: from_julian_day(solar_transit - hour_angle_days),
+		set: from_julian_day(solar_transit + hour_angle_days)
+	}
+}
+
+pub fn get_sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+	SunTimes {
+		daylight: get_sun_crossing(date, latitude, longitude, SUNRISE_ALTITUDE_DEGREES),
+		civil_twilight: get_sun_crossing(date, latitude, longitude, CIVIL_TWILIGHT_ALTITUDE_DEGREES)
+	}
+}
+
+pub fn get_moon_phase(time: DateTime<Utc>) -> MoonPhase {
+	let lunar_months = (to_julian_day(time) - REFERENCE_NEW_MOON_JULIAN_DAY) / SYNODIC_MONTH_DAYS;
+	MoonPhase {fraction: lunar_months.rem_euclid(1.0)}
+}
+
+//////////
+
+impl MoonPhase {
+	const NAMES_AND_EMOJIS: [(&'static str, &'static str); 8] = [
+		("New moon", "🌑"),
+		("Waxing crescent", "🌒"),
+		("First quarter", "🌓"),
+		("Waxing gibbous", "🌔"),
+		("Full moon", "🌕"),
+		("Waning gibbous", "🌖"),
+		("Last quarter", "🌗"),
+		("Waning crescent", "🌘")
+	];
+
+	// This is the fraction of the moon's face that's lit
+	pub fn get_illumination(&self) -> f64 {
+		(1.0 - (self.fraction * TAU).cos()) / 2.0
+	}
+
+	fn get_name_and_emoji(&self) -> (&'static str, &'static str) {
+		let num_phases = Self::NAMES_AND_EMOJIS.len();
+		let index = (self.fraction * num_phases as f64).round() as usize % num_phases;
+		Self::NAMES_AND_EMOJIS[index]
+	}
+
+	pub fn get_name(&self) -> &'static str {
+		self.get_name_and_emoji().0
+	}
+
+	pub fn get_emoji(&self) -> &'static str {
+		self.get_name_and_emoji().1
+	}
+}
+
+impl AstronomyState {
+	pub fn new(config: &AstronomyConfig) -> Self {
+		let date = chrono::Local::now().date_naive();
+
+		Self {
+			config: *config,
+			date,
+			sun_times: get_sun_times(date, config.latitude, config.longitude),
+			moon_phase: get_moon_phase(Utc::now())
+		}
+	}
+
+	pub fn update(&mut self) {
+		let date = chrono::Local::now().date_naive();
+
+		if date != self.date {
+			self.date = date;
+			self.sun_times = get_sun_times(date, self.config.latitude, self.config.longitude);
+		}
+
+		self.moon_phase = get_moon_phase(Utc::now());
+	}
+
+	fn make_text(&self) -> String {
+		let format_time = |time: DateTime<Utc>| time.with_timezone(&chrono::Local).format("%-I:%M %p").to_string();
+
+		let daylight_text = match self.sun_times.daylight {
+			SunCrossing::RisesAndSets {rise, set} => format!("🌅 {}  🌇 {}", format_time(rise), format_time(set)),
+			SunCrossing::AlwaysAbove => "☀️ Midnight sun".to_string(),
+			SunCrossing::AlwaysBelow => "🌃 Polar night".to_string()
+		};
+
+		let twilight_text = match self.sun_times.civil_twilight {
+			SunCrossing::RisesAndSets {rise, set} => format!("Twilight {}–{}", format_time(rise), format_time(set)),
+			SunCrossing::AlwaysAbove => "Twilight all night".to_string(),
+			SunCrossing::AlwaysBelow => "No twilight".to_string()
+		};
+
+		let moon_phase = &self.moon_phase;
+
+		format!("{daylight_text}  ·  {twilight_text}  ·  {} {} ({:.0}%)",
+			moon_phase.get_emoji(), moon_phase.get_name(), moon_phase.get_illumination() * 100.0)
+	}
+}
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for AstronomyWindowState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let inner_shared_state = updater_params.shared_window_state.get::<SharedWindowState>();
+		let maybe_text = inner_shared_state.maybe_astronomy_state.as_ref().map(AstronomyState::make_text);
+
+		let wrapped_individual_state = updater_params.window.get_state_mut
+			::<updatable_text_pattern::UpdatableTextWindowFields<AstronomyWindowState>>();
+
+		let astronomy_window_state = &mut wrapped_individual_state.inner;
+
+		let Some(text) = maybe_text else {
+			astronomy_window_state.text.clear();
+			updater_params.window.set_draw_skipping(true);
+			return true;
+		};
+
+		if text == astronomy_window_state.text {
+			return true;
+		}
+
+		astronomy_window_state.text = text;
+		updater_params.window.set_draw_skipping(false);
+		false
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), "")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Borrowed(&self.text)
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The astronomy window contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+//////////
+
+// The location is in the app config, since the sun and moon data is shared by the whole dashboard
+pub fn make_astronomy_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
+	text_color: ColorSDL, maybe_background_color: Option<ColorSDL>) -> Window {
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: AstronomyWindowState {text: String::new()},
+		text_color,
+		scroll_fn: |_, _| (0.0, false),
+		update_rate,
+		maybe_border_color: None
+	};
+
+	let background = maybe_background_color.map_or(WindowContents::Nothing, WindowContents::Color);
+
+	let mut window = updatable_text_pattern::make_window(
+		fields, top_left, size,
+		WindowContents::Many(vec![background, WindowContents::Nothing])
+	);
+
+	window.set_draw_skipping(true);
+	window
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The expected values are from almanac tables (in UTC, so that the tests don't depend on the local time zone)
+	const MAX_SUN_TIME_ERROR_MINUTES: i64 = 2;
+	const MAX_MOON_PHASE_ERROR: f64 = 0.03;
+
+	fn utc(time: &str) -> DateTime<Utc> {
+		DateTime::parse_from_rfc3339(&format!("{time}:00+00:00")).unwrap().with_timezone(&Utc)
+	}
+
+	fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+		NaiveDate::from_ymd_opt(year, month, day).unwrap()
+	}
+
+	fn assert_crossing_near(crossing: SunCrossing, expected_rise: &str, expected_set: &str) {
+		let SunCrossing::RisesAndSets {rise, set} = crossing else {
+			panic!("Expected the sun to rise and set, but got {crossing:?}");
+		};
+
+		for (actual, expected) in [(rise, utc(expected_rise)), (set, utc(expected_set))] {
+			let error_minutes = (actual - expected).num_minutes().abs();
+			assert!(error_minutes <= MAX_SUN_TIME_ERROR_MINUTES, "Expected around {expected}, but got {actual}");
+		}
+	}
+
+	fn assert_moon_phase_near(time: &str, expected_fraction: f64) {
+		let fraction = get_moon_phase(utc(time)).fraction;
+
+		// The fraction wraps around at the new moon
+		let raw_error = (fraction - expected_fraction).abs();
+		let error = raw_error.min(1.0 - raw_error);
+
+		assert!(error <= MAX_MOON_PHASE_ERROR, "Expected a moon phase of around {expected_fraction} at {time}, but got {fraction}");
+	}
+
+	#[test]
+	fn summer_solstice_in_boston() {
+		let sun_times = get_sun_times(date(2024, 6, 21), 42.3601, -71.0589);
+		assert_crossing_near(sun_times.daylight, "2024-06-21T09:07", "2024-06-22T00:25");
+		assert_crossing_near(sun_times.civil_twilight, "2024-06-21T08:32", "2024-06-22T01:00");
+	}
+
+	#[test]
+	fn winter_solstice_in_london() {
+		let sun_times = get_sun_times(date(2024, 12, 21), 51.5074, -0.1278);
+		assert_crossing_near(sun_times.daylight, "2024-12-21T08:03", "2024-12-21T15:53");
+		assert_crossing_near(sun_times.civil_twilight, "2024-12-21T07:23", "2024-12-21T16:34");
+	}
+
+	#[test]
+	fn winter_solstice_in_sydney() {
+		// Sydney is east of Greenwich, so its sunrise is on the previous UTC day
+		let sun_times = get_sun_times(date(2024, 6, 21), -33.8688, 151.2093);
+		assert_crossing_near(sun_times.daylight, "2024-06-20T21:00", "2024-06-21T06:53");
+	}
+
+	#[test]
+	fn polar_day_and_night_in_tromso() {
+		let (latitude, longitude) = (69.6492, 18.9553);
+
+		let summer = get_sun_times(date(2024, 6, 21), latitude, longitude);
+		assert_eq!(summer.daylight, SunCrossing::AlwaysAbove);
+		assert_eq!(summer.civil_twilight, SunCrossing::AlwaysAbove);
+
+		// The sun stays below the horizon, but not far enough below it to skip civil twilight
+		let winter = get_sun_times(date(2024, 12, 21), latitude, longitude);
+		assert_eq!(winter.daylight, SunCrossing::AlwaysBelow);
+		assert!(matches!(winter.civil_twilight, SunCrossing::RisesAndSets {..}));
+	}
+
+	#[test]
+	fn moon_phases() {
+		assert_moon_phase_near("2024-01-11T11:57", 0.0); // New moon
+		assert_moon_phase_near("2024-06-14T05:18", 0.25); // First quarter
+		assert_moon_phase_near("2024-04-23T23:49", 0.5); // Full moon
+		assert_moon_phase_near("2024-12-30T22:27", 0.0); // New moon
+		assert_moon_phase_near("2025-03-14T06:55", 0.5); // Full moon
+	}
+
+	#[test]
+	fn moon_phase_names() {
+		let phase = |fraction| MoonPhase {fraction};
+
+		assert_eq!(phase(0.01).get_name(), "New moon");
+		assert_eq!(phase(0.99).get_name(), "New moon");
+		assert_eq!(phase(0.25).get_name(), "First quarter");
+		assert_eq!(phase(0.5).get_emoji(), "🌕");
+		assert_eq!(phase(0.9).get_name(), "Waning crescent");
+
+		assert!(phase(0.0).get_illumination() < 0.01);
+		assert!((phase(0.5).get_illumination() - 1.0).abs() < 0.01);
+		assert!((phase(0.25).get_illumination() - 0.5).abs() < 0.01);
+	}
+}
@@ -4,6 +4,7 @@ use crate::{
 	window_tree::{
 		Window,
 		ColorSDL,
+		FrameTime,
 		GeneralLine,
 		WindowContents,
 		WindowUpdaterParams
@@ -14,12 +15,10 @@ use crate::{
 		generic_result::*,
 		update_rate::UpdateRate,
 		dynamic_optional::DynamicOptional
-	},
-
-	dashboard_defs::shared_window_state::SharedWindowState
+	}
 };
 
-use chrono::{Local, Timelike};
+use chrono::Timelike;
 
 // This is called raw because it's centered at (0, 0) and is unrotated.
 type RawClockHand = GeneralLine<(f32, f32)>;
@@ -65,7 +64,7 @@ pub struct ClockHandConfigs {
 	pub hours: ClockHandConfig
 }
 
-pub struct ClockHands {
+struct ClockHands {
 	milliseconds: RawClockHand,
 	seconds: RawClockHand,
 	minutes: RawClockHand,
@@ -73,98 +72,141 @@ pub struct ClockHands {
 }
 
 impl ClockHands {
-	pub fn new_with_window(
-		update_rate: UpdateRate,
-		top_left: Vec2f,
-		size: Vec2f,
-		hand_configs: ClockHandConfigs,
-		dial_texture_path: &str,
-		texture_pool: &mut TexturePool) -> GenericResult<(Self, Window)> {
+	fn as_list(&self) -> [&RawClockHand; NUM_CLOCK_HANDS] {
+		[&self.milliseconds, &self.seconds, &self.minutes, &self.hours]
+	}
+}
 
-		fn updater_fn(params: WindowUpdaterParams) -> MaybeError {
-			let curr_time = Local::now();
+//////////
 
-			let time_units: [(u32, u32); NUM_CLOCK_HANDS] = [
-				(curr_time.timestamp_subsec_millis(), 1000),
-				(curr_time.second(), 60),
-				(curr_time.minute(), 60),
-				(curr_time.hour() % 12, 12)
-			];
+/* This returns the top of the hour that the given time is near (if it's at most `before` ahead of
+it, or at most `after` past it). For example, with 5 minutes on each side, both 9:57 and 10:04 are near 10:00. */
+pub fn get_nearby_top_of_hour(curr_time: FrameTime, before: chrono::Duration, after: chrono::Duration) -> Option<FrameTime> {
+	let prev_top_of_hour = curr_time.with_minute(0)?.with_second(0)?.with_nanosecond(0)?;
+	let next_top_of_hour = prev_top_of_hour + chrono::Duration::hours(1);
 
-			let inner_shared_state = params.shared_window_state.get::<SharedWindowState>();
-			let clock_hands = &inner_shared_state.clock_hands;
+	if curr_time - prev_top_of_hour <= after {Some(prev_top_of_hour)}
+	else if next_top_of_hour - curr_time <= before {Some(next_top_of_hour)}
+	else {None}
+}
 
-			let clock_hands_as_list: [&RawClockHand; NUM_CLOCK_HANDS] = [
-				&clock_hands.milliseconds, &clock_hands.seconds, &clock_hands.minutes, &clock_hands.hours
-			];
+// The clock hands are kept in the window's state, so that multiple clocks can have different hands
+pub fn make_clock_window(
+	update_rate: UpdateRate,
+	top_left: Vec2f,
+	size: Vec2f,
+	hand_configs: ClockHandConfigs,
+	dial_texture_path: &str,
+	texture_pool: &mut TexturePool) -> GenericResult<Window> {
+
+	fn updater_fn(params: WindowUpdaterParams) -> MaybeError {
+		let curr_time = params.curr_time;
+
+		let time_units: [(u32, u32); NUM_CLOCK_HANDS] = [
+			(curr_time.timestamp_subsec_millis(), 1000),
+			(curr_time.second(), 60),
+			(curr_time.minute(), 60),
+			(curr_time.hour() % 12, 12)
+		];
 
-			//////////
+		let (clock_hands, window_contents) = params.window.get_state_and_contents_mut::<ClockHands>();
 
-			let WindowContents::Many(all_contents) = params.window.get_contents_mut()
-			else {panic!("The clock's window contents was expected to be a list!")};
+		//////////
 
-			let WindowContents::Lines(rotated_hands) = &mut all_contents[1]
-			else {panic!("The second item in the clock's window contents was not a set of lines!")};
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The clock's window contents was expected to be a list!")};
 
-			//////////
+		let WindowContents::Lines(rotated_hands) = &mut all_contents[1]
+		else {panic!("The second item in the clock's window contents was not a set of lines!")};
 
-			let mut prev_time_fract = 0.0;
+		//////////
 
-			for ((raw_hand, rotated_hand), time_unit) in
-				clock_hands_as_list.into_iter().zip(rotated_hands.iter_mut().rev()).zip(time_units) {
+		let mut prev_time_fract = 0.0;
 
-				let time_fract = (time_unit.0 as f32 + prev_time_fract) / time_unit.1 as f32;
-				prev_time_fract = time_fract;
+		for ((raw_hand, rotated_hand), time_unit) in
+			clock_hands.as_list().into_iter().zip(rotated_hands.iter_mut().rev()).zip(time_units) {
 
-				let angle = time_fract * std::f32::consts::TAU;
-				let (cos_angle, sin_angle) = (angle.cos(), angle.sin());
+			let time_fract = (time_unit.0 as f32 + prev_time_fract) / time_unit.1 as f32;
+			prev_time_fract = time_fract;
 
-				rotated_hand.1.iter_mut().zip(&raw_hand.1).for_each(|(dest, raw)| {
-					*dest = Vec2f::new(
-						(raw.0 * cos_angle - raw.1 * sin_angle) + CLOCK_CENTER.0,
-						(raw.0 * sin_angle + raw.1 * cos_angle) + CLOCK_CENTER.1
-					);
-				});
-			}
+			let angle = time_fract * std::f32::consts::TAU;
+			let (cos_angle, sin_angle) = (angle.cos(), angle.sin());
 
-			Ok(())
+			rotated_hand.1.iter_mut().zip(&raw_hand.1).for_each(|(dest, raw)| {
+				*dest = Vec2f::new(
+					(raw.0 * cos_angle - raw.1 * sin_angle) + CLOCK_CENTER.0,
+					(raw.0 * sin_angle + raw.1 * cos_angle) + CLOCK_CENTER.1
+				);
+			});
 		}
 
-		//////////
+		Ok(())
+	}
 
-		let texture_contents = WindowContents::make_texture_contents(dial_texture_path, texture_pool)?;
+	//////////
 
-		let clock_hand_configs_as_list: [&ClockHandConfig; NUM_CLOCK_HANDS] = [
-			&hand_configs.milliseconds, &hand_configs.seconds,
-			&hand_configs.minutes, &hand_configs.hours
-		];
+	let texture_contents = WindowContents::make_texture_contents(dial_texture_path, texture_pool)?;
+
+	let clock_hand_configs_as_list: [&ClockHandConfig; NUM_CLOCK_HANDS] = [
+		&hand_configs.milliseconds, &hand_configs.seconds,
+		&hand_configs.minutes, &hand_configs.hours
+	];
+
+	let raw_clock_hands = clock_hand_configs_as_list.map(|config| config.make_geometry());
+
+	let line_contents = WindowContents::Lines(
+		raw_clock_hands.iter().rev().map(|(color, clock_hand)| {
+			(*color, vec![Vec2f::ZERO; clock_hand.len()])
+		}).collect());
+
+	let [milliseconds, seconds, minutes, hours] = raw_clock_hands;
+
+	Ok(Window::new(
+		Some((updater_fn, update_rate)),
+		DynamicOptional::new(ClockHands {milliseconds, seconds, minutes, hours}),
+		WindowContents::Many(vec![texture_contents, line_contents]),
+		None,
+		top_left,
+		size,
+		None
+	))
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::snapshot_testing::assert_matches_golden_image;
+
+	#[test]
+	fn nearby_top_of_hour() {
+		let at = |time: &str| chrono::DateTime::parse_from_rfc3339(&format!("2024-03-15T{time}+00:00")).unwrap();
+		let minutes = chrono::Duration::minutes;
+
+		assert_eq!(get_nearby_top_of_hour(at("09:57:00"), minutes(5), minutes(5)), Some(at("10:00:00")));
+		assert_eq!(get_nearby_top_of_hour(at("10:04:59"), minutes(5), minutes(5)), Some(at("10:00:00")));
+		assert_eq!(get_nearby_top_of_hour(at("10:05:01"), minutes(5), minutes(5)), None);
+		assert_eq!(get_nearby_top_of_hour(at("09:54:59"), minutes(5), minutes(5)), None);
+		assert_eq!(get_nearby_top_of_hour(at("23:58:00"), minutes(3), minutes(0)), Some(at("00:00:00") + chrono::Duration::days(1)));
+	}
 
-		let raw_clock_hands = clock_hand_configs_as_list.map(|config| config.make_geometry());
-
-		let line_contents = WindowContents::Lines(
-			raw_clock_hands.iter().rev().map(|(color, clock_hand)| {
-				(*color, vec![Vec2f::ZERO; clock_hand.len()])
-			}).collect());
-
-		let clock_window = Window::new(
-			Some((updater_fn, update_rate)),
-			DynamicOptional::NONE,
-			WindowContents::Many(vec![texture_contents, line_contents]),
-			None,
-			top_left,
-			size,
-			None
-		);
-
-		Ok((
-			ClockHands {
-				milliseconds: raw_clock_hands[0].clone(),
-				seconds: raw_clock_hands[1].clone(),
-				minutes: raw_clock_hands[2].clone(),
-				hours: raw_clock_hands[3].clone()
-			},
-
-			clock_window
-		))
+	#[test]
+	fn clock_hands_at_frozen_time() {
+		assert_matches_golden_image("clock_hands", (200, 200), |texture_pool| {
+			let hand_configs = ClockHandConfigs {
+				milliseconds: ClockHandConfig::new(0.01, 0.2, 0.5, ColorSDL::RGBA(255, 0, 0, 100)),
+				seconds: ClockHandConfig::new(0.01, 0.02, 0.48, ColorSDL::RGB(255, 255, 255)),
+				minutes: ClockHandConfig::new(0.01, 0.02, 0.35, ColorSDL::RGB(255, 255, 0)),
+				hours: ClockHandConfig::new(0.01, 0.02, 0.2, ColorSDL::RGB(0, 0, 0))
+			};
+
+			let window = make_clock_window(
+				UpdateRate::ONCE_PER_FRAME, Vec2f::ZERO, Vec2f::ONE,
+				hand_configs, "assets/watch_dial.png", texture_pool
+			)?;
+
+			Ok((window, DynamicOptional::NONE))
+		});
 	}
 }
@@ -20,9 +20,9 @@ use crate::{
 };
 
 pub fn make_credit_window(top_left: Vec2f, size: Vec2f,
-	border_color: ColorSDL, text_color: ColorSDL, text: &'static str) -> Window {
+	border_color: ColorSDL, text_color: ColorSDL, text: &str) -> Window {
 
-	type CreditWindowState = &'static str;
+	type CreditWindowState = String;
 
 	impl updatable_text_pattern::UpdatableTextWindowMethods for CreditWindowState {
 		fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
@@ -46,7 +46,7 @@ pub fn make_credit_window(top_left: Vec2f, size: Vec2f,
 	}
 
 	let fields = updatable_text_pattern::UpdatableTextWindowFields {
-		inner: text,
+		inner: text.to_string(),
 		text_color,
 		scroll_fn: |seed, _| ((seed * 5.0).sin() * 0.5 + 0.5, false),
 		update_rate: UpdateRate::ALMOST_NEVER,
@@ -1,47 +1,55 @@
 use std::borrow::Cow;
 
 use chrono::Duration;
-use sdl2::{render::BlendMode, ttf::{FontStyle, Hinting}};
+use sdl2::ttf::{FontStyle, Hinting};
 
 use crate::{
 	texture::{FontInfo, TextureCreationInfo, TexturePool},
-	spinitron::{model::SpinitronModelName, state::SpinitronState},
+
+	spinitron::{
+		cache,
+		spin_log,
+		now_playing,
+		mock_server,
+		state::SpinitronState,
+		api::{self, ApiAccess}
+	},
 
 	utility_types::{
 		json_utils,
-		vec2f::Vec2f,
 		generic_result::*,
-		dynamic_optional::DynamicOptional,
-		update_rate::{UpdateRate, UpdateRateCreator}
+		update_rate::UpdateRateCreator,
+		dynamic_optional::DynamicOptional
 	},
 
 	window_tree::{
-		ColorSDL,
 		Window,
-		WindowContents,
 		PossibleSharedWindowStateUpdater
 	},
 
 	dashboard_defs::{
-		error::make_error_window,
-		credit::make_credit_window,
-		weather::make_weather_window,
+		twilio::{TwilioState, TwilioFeatureConfigs},
+		twilio_moderation::TwilioModerationConfig,
+		twilio_cleanup::TwilioCleanupConfig,
+		twilio_replies::TwilioReplyConfig,
+		twilio_webhook::TwilioWebhookConfig,
+		weather::{WeatherState, WeatherConfig},
+		weather_mock_server::{self, WeatherMockServerConfig},
+		astronomy::{AstronomyState, AstronomyConfig},
+		explicit_content::{MAX_NUM_QUEUED_SPINS, ExplicitContentGuard, ExplicitContentGuardConfig},
+		spin_history::MAX_NUM_COVERS,
+		show_schedule::MAX_NUM_ROWS,
 		shared_window_state::SharedWindowState,
-		twilio::{make_twilio_window, TwilioState},
-		surprise::{make_surprise_window, SurpriseCreationInfo},
-		clock::{ClockHandConfig, ClockHandConfigs, ClockHands},
-		spinitron::{make_spinitron_windows, SpinitronModelWindowInfo, SpinitronModelWindowsInfo}
+		layout::{build_window, LayoutBuildContext, WindowLayout}
 	}
 };
 
-////////// TODO: maybe split `make_dashboard` into some smaller sub-functions
-
 /* TODO:
 - Rename all `Possible` types to `Maybe`s (incl. the associated variable names) (and all `inner-prefixed` vars too)
 - Make plain texture creation less verbose through a wrapper function
 */
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, Default)]
 struct ApiKeys {
 	spinitron: String,
 	openweathermap: String,
@@ -49,353 +57,121 @@ struct ApiKeys {
 	twilio_auth_token: String
 }
 
-//////////
-
-// This returns a top-level window, shared window state, and a shared window state updater
-pub fn make_dashboard(
-	texture_pool: &mut TexturePool,
-	update_rate_creator: UpdateRateCreator)
-	-> GenericResult<(Window, DynamicOptional, PossibleSharedWindowStateUpdater)> {
-
-	////////// Defining some shared global variables
-
-	const FONT_INFO: FontInfo = FontInfo {
-		path: "assets/unifont/unifont-15.1.05.otf",
-		unusual_chars_fallback_path: "assets/unifont/unifont_upper-15.1.05.otf",
-
-		/* Providing this function instead of the variant below since
-		`font.find_glyph` is buggy for the Rust sdl2::ttf bindings */
-		font_has_char: |_, c| c as u32 <= 65535,
-		// font_has_char: |font, c| font.find_glyph(c).is_some(),
+/* This is the dashboard-specific part of the app config (it's
+flattened into it, so these fields sit at the top level of it). */
+#[derive(serde::Deserialize)]
+pub struct DashboardConfig {
+	pub layout_path: String,
 
-		style: FontStyle::NORMAL,
-		hinting: Hinting::Normal,
-		maybe_outline_width: None
-	};
+	#[serde(default = "default_spinitron_api_base_url")]
+	spinitron_api_base_url: String,
 
-	let top_bar_window_size_y = 0.1;
-	let main_windows_gap_size = 0.01;
+	/* If this is set, a local mock Spinitron API is run, and it's used instead of the base URL above.
+	It looks like `{"address": "127.0.0.1:8135", "fixture_dir": "assets/spinitron_fixtures/typical"}`. */
+	maybe_spinitron_mock_server: Option<mock_server::MockServerConfig>,
 
-	let theme_color_1 = ColorSDL::RGB(249, 236, 210);
-	let shared_update_rate = update_rate_creator.new_instance(15.0);
-	let api_keys: ApiKeys = json_utils::load_from_file("assets/api_keys.json")?;
+	// If this is set, the last synced Spinitron models are kept on disk, and shown right away after a restart
+	maybe_spinitron_cache: Option<cache::CacheConfig>,
 
-	////////// Defining the Spinitron window extents
+	// If this is set, every new spin is archived to a log file for its day
+	maybe_spin_log: Option<spin_log::SpinLogConfig>,
 
-	// Note: `tl` = top left
-	let spin_tl = Vec2f::new_scalar(main_windows_gap_size);
-	let spin_size = Vec2f::new_scalar(0.55);
-	let spin_text_height = 0.03;
-	let spin_tr = spin_tl.x() + spin_size.x();
+	// If this is set, the now-playing data is written to files, and served over HTTP, for other station systems
+	maybe_now_playing_export: Option<now_playing::NowPlayingExportConfig>,
 
-	let persona_tl = Vec2f::new(spin_tr + main_windows_gap_size, spin_tl.y());
-	let persona_size = Vec2f::new_scalar(0.1);
+	// If this is set, flagged spins outside of safe harbor hours trigger a warning
+	maybe_explicit_content_guard: Option<ExplicitContentGuardConfig>,
 
-	let persona_text_tl = Vec2f::translate_y(&persona_tl, persona_size.y());
-	let persona_text_height = 0.02;
+	// If this is set, the weather for its location is shown (the API key is in `assets/api_keys.json`)
+	maybe_weather: Option<WeatherConfig>,
 
-	let show_tl = Vec2f::new(persona_tl.x() + persona_size.x() + main_windows_gap_size, spin_tl.y());
-	let show_size = Vec2f::new_scalar(1.0 - show_tl.x() - main_windows_gap_size);
+	/* If this is set, a local mock OpenWeatherMap API is run, and the weather uses it instead of its base URLs.
+	It looks like `{"address": "127.0.0.1:8138", "fixture_dir": "assets/weather_fixtures/typical"}`. */
+	maybe_weather_mock_server: Option<WeatherMockServerConfig>,
 
-	let show_text_tl = Vec2f::translate(&(spin_tl + spin_size), 0.03, -0.2);
-	let show_text_size = Vec2f::new(0.37, 0.05);
+	// If this is set, the sunrise, sunset, and moon phase are computed for its location (with no network access)
+	maybe_astronomy: Option<AstronomyConfig>,
 
-	// TODO: make a type for the top-left/size combo (and add useful utility functions from there)
+	// If this is set, incoming texts are moderated (with a blocklist, a content filter, and a queue for staff to review)
+	maybe_twilio_moderation: Option<TwilioModerationConfig>,
 
-	//////////
+	// If this is set, old texts are deleted from the Twilio message history on a schedule
+	maybe_twilio_cleanup: Option<TwilioCleanupConfig>,
 
-	let all_model_windows_info = [
-		SpinitronModelWindowsInfo {
-			model_name: SpinitronModelName::Spin,
-			text_color: theme_color_1,
-
-			texture_window: Some(SpinitronModelWindowInfo {
-				tl: spin_tl,
-				size: spin_size,
-				border_color: Some(theme_color_1)
-			}),
-
-			text_window: Some(SpinitronModelWindowInfo {
-				tl: Vec2f::translate_y(&spin_tl, spin_size.y()),
-				size: Vec2f::new(spin_size.x(), spin_text_height),
-				border_color: Some(theme_color_1)
-			})
-		},
-
-		SpinitronModelWindowsInfo {
-			model_name: SpinitronModelName::Playlist,
-			text_color: theme_color_1,
-			texture_window: None,
-			text_window: None
-		},
-
-		// Putting show before persona here so that the persona text is drawn over
-		SpinitronModelWindowsInfo {
-			model_name: SpinitronModelName::Show,
-			text_color: theme_color_1,
-
-			texture_window: Some(SpinitronModelWindowInfo {
-				tl: show_tl,
-				size: show_size,
-				border_color: Some(theme_color_1)
-			}),
-
-			text_window: Some(SpinitronModelWindowInfo {
-				tl: show_text_tl,
-				size: show_text_size,
-				border_color: Some(theme_color_1)
-			})
-		},
-
-		SpinitronModelWindowsInfo {
-			model_name: SpinitronModelName::Persona,
-			text_color: theme_color_1,
-
-			texture_window: Some(SpinitronModelWindowInfo {
-				tl: persona_tl,
-				size: persona_size,
-				border_color: Some(theme_color_1)
-			}),
-
-			text_window: Some(SpinitronModelWindowInfo {
-				tl: persona_text_tl,
-				size: Vec2f::new(persona_size.x(), persona_text_height),
-				border_color: Some(theme_color_1)
-			})
-		}
-	];
-
-	// The Spinitron windows update at the same rate as the shared update rate
-	let spinitron_windows = make_spinitron_windows(
-		&all_model_windows_info, shared_update_rate
-	);
+	// If this is set, DJs can reply to texts through a local command socket
+	maybe_twilio_replies: Option<TwilioReplyConfig>,
 
-	////////// Making a Twilio window
-
-	let twilio_state = TwilioState::new(
-		&api_keys.twilio_account_sid,
-		&api_keys.twilio_auth_token,
-		6,
-		Duration::days(5),
-		false
-	);
-
-	let twilio_window = make_twilio_window(
-		&twilio_state,
-
-		// This is how often the history windows check for new messages (this is low so that it'll be fast in the beginning)
-		update_rate_creator.new_instance(0.25),
+	// If this is set, incoming texts are received through Twilio's webhook (and the message list is only polled now and then)
+	maybe_twilio_webhook: Option<TwilioWebhookConfig>
+}
 
-		Vec2f::new(0.58, 0.45), Vec2f::new(0.4, 0.27),
+fn default_spinitron_api_base_url() -> String {
+	api::DEFAULT_BASE_URL.to_string()
+}
 
-		0.025,
-		WindowContents::Color(ColorSDL::RGB(0, 200, 0)),
+//////////
 
-		Vec2f::new(0.1, 0.45),
-		theme_color_1, theme_color_1,
+// This is public so that the snapshot tests can render text in the same way
+pub const FONT_INFO: FontInfo = FontInfo {
+	path: "assets/unifont/unifont-15.1.05.otf",
+	unusual_chars_fallback_path: "assets/unifont/unifont_upper-15.1.05.otf",
 
-		WindowContents::make_texture_contents("assets/text_bubble.png", texture_pool)?
-	);
+	/* Providing this function instead of the variant below since
+	`font.find_glyph` is buggy for the Rust sdl2::ttf bindings */
+	font_has_char: |_, c| c as u32 <= 65535,
+	// font_has_char: |font, c| font.find_glyph(c).is_some(),
 
-	////////// Making an error window
+	style: FontStyle::NORMAL,
+	hinting: Hinting::Normal,
+	maybe_outline_width: None
+};
 
-	let error_window = make_error_window(
-		Vec2f::new(0.0, 0.95),
-		Vec2f::new(0.15, 0.05),
-		update_rate_creator.new_instance(2.0),
-		WindowContents::Color(ColorSDL::RGBA(255, 0, 0, 190)),
-		ColorSDL::GREEN
-	);
+//////////
 
-	////////// Making a credit window
+// This returns a top-level window, shared window state, and a shared window state updater
+pub fn make_dashboard(
+	texture_pool: &mut TexturePool,
+	update_rate_creator: UpdateRateCreator,
+	config: &DashboardConfig)
+	-> GenericResult<(Window, DynamicOptional, PossibleSharedWindowStateUpdater)> {
 
-	let credit_window = make_credit_window(
-		Vec2f::new(0.85, 0.97),
-		Vec2f::new(0.15, 0.03),
-		ColorSDL::RED,
-		ColorSDL::RGB(210, 180, 140),
-		"By: Caspian Ahlberg"
-	);
+	////////// Defining some shared global variables
 
-	////////// Making a clock window
+	let shared_update_rate = update_rate_creator.new_instance(15.0);
 
-	let clock_size_x = top_bar_window_size_y;
-	let clock_tl = Vec2f::new(1.0 - clock_size_x, 0.0);
-	let clock_size = Vec2f::new(clock_size_x, 1.0);
+	/* Without the API keys, the dashboard still starts up (everything that needs them keeps
+	failing to connect, which shows up in the error window), so that the screen is never blank. */
+	let api_keys: ApiKeys = json_utils::load_from_file("assets/api_keys.json").unwrap_or_else(|err| {
+		log::error!("Could not load the API keys, so continuing without them: {err:#}");
+		ApiKeys::default()
+	});
 
-	let (clock_hands, clock_window) = ClockHands::new_with_window(
-		UpdateRate::ONCE_PER_FRAME,
-		clock_tl,
-		clock_size,
+	let layout: WindowLayout = json_utils::load_from_file(&config.layout_path)?;
 
-		ClockHandConfigs {
-			milliseconds: ClockHandConfig::new(0.01, 0.2, 0.5, ColorSDL::RGBA(255, 0, 0, 100)), // Milliseconds
-			seconds: ClockHandConfig::new(0.01, 0.02, 0.48, ColorSDL::WHITE), // Seconds
-			minutes: ClockHandConfig::new(0.01, 0.02, 0.35, ColorSDL::YELLOW), // Minutes
-			hours: ClockHandConfig::new(0.01, 0.02, 0.2, ColorSDL::BLACK) // Hours
-		},
+	////////// Making the Twilio state (the Twilio window needs it when being built)
 
-		"assets/watch_dial.png",
-		texture_pool
+	let twilio_state = TwilioState::new(
+		&api_keys.twilio_account_sid,
+		&api_keys.twilio_auth_token,
+		6,
+		Duration::days(5),
+		false,
+		TwilioFeatureConfigs {
+			maybe_moderation: config.maybe_twilio_moderation.as_ref(),
+			maybe_cleanup: config.maybe_twilio_cleanup.as_ref(),
+			maybe_replies: config.maybe_twilio_replies.as_ref(),
+			maybe_webhook: config.maybe_twilio_webhook.as_ref()
+		}
 	)?;
 
-	////////// Making a weather window
-
-	let weather_window = make_weather_window(
-		Vec2f::ZERO,
-		Vec2f::new(0.4, 0.3),
-		update_rate_creator,
-		&api_keys.openweathermap,
-		"Brunswick",
-		"ME",
-		"US"
-	);
-
-	////////// Making some static texture windows
-
-	// Texture path, top left, size (TODO: make animated textures possible)
-	let main_static_texture_info = [
-		("assets/dashboard_bookshelf.png", Vec2f::ZERO, Vec2f::ONE, false),
-		("assets/logo.png", Vec2f::new(0.6, 0.75), Vec2f::new(0.1, 0.05), false),
-		("assets/soup.png", Vec2f::new(0.45, 0.72), Vec2f::new(0.06666666, 0.1), false),
-		("assets/ness.bmp", Vec2f::new(0.28, 0.73), Vec2f::new_scalar(0.08), false)
-	];
-
-	let foreground_static_texture_info = [
-		("assets/dashboard_foreground.png", Vec2f::ZERO, Vec2f::ONE, true)
-	];
-
-	let background_static_texture_info = [
-		// "assets/dashboard_background.png"
-	];
-
-	let add_static_texture_set =
-		|set: &mut Vec<Window>, all_info: &[(&'static str, Vec2f, Vec2f, bool)], texture_pool: &mut TexturePool| {
-
-		set.extend(all_info.iter().map(|&(path, tl, size, skip_ar_correction)| {
-			let mut window = Window::new(
-				None,
-				DynamicOptional::NONE,
-				WindowContents::make_texture_contents(path, texture_pool).unwrap(),
-				None,
-				tl,
-				size,
-				None
-			);
-
-			window.set_aspect_ratio_correction_skipping(skip_ar_correction);
-			window
-		}))
-	};
-
-	let mut all_main_windows = vec![twilio_window, error_window, credit_window];
-	all_main_windows.extend(spinitron_windows);
-	add_static_texture_set(&mut all_main_windows, &main_static_texture_info, texture_pool);
-
-	////////// Making all of the main windows
-
-	let main_window_tl_y = main_windows_gap_size + top_bar_window_size_y + main_windows_gap_size;
-	let main_window_size_y = 1.0 - main_window_tl_y - main_windows_gap_size;
-	let x_width_from_main_window_gap_size = 1.0 - main_windows_gap_size * 2.0;
-
-	let top_bar_tl = Vec2f::new_scalar(main_windows_gap_size);
-
-	let top_bar_window = Window::new(
-		None,
-		DynamicOptional::NONE,
-		WindowContents::Color(ColorSDL::RGB(128, 0, 32)),
-		None,
-		top_bar_tl,
-		Vec2f::new(x_width_from_main_window_gap_size, top_bar_window_size_y),
-		Some(vec![clock_window, weather_window])
-	);
-
-	let mut main_window = Window::new(
-		None,
-		DynamicOptional::NONE,
-
-		WindowContents::Many(
-			background_static_texture_info.into_iter().map(|path|
-				WindowContents::make_texture_contents(path, texture_pool)
-			).collect::<GenericResult<_>>()?
-		),
-
-		Some(theme_color_1),
-		Vec2f::new(main_windows_gap_size, main_window_tl_y),
-		Vec2f::new(x_width_from_main_window_gap_size, main_window_size_y),
-		Some(all_main_windows)
-	);
-
-	main_window.set_aspect_ratio_correction_skipping(true);
-
-	////////// Making a surprise window
-
-	let surprise_window = make_surprise_window(
-		Vec2f::ZERO, Vec2f::ONE, "/tmp/surprises_wbor_studio_dashboard.sock",
-
-		&[
-			SurpriseCreationInfo {
-				texture_path: "assets/nathan.png",
-				texture_blend_mode: BlendMode::None,
-
-				update_rate: Duration::seconds(15),
-				num_update_steps_to_appear_for: 1,
-				chance_of_appearing_when_updating: 0.0007,
-
-				local_hours_24_start: 8,
-				local_hours_24_end: 22,
-
-				flicker_window: false
-			},
-
-			SurpriseCreationInfo {
-				texture_path: "assets/jumpscare.png",
-				texture_blend_mode: BlendMode::Add,
-
-				update_rate: Duration::milliseconds(35),
-				num_update_steps_to_appear_for: 20,
-				chance_of_appearing_when_updating: 0.000003,
-
-				local_hours_24_start: 0,
-				local_hours_24_end: 5,
-
-				flicker_window: true
-			},
-
-			SurpriseCreationInfo {
-				texture_path: "assets/horrible.webp",
-				texture_blend_mode: BlendMode::Add,
-
-				update_rate: Duration::milliseconds(100),
-				num_update_steps_to_appear_for: 9,
-				chance_of_appearing_when_updating: 0.0, // This one can only be triggered artificially
-
-				local_hours_24_start: 0,
-				local_hours_24_end: 23,
-
-				flicker_window: true
-			}
-		],
+	////////// Building the window tree from the layout
 
+	let all_windows_window = build_window(&layout, &mut LayoutBuildContext {
+		texture_pool,
 		update_rate_creator,
-		texture_pool
-	)?;
-
-	////////// Making the highest-level window
-
-	let mut all_windows = vec![top_bar_window, main_window];
-	add_static_texture_set(&mut all_windows, &foreground_static_texture_info, texture_pool);
-	all_windows.push(surprise_window);
-
-	let all_windows_window = Window::new(
-		None,
-		DynamicOptional::NONE,
-		WindowContents::Nothing,
-		None,
-		Vec2f::ZERO,
-		Vec2f::ONE,
-		Some(all_windows)
-	);
+		shared_update_rate,
+		twilio_state: &twilio_state
+	})?;
 
 	////////// Defining the shared state
 
@@ -405,21 +181,53 @@ pub fn make_dashboard(
 
 	let initial_spin_window_size_guess = (1000, 1000);
 	let spin_expiry_duration = Duration::minutes(20);
+	let spin_history_length = MAX_NUM_COVERS;
+	let max_num_upcoming_shows = MAX_NUM_ROWS;
+	let max_num_queued_spins = if config.maybe_explicit_content_guard.is_some() {MAX_NUM_QUEUED_SPINS} else {0};
+
+	let spinitron_base_url = if let Some(mock_server_config) = &config.maybe_spinitron_mock_server {
+		mock_server::start_or_update(mock_server_config)?;
+		mock_server_config.get_base_url()
+	}
+	else {
+		config.spinitron_api_base_url.clone()
+	};
+
+	let spinitron_api_access = ApiAccess {base_url: spinitron_base_url, api_key: api_keys.spinitron};
 
 	let spinitron_state = SpinitronState::new(
-		(&api_keys.spinitron, spin_expiry_duration,
-		&FALLBACK_TEXTURE_CREATION_INFO, initial_spin_window_size_guess)
+		(&spinitron_api_access, spin_expiry_duration,
+		&FALLBACK_TEXTURE_CREATION_INFO, initial_spin_window_size_guess,
+		config.maybe_spinitron_cache.as_ref(), spin_history_length, max_num_upcoming_shows, max_num_queued_spins,
+		config.maybe_spin_log.as_ref(), config.maybe_now_playing_export.as_ref())
 	)?;
 
+	let mut maybe_weather_config = config.maybe_weather.clone();
+
+	if let (Some(weather_config), Some(mock_server_config)) = (&mut maybe_weather_config, &config.maybe_weather_mock_server) {
+		weather_mock_server::start_or_update(mock_server_config)?;
+		weather_config.api_base_url = mock_server_config.get_base_url();
+		weather_config.alerts_api_base_url = mock_server_config.get_base_url();
+	}
+
+	let maybe_weather_state = maybe_weather_config.as_ref().map(|weather_config| WeatherState::new(&api_keys.openweathermap, weather_config));
+	let maybe_astronomy_state = config.maybe_astronomy.as_ref().map(AstronomyState::new);
+	let maybe_explicit_content_guard = config.maybe_explicit_content_guard.as_ref().map(ExplicitContentGuard::new).transpose()?;
+
 	let boxed_shared_state = DynamicOptional::new(
 		SharedWindowState {
-			clock_hands,
 			spinitron_state,
 			twilio_state,
+			maybe_weather_state,
+			maybe_astronomy_state,
 			font_info: &FONT_INFO,
 			fallback_texture_creation_info: &FALLBACK_TEXTURE_CREATION_INFO,
 			curr_dashboard_error: None,
-			rand_generator: rand::thread_rng()
+			config_reload_error: None,
+			maybe_explicit_content_guard,
+			curr_explicit_content_warning: None,
+			rand_generator: rand::thread_rng(),
+			keys_pressed_this_frame: Vec::new()
 		}
 	);
 
@@ -429,9 +237,20 @@ pub fn make_dashboard(
 		let mut error = None;
 
 		// More continual updaters can be added here
+		let weather_succeeded = match &mut state.maybe_weather_state {
+			Some(weather_state) => weather_state.update()?,
+			None => true
+		};
+
+		// This never fails, since it's computed locally
+		if let Some(astronomy_state) = &mut state.maybe_astronomy_state {
+			astronomy_state.update();
+		}
+
 		let success_states_and_names = [
 			(state.spinitron_state.update()?, "Spinitron"),
-			(state.twilio_state.update(texture_pool)?, "Twilio (messaging)")
+			(state.twilio_state.update(texture_pool)?, "Twilio (messaging)"),
+			(weather_succeeded, "Weather")
 		];
 
 		for (succeeded, name) in success_states_and_names {
@@ -450,6 +269,25 @@ pub fn make_dashboard(
 			*inner_error += "!";
 		}
 
+		// A config reload error stays up until the config is fixed (at which point, this state is remade)
+		if let Some(config_reload_error) = &state.config_reload_error {
+			error = Some(match error {
+				Some(inner_error) => format!("{inner_error} {config_reload_error}"),
+				None => config_reload_error.clone()
+			});
+		}
+
+		// An explicit content warning goes before any other error, since it needs to be acted on right away
+		state.curr_explicit_content_warning = state.maybe_explicit_content_guard.as_mut()
+			.and_then(|guard| guard.check(&state.spinitron_state));
+
+		if let Some(warning) = &state.curr_explicit_content_warning {
+			error = Some(match error {
+				Some(inner_error) => format!("{warning} {inner_error}"),
+				None => warning.clone()
+			});
+		}
+
 		state.curr_dashboard_error = error;
 
 		Ok(())
@@ -463,3 +301,18 @@ pub fn make_dashboard(
 		Some((shared_window_state_updater, shared_update_rate))
 	))
 }
+
+pub fn set_keys_pressed_this_frame(shared_window_state: &mut DynamicOptional, keys: &[sdl2::keyboard::Keycode]) {
+	let state = shared_window_state.get_mut::<SharedWindowState>();
+	state.keys_pressed_this_frame.clear();
+	state.keys_pressed_this_frame.extend_from_slice(keys);
+}
+
+// This is for showing an error from a failed config reload (the previous dashboard is kept when that happens)
+pub fn report_config_reload_error(shared_window_state: &mut DynamicOptional, error: &anyhow::Error) {
+	let state = shared_window_state.get_mut::<SharedWindowState>();
+	let config_reload_error = format!("Invalid config edit: {error:#}");
+
+	state.curr_dashboard_error = Some(config_reload_error.clone());
+	state.config_reload_error = Some(config_reload_error);
+}
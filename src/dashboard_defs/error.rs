@@ -19,11 +19,18 @@ use crate::{
 	}
 };
 
+// This picks the message that an error window shows (from the shared window state)
+pub type ErrorMessageSource = for<'a> fn(&'a SharedWindowState<'_>) -> &'a Option<String>;
+
 // TODO: maybe replace this with the SDL message box?
 pub fn make_error_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
-	background_contents: WindowContents, text_color: ColorSDL) -> Window {
+	background_contents: WindowContents, text_color: ColorSDL, message_source: ErrorMessageSource) -> Window {
 
-	type ErrorWindowState = Option<String>; // This is the previous error
+	#[derive(Clone)]
+	struct ErrorWindowState {
+		prev_error: Option<String>,
+		message_source: ErrorMessageSource
+	}
 
 	impl updatable_text_pattern::UpdatableTextWindowMethods for ErrorWindowState {
 		fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
@@ -32,16 +39,16 @@ pub fn make_error_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
 			let wrapped_individual_state = updater_params.window.get_state_mut
 				::<updatable_text_pattern::UpdatableTextWindowFields<ErrorWindowState>>();
 
-			let prev_error = &wrapped_individual_state.inner;
+			let error_state = &mut wrapped_individual_state.inner;
 
 			let (curr_error, cached_error) = (
-				&inner_shared_state.curr_dashboard_error, prev_error
+				(error_state.message_source)(inner_shared_state), &error_state.prev_error
 			);
 
 			// This means that the error changed (or disappeared)!
 			if curr_error != cached_error {
 				let skip_update = curr_error.is_none();
-				wrapped_individual_state.inner.clone_from(curr_error);
+				error_state.prev_error.clone_from(curr_error);
 				updater_params.window.set_draw_skipping(skip_update);
 				skip_update
 			}
@@ -55,7 +62,7 @@ pub fn make_error_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
 		}
 
 		fn extract_text(&self) -> Cow<str> {
-			Cow::Borrowed(self.as_ref().unwrap())
+			Cow::Borrowed(self.prev_error.as_ref().unwrap())
 		}
 
 		fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
@@ -66,7 +73,7 @@ pub fn make_error_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
 	}
 
 	let fields = updatable_text_pattern::UpdatableTextWindowFields {
-		inner: None,
+		inner: ErrorWindowState {prev_error: None, message_source},
 		text_color,
 
 		scroll_fn: |seed, _| {
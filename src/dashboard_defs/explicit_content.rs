@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use chrono::Timelike;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::{
+	utility_types::{
+		json_utils,
+		generic_result::*
+	},
+
+	spinitron::{
+		model::{Spin, SpinitronModel},
+		state::SpinitronState,
+		wrapper_types::SpinitronModelId
+	}
+};
+
+/* This guards against airing flagged material outside of the FCC safe harbor hours (by default, 10 PM to 6 AM).
+The flag list is a local JSON file with `artists`, `songs`, and `releases` arrays, where each entry is a regex
+(matched case-insensitively against the whole field, so plain names work as-is, as long as they have no special characters).
+
+When the current spin (until its end time), or a queued one (which is a spin that was logged ahead of time) matches outside of safe harbor,
+a warning is shown through the dashboard error, and an incident is written to the incident log (one JSON object per line). */
+
+#[derive(Deserialize, Clone)]
+pub struct ExplicitContentGuardConfig {
+	pub flag_list_path: String,
+	pub incident_log_path: String,
+
+	#[serde(default = "default_safe_harbor_start_hour")]
+	pub safe_harbor_start_hour: u32,
+
+	#[serde(default = "default_safe_harbor_end_hour")]
+	pub safe_harbor_end_hour: u32
+}
+
+// This many queued spins are checked (they're only requested from Spinitron when the guard is configured)
+pub const MAX_NUM_QUEUED_SPINS: usize = 5;
+
+const fn default_safe_harbor_start_hour() -> u32 {22}
+const fn default_safe_harbor_end_hour() -> u32 {6}
+
+#[derive(Deserialize)]
+struct FlagList {
+	#[serde(default)]
+	artists: Vec<String>,
+
+	#[serde(default)]
+	songs: Vec<String>,
+
+	#[serde(default)]
+	releases: Vec<String>
+}
+
+// Each field name is paired with its patterns
+type FlagPatterns = [(&'static str, Vec<Regex>); 3];
+
+pub struct ExplicitContentGuard {
+	patterns: FlagPatterns,
+	safe_harbor_hours: (u32, u32),
+	incident_log_path: String,
+
+	// These are the flagged spins that were already logged (so that each incident is only logged once)
+	logged_spin_ids: HashSet<SpinitronModelId>
+}
+
+//////////
+
+fn compile_patterns(field_name: &'static str, patterns: &[String]) -> GenericResult<(&'static str, Vec<Regex>)> {
+	let compiled = patterns.iter().map(|pattern| {
+		RegexBuilder::new(&format!("^(?:{pattern})$"))
+			.case_insensitive(true)
+			.build()
+			.context(format!("Invalid flagged {field_name} pattern '{pattern}'"))
+	}).collect::<GenericResult<_>>()?;
+
+	Ok((field_name, compiled))
+}
+
+impl ExplicitContentGuard {
+	pub fn new(config: &ExplicitContentGuardConfig) -> GenericResult<Self> {
+		let (start_hour, end_hour) = (config.safe_harbor_start_hour, config.safe_harbor_end_hour);
+
+		if start_hour >= 24 || end_hour >= 24 {
+			return error_msg!("The safe harbor hours should be under 24, but they were {start_hour} and {end_hour}");
+		}
+
+		let flag_list: FlagList = json_utils::load_from_file(&config.flag_list_path)
+			.context(format!("Could not load the explicit content flag list at '{}'", config.flag_list_path))?;
+
+		Ok(Self {
+			patterns: [
+				compile_patterns("artist", &flag_list.artists)?,
+				compile_patterns("song", &flag_list.songs)?,
+				compile_patterns("release", &flag_list.releases)?
+			],
+
+			safe_harbor_hours: (start_hour, end_hour),
+			incident_log_path: config.incident_log_path.clone(),
+			logged_spin_ids: HashSet::new()
+		})
+	}
+
+	// The safe harbor wraps around midnight if its start hour is after its end hour
+	fn is_safe_harbor(&self, hour: u32) -> bool {
+		let (start_hour, end_hour) = self.safe_harbor_hours;
+
+		if start_hour <= end_hour {(start_hour..end_hour).contains(&hour)}
+		else {hour >= start_hour || hour < end_hour}
+	}
+
+	// This returns the name of the matched field, and the pattern that matched it
+	fn find_match(&self, spin: &Spin) -> Option<(&'static str, &str)> {
+		self.patterns.iter().find_map(|(field_name, patterns)| {
+			let field = match *field_name {
+				"artist" => spin.get_artist(),
+				"song" => spin.get_song(),
+				_ => spin.get_release()
+			};
+
+			patterns.iter().find(|pattern| pattern.is_match(field)).map(|pattern| (*field_name, pattern.as_str()))
+		})
+	}
+
+	fn write_incident(&self, spin: &Spin, (field_name, pattern): (&str, &str), curr_time: chrono::DateTime<chrono::Local>) {
+		let incident = serde_json::json!({
+			"time": curr_time.to_rfc3339(),
+			"spin_id": spin.get_id(),
+			"artist": spin.get_artist(),
+			"song": spin.get_song(),
+			"release": spin.get_release(),
+			"matched_field": field_name,
+			"matched_pattern": pattern
+		});
+
+		if let Err(err) = json_utils::append_json_lines(&self.incident_log_path, [&incident]) {
+			log::error!("Could not write to the explicit content incident log at '{}': '{err}'.", self.incident_log_path);
+		}
+	}
+
+	/* This returns the flagged spins (along with what they matched), with the current spin first. The current spin is
+only checked until its end time, and the queued ones are only checked if they start later (since the current spin may be one). */
+	fn find_flagged_spins<'a>(&self, maybe_curr_spin: Option<&'a Spin>, queued_spins: &'a [Spin],
+		curr_time: chrono::DateTime<chrono::Local>) -> Vec<(&'a Spin, (&'static str, &str))> {
+
+		let maybe_curr_spin = maybe_curr_spin.filter(|spin| spin.get_end_time().is_ok_and(|end_time| end_time > curr_time));
+		let queued_spins = queued_spins.iter().filter(|spin| spin.get_start_time().is_ok_and(|start_time| start_time > curr_time));
+
+		maybe_curr_spin.into_iter().chain(queued_spins)
+			.filter_map(|spin| self.find_match(spin).map(|matched| (spin, matched)))
+			.collect()
+	}
+
+	// This returns a warning if a flagged spin is (or will soon be) on air outside of safe harbor
+	pub fn check(&mut self, spinitron_state: &SpinitronState) -> Option<String> {
+		let curr_time = chrono::Local::now();
+
+		if self.is_safe_harbor(curr_time.hour()) || spinitron_state.is_connecting() {
+			self.logged_spin_ids.clear();
+			return None;
+		}
+
+		let maybe_curr_spin = (!spinitron_state.spin_is_expired()).then(|| spinitron_state.get_spin());
+		let flagged_spins = self.find_flagged_spins(maybe_curr_spin, spinitron_state.get_queued_spins(), curr_time);
+		let flagged_spin_ids: HashSet<SpinitronModelId> = flagged_spins.iter().map(|(spin, _)| spin.get_id()).collect();
+
+		for (spin, matched) in &flagged_spins {
+			if !self.logged_spin_ids.contains(&spin.get_id()) {
+				log::warn!("Flagged content outside of safe harbor: '{}' by {} (matched the {} pattern '{}').",
+					spin.get_song(), spin.get_artist(), matched.0, matched.1);
+
+				self.write_incident(spin, *matched, curr_time);
+			}
+		}
+
+		let warning = flagged_spins.first().map(|(spin, _)| {
+			let is_queued = spin.get_start_time().is_ok_and(|start_time| start_time > curr_time);
+			let description = if is_queued {"QUEUED"} else {"ON AIR"};
+			format!("FLAGGED CONTENT {description} OUTSIDE SAFE HARBOR: '{}' by {}!", spin.get_song(), spin.get_artist())
+		});
+
+		self.logged_spin_ids = flagged_spin_ids;
+		warning
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn make_guard(artists: &[&str], songs: &[&str], safe_harbor_hours: (u32, u32)) -> ExplicitContentGuard {
+		let to_strings = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>();
+
+		ExplicitContentGuard {
+			patterns: [
+				compile_patterns("artist", &to_strings(artists)).unwrap(),
+				compile_patterns("song", &to_strings(songs)).unwrap(),
+				compile_patterns("release", &[]).unwrap()
+			],
+
+			safe_harbor_hours,
+			incident_log_path: String::new(),
+			logged_spin_ids: HashSet::new()
+		}
+	}
+
+	fn make_spin(artist: &str, song: &str) -> Spin {
+		make_timed_spin(1, artist, song, "2024-03-15T10:00:00+0000")
+	}
+
+	// Each spin is 3 minutes long
+	fn make_timed_spin(id: u32, artist: &str, song: &str, start: &str) -> Spin {
+		let start_time = chrono::DateTime::parse_from_str(start, "%Y-%m-%dT%H:%M:%S%z").unwrap();
+		let end = (start_time + chrono::Duration::minutes(3)).format("%Y-%m-%dT%H:%M:%S%z").to_string();
+
+		serde_json::from_value(serde_json::json!({
+			"id": id, "artist": artist, "song": song, "release": "Some Release",
+			"duration": 180, "start": start, "end": end
+		})).unwrap()
+	}
+
+	#[test]
+	fn safe_harbor_wraps_past_midnight() {
+		let guard = make_guard(&[], &[], (22, 6));
+		let safe_hours: Vec<u32> = (0..24).filter(|hour| guard.is_safe_harbor(*hour)).collect();
+		assert_eq!(safe_hours, [0, 1, 2, 3, 4, 5, 22, 23]);
+	}
+
+	#[test]
+	fn safe_harbor_within_one_day() {
+		let guard = make_guard(&[], &[], (1, 4));
+		let safe_hours: Vec<u32> = (0..24).filter(|hour| guard.is_safe_harbor(*hour)).collect();
+		assert_eq!(safe_hours, [1, 2, 3]);
+	}
+
+	#[test]
+	fn matches_whole_fields_ignoring_case() {
+		let guard = make_guard(&["the bad words", "dj .*"], &["(?:very )?loud song"], (22, 6));
+
+		assert_eq!(guard.find_match(&make_spin("THE BAD WORDS", "Fine Song")), Some(("artist", "^(?:the bad words)$")));
+		assert_eq!(guard.find_match(&make_spin("DJ Anybody", "Fine Song")), Some(("artist", "^(?:dj .*)$")));
+		assert_eq!(guard.find_match(&make_spin("Someone", "Very Loud Song")), Some(("song", "^(?:(?:very )?loud song)$")));
+
+		// Partial matches don't count
+		assert_eq!(guard.find_match(&make_spin("The Bad Words Band", "Fine Song")), None);
+		assert_eq!(guard.find_match(&make_spin("Someone", "A Loud Song")), None);
+	}
+
+	#[test]
+	fn checks_the_current_spin_until_it_ends_and_queued_spins_before_they_start() {
+		let guard = make_guard(&["the bad words"], &[], (22, 6));
+		let curr_spin = make_timed_spin(1, "The Bad Words", "Current Song", "2024-03-15T10:00:00+0000");
+
+		let queued_spins = [
+			make_timed_spin(2, "Someone", "Fine Song", "2024-03-15T10:03:00+0000"),
+			make_timed_spin(3, "The Bad Words", "Queued Song", "2024-03-15T10:06:00+0000")
+		];
+
+		let at = |time: &str| chrono::DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&chrono::Local);
+		let flagged_ids = |time: &str| guard.find_flagged_spins(Some(&curr_spin), &queued_spins, at(time))
+			.iter().map(|(spin, _)| spin.get_id()).collect::<Vec<_>>();
+
+		assert_eq!(flagged_ids("2024-03-15T10:01:00+00:00"), [1, 3]);
+
+		// The current spin is over (even if it's not expired yet), and the flagged queued one hasn't started
+		assert_eq!(flagged_ids("2024-03-15T10:04:00+00:00"), [3]);
+
+		// The flagged queued spin already started, so it would be the current spin by now
+		assert!(flagged_ids("2024-03-15T10:07:00+00:00").is_empty());
+	}
+}
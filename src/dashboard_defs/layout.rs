@@ -0,0 +1,684 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{
+	texture::TexturePool,
+	spinitron::model::{SpinBadge, SpinitronModelName},
+
+	utility_types::{
+		vec2f::Vec2f,
+		generic_result::*,
+		dynamic_optional::DynamicOptional,
+		update_rate::{Seconds, UpdateRate, UpdateRateCreator}
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		WindowContents
+	},
+
+	dashboard_defs::{
+		error::{make_error_window, ErrorMessageSource},
+		credit::make_credit_window,
+		weather::{make_weather_window, make_weather_forecast_window, make_weather_alert_banner_window},
+		astronomy::make_astronomy_window,
+		clock::{make_clock_window, ClockHandConfig, ClockHandConfigs},
+		twilio::{make_twilio_window, TwilioState},
+		spinitron::make_spinitron_model_window,
+		spin_progress::make_spin_progress_window,
+		spin_history::{make_spin_history_window, MAX_NUM_COVERS},
+		spin_badges::make_spin_badges_window,
+		show_schedule::{make_show_schedule_window, MAX_NUM_ROWS},
+		show_countdown::{make_show_countdown_window, ShowCountdownWarning},
+		legal_id::{make_legal_id_reminder_window, LegalIdReminderCreationInfo},
+		surprise::{make_surprise_window, SurpriseCreationInfo}
+	}
+};
+
+/* This describes the window tree in data form (see `assets/dashboard_layout.json`).
+Each window has a kind, which maps to a builder in the registry below. The builder
+gets the window's kind-specific params, and everything else (the extents, the border
+color, aspect ratio correction skipping, and child windows) is handled uniformly. */
+
+////////// These are the serializable building blocks of a layout
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum LayoutColor {
+	Rgb(u8, u8, u8),
+	Rgba(u8, u8, u8, u8)
+}
+
+impl From<LayoutColor> for ColorSDL {
+	fn from(color: LayoutColor) -> Self {
+		match color {
+			LayoutColor::Rgb(r, g, b) => ColorSDL::RGB(r, g, b),
+			LayoutColor::Rgba(r, g, b, a) => ColorSDL::RGBA(r, g, b, a)
+		}
+	}
+}
+
+#[derive(Deserialize, Default)]
+enum ContentsLayout {
+	#[default]
+	Nothing,
+	Color(LayoutColor),
+	Texture(String),
+	Many(Vec<ContentsLayout>)
+}
+
+impl ContentsLayout {
+	fn make_contents(&self, texture_pool: &mut TexturePool) -> GenericResult<WindowContents> {
+		Ok(match self {
+			Self::Nothing => WindowContents::Nothing,
+			Self::Color(color) => WindowContents::Color((*color).into()),
+			Self::Texture(path) => WindowContents::make_texture_contents(path, texture_pool)?,
+
+			Self::Many(many) => WindowContents::Many(
+				many.iter().map(|contents| contents.make_contents(texture_pool)).collect::<GenericResult<_>>()?
+			)
+		})
+	}
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum LayoutBlendMode {
+	None, Blend, Add, Mod
+}
+
+impl From<LayoutBlendMode> for sdl2::render::BlendMode {
+	fn from(blend_mode: LayoutBlendMode) -> Self {
+		match blend_mode {
+			LayoutBlendMode::None => Self::None,
+			LayoutBlendMode::Blend => Self::Blend,
+			LayoutBlendMode::Add => Self::Add,
+			LayoutBlendMode::Mod => Self::Mod
+		}
+	}
+}
+
+fn default_params() -> serde_json::Value {
+	serde_json::Value::Object(serde_json::Map::new())
+}
+
+#[derive(Deserialize)]
+pub struct WindowLayout {
+	kind: String,
+	top_left: Vec2f,
+	size: Vec2f,
+
+	#[serde(default)]
+	border_color: Option<LayoutColor>,
+
+	#[serde(default)]
+	skip_aspect_ratio_correction: bool,
+
+	#[serde(default = "default_params")]
+	params: serde_json::Value,
+
+	#[serde(default)]
+	children: Vec<WindowLayout>
+}
+
+//////////
+
+// This is everything that the window builders may need, beyond their own params
+pub struct LayoutBuildContext<'a, 'b, 'c> {
+	pub texture_pool: &'a mut TexturePool<'b>,
+	pub update_rate_creator: UpdateRateCreator,
+	pub shared_update_rate: UpdateRate,
+	pub twilio_state: &'a TwilioState<'c>
+}
+
+type WindowBuilder = fn(&WindowLayout, &mut LayoutBuildContext) -> GenericResult<Window>;
+
+lazy_static::lazy_static!(
+	static ref WINDOW_BUILDER_REGISTRY: HashMap<&'static str, WindowBuilder> = HashMap::from([
+		("container", build_container_window as WindowBuilder),
+		("static_texture", build_static_texture_window),
+		("spinitron_model", build_spinitron_model_window),
+		("spin_progress", build_spin_progress_window),
+		("spin_history", build_spin_history_window),
+		("spin_badges", build_spin_badges_window),
+		("show_schedule", build_show_schedule_window),
+		("show_countdown", build_show_countdown_window),
+		("legal_id_reminder", build_legal_id_reminder_window),
+		("twilio", build_twilio_window),
+		("clock", build_clock_window),
+		("weather", build_weather_window),
+		("weather_forecast", build_weather_forecast_window),
+		("weather_alert_banner", build_weather_alert_banner_window),
+		("astronomy", build_astronomy_window),
+		("error", build_error_window),
+		("explicit_content_warning", build_explicit_content_warning_window),
+		("credit", build_credit_window),
+		("surprise", build_surprise_window)
+	]);
+);
+
+fn parse_params<T: for<'de> Deserialize<'de>>(layout: &WindowLayout) -> GenericResult<T> {
+	T::deserialize(&layout.params).context(format!("Invalid params for a window of kind '{}'", layout.kind))
+}
+
+fn get_border_color(layout: &WindowLayout) -> GenericResult<ColorSDL> {
+	layout.border_color.map(ColorSDL::from).context(format!("A window of kind '{}' needs a border color", layout.kind))
+}
+
+pub fn build_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	let Some(builder) = WINDOW_BUILDER_REGISTRY.get(layout.kind.as_str()) else {
+		return error_msg!("Unknown window kind '{}' in the layout", layout.kind);
+	};
+
+	let mut window = builder(layout, context)?;
+
+	if let Some(border_color) = layout.border_color {
+		window.set_border_color(Some(border_color.into()));
+	}
+
+	if layout.skip_aspect_ratio_correction {
+		window.set_aspect_ratio_correction_skipping(true);
+	}
+
+	let children = layout.children.iter().map(|child| build_window(child, context)).collect::<GenericResult<_>>()?;
+	window.add_children(children);
+
+	Ok(window)
+}
+
+////////// These are the window builders
+
+fn build_container_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		#[serde(default)]
+		contents: ContentsLayout
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(Window::new(
+		None,
+		DynamicOptional::NONE,
+		params.contents.make_contents(context.texture_pool)?,
+		None,
+		layout.top_left,
+		layout.size,
+		None
+	))
+}
+
+fn build_static_texture_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		path: String
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(Window::new(
+		None,
+		DynamicOptional::NONE,
+		WindowContents::make_texture_contents(&params.path, context.texture_pool)?,
+		None,
+		layout.top_left,
+		layout.size,
+		None
+	))
+}
+
+fn build_spinitron_model_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// If there's a text color, this is a text window
+	#[derive(Deserialize)]
+	struct Params {
+		model_name: SpinitronModelName,
+
+		#[serde(default)]
+		text_color: Option<LayoutColor>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	// The Spinitron windows update at the same rate as the shared update rate
+	Ok(make_spinitron_model_window(
+		params.model_name,
+		params.text_color.map(ColorSDL::from),
+		layout.top_left, layout.size,
+		layout.border_color.map(ColorSDL::from),
+		context.shared_update_rate
+	))
+}
+
+fn build_spin_progress_window(layout: &WindowLayout, _: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The bar height is relative to the window height (the progress text goes under the bar)
+	#[derive(Deserialize)]
+	struct Params {
+		bar_height: f32,
+		elapsed_color: LayoutColor,
+		remaining_color: LayoutColor,
+		text_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	if !(0.0..=1.0).contains(&params.bar_height) {
+		return error_msg!("The spin progress bar height should be between 0 and 1, but it was {}", params.bar_height);
+	}
+
+	Ok(make_spin_progress_window(
+		layout.top_left, layout.size,
+		params.bar_height,
+		params.elapsed_color.into(),
+		params.remaining_color.into(),
+		params.text_color.into()
+	))
+}
+
+fn build_spin_history_window(layout: &WindowLayout, _: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The cover padding is relative to each cover's slot
+	#[derive(Deserialize)]
+	struct Params {
+		num_covers: usize,
+		cover_padding: f32,
+		animation_duration_ms: i64,
+
+		#[serde(default)]
+		cover_border_color: Option<LayoutColor>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	if params.num_covers == 0 || params.num_covers > MAX_NUM_COVERS {
+		return error_msg!("The spin history window should have 1 to {MAX_NUM_COVERS} covers, but it had {}", params.num_covers);
+	}
+
+	if !(0.0..0.5).contains(&params.cover_padding) {
+		return error_msg!("The spin history cover padding should be at least 0 and under 0.5, but it was {}", params.cover_padding);
+	}
+
+	// Otherwise, the cover scale would be NaN
+	if params.animation_duration_ms <= 0 {
+		return error_msg!("The spin history animation duration should be positive, but it was {} ms", params.animation_duration_ms);
+	}
+
+	Ok(make_spin_history_window(
+		layout.top_left, layout.size,
+		params.num_covers,
+		params.cover_padding,
+		chrono::Duration::milliseconds(params.animation_duration_ms),
+		params.cover_border_color.map(ColorSDL::from)
+	))
+}
+
+fn build_spin_badges_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The badge texts map badge names (`local`, `new`, `request`, and `various_artists`) to what's shown for them
+	#[derive(Deserialize)]
+	struct Params {
+		#[serde(default)]
+		badge_texts: HashMap<SpinBadge, String>,
+
+		separator: String,
+		text_color: LayoutColor,
+
+		#[serde(default)]
+		background_color: Option<LayoutColor>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	// Like the other Spinitron windows, this updates at the shared update rate
+	Ok(make_spin_badges_window(
+		layout.top_left, layout.size,
+		context.shared_update_rate,
+		params.badge_texts,
+		params.separator,
+		params.text_color.into(),
+		params.background_color.map(ColorSDL::from)
+	))
+}
+
+fn build_show_schedule_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The current show's row is drawn over the highlight color
+	#[derive(Deserialize)]
+	struct Params {
+		num_rows: usize,
+		update_rate_secs: Seconds,
+		text_color: LayoutColor,
+		highlight_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	if params.num_rows == 0 || params.num_rows > MAX_NUM_ROWS {
+		return error_msg!("The show schedule window should have 1 to {MAX_NUM_ROWS} rows, but it had {}", params.num_rows);
+	}
+
+	Ok(make_show_schedule_window(
+		layout.top_left, layout.size,
+		params.num_rows,
+		context.update_rate_creator.new_instance(params.update_rate_secs),
+		params.text_color.into(),
+		params.highlight_color.into()
+	))
+}
+
+fn build_show_countdown_window(layout: &WindowLayout, _: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct WarningParams {
+		minutes_before_end: f64,
+		background_color: LayoutColor,
+
+		#[serde(default)]
+		flashing: bool
+	}
+
+	#[derive(Deserialize)]
+	struct Params {
+		text_color: LayoutColor,
+		warnings: Vec<WarningParams>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	let warnings = params.warnings.iter().map(|warning| {
+		ShowCountdownWarning {
+			time_before_end: chrono::Duration::milliseconds((warning.minutes_before_end * 60_000.0) as i64),
+			background_color: warning.background_color.into(),
+			flashing: warning.flashing
+		}
+	}).collect();
+
+	Ok(make_show_countdown_window(
+		layout.top_left, layout.size,
+		params.text_color.into(),
+		warnings
+	))
+}
+
+fn build_legal_id_reminder_window(layout: &WindowLayout, _: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The acknowledgement key is an SDL key name, like "Space" or "Return"
+	#[derive(Deserialize)]
+	struct Params {
+		text: String,
+		minutes_before_hour: f64,
+		minutes_after_hour: f64,
+
+		acknowledgement_key: String,
+		acknowledgement_socket_path: String,
+		audit_log_path: String,
+
+		text_color: LayoutColor,
+		background_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	let Some(acknowledgement_key) = sdl2::keyboard::Keycode::from_name(&params.acknowledgement_key) else {
+		return error_msg!("Unknown key name '{}' for acknowledging the legal ID reminder", params.acknowledgement_key);
+	};
+
+	let minutes_to_duration = |minutes: f64| chrono::Duration::milliseconds((minutes * 60_000.0) as i64);
+
+	make_legal_id_reminder_window(
+		layout.top_left, layout.size,
+
+		&LegalIdReminderCreationInfo {
+			text: &params.text,
+			time_before_hour: minutes_to_duration(params.minutes_before_hour),
+			time_after_hour: minutes_to_duration(params.minutes_after_hour),
+
+			acknowledgement_key,
+			acknowledgement_socket_path: &params.acknowledgement_socket_path,
+			audit_log_path: &params.audit_log_path,
+
+			text_color: params.text_color.into(),
+			background_color: params.background_color.into()
+		}
+	)
+}
+
+fn build_twilio_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		update_rate_secs: Seconds,
+		top_box_height: f32,
+		top_box_contents: ContentsLayout,
+		message_background_contents_text_crop_factor: Vec2f,
+		text_color: LayoutColor,
+		message_background_contents: ContentsLayout
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_twilio_window(
+		context.twilio_state,
+		context.update_rate_creator.new_instance(params.update_rate_secs),
+		layout.top_left, layout.size,
+		params.top_box_height,
+		params.top_box_contents.make_contents(context.texture_pool)?,
+		params.message_background_contents_text_crop_factor,
+		get_border_color(layout)?, params.text_color.into(),
+		params.message_background_contents.make_contents(context.texture_pool)?
+	))
+}
+
+fn build_clock_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// These extents are defined assuming that the clock is pointing to 12:00
+	#[derive(Deserialize)]
+	struct HandParams {
+		x_extent: f32,
+		minor_y_extent: f32,
+		major_y_extent: f32,
+		color: LayoutColor
+	}
+
+	#[derive(Deserialize)]
+	struct Params {
+		dial_texture_path: String,
+		milliseconds_hand: HandParams,
+		seconds_hand: HandParams,
+		minutes_hand: HandParams,
+		hours_hand: HandParams
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	let make_hand_config = |hand: &HandParams|
+		ClockHandConfig::new(hand.x_extent, hand.minor_y_extent, hand.major_y_extent, hand.color.into());
+
+	make_clock_window(
+		UpdateRate::ONCE_PER_FRAME,
+		layout.top_left,
+		layout.size,
+
+		ClockHandConfigs {
+			milliseconds: make_hand_config(&params.milliseconds_hand),
+			seconds: make_hand_config(&params.seconds_hand),
+			minutes: make_hand_config(&params.minutes_hand),
+			hours: make_hand_config(&params.hours_hand)
+		},
+
+		&params.dial_texture_path,
+		context.texture_pool
+	)
+}
+
+fn build_weather_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	// The location and units are in the app config, since the weather is fetched for the whole dashboard
+	#[derive(Deserialize)]
+	struct Params {
+		text_color: LayoutColor,
+		background_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_weather_window(
+		layout.top_left,
+		layout.size,
+		context.shared_update_rate,
+		params.text_color.into(),
+		params.background_color.into()
+	))
+}
+
+fn build_weather_forecast_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		num_hours: usize,
+		num_days: usize,
+		text_color: LayoutColor,
+
+		#[serde(default)]
+		background_color: Option<LayoutColor>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_weather_forecast_window(
+		layout.top_left,
+		layout.size,
+		context.shared_update_rate,
+		params.num_hours,
+		params.num_days,
+		params.text_color.into(),
+		params.background_color.map(ColorSDL::from)
+	))
+}
+
+fn build_weather_alert_banner_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		text_color: LayoutColor,
+		background_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_weather_alert_banner_window(
+		layout.top_left,
+		layout.size,
+		context.shared_update_rate,
+		params.text_color.into(),
+		params.background_color.into()
+	))
+}
+
+fn build_astronomy_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		text_color: LayoutColor,
+
+		#[serde(default)]
+		background_color: Option<LayoutColor>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_astronomy_window(
+		layout.top_left,
+		layout.size,
+		context.shared_update_rate,
+		params.text_color.into(),
+		params.background_color.map(ColorSDL::from)
+	))
+}
+
+fn build_error_window_with_source(layout: &WindowLayout, context: &mut LayoutBuildContext,
+	message_source: ErrorMessageSource) -> GenericResult<Window> {
+
+	#[derive(Deserialize)]
+	struct Params {
+		update_rate_secs: Seconds,
+		background_contents: ContentsLayout,
+		text_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_error_window(
+		layout.top_left,
+		layout.size,
+		context.update_rate_creator.new_instance(params.update_rate_secs),
+		params.background_contents.make_contents(context.texture_pool)?,
+		params.text_color.into(),
+		message_source
+	))
+}
+
+fn build_error_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	build_error_window_with_source(layout, context, |state| &state.curr_dashboard_error)
+}
+
+// This is a more prominent error window, which only shows explicit content warnings (see `explicit_content.rs`)
+fn build_explicit_content_warning_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	build_error_window_with_source(layout, context, |state| &state.curr_explicit_content_warning)
+}
+
+fn build_credit_window(layout: &WindowLayout, _: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct Params {
+		text: String,
+		text_color: LayoutColor
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	Ok(make_credit_window(
+		layout.top_left,
+		layout.size,
+		get_border_color(layout)?,
+		params.text_color.into(),
+		&params.text
+	))
+}
+
+fn build_surprise_window(layout: &WindowLayout, context: &mut LayoutBuildContext) -> GenericResult<Window> {
+	#[derive(Deserialize)]
+	struct SurpriseParams {
+		texture_path: String,
+		texture_blend_mode: LayoutBlendMode,
+
+		update_rate_ms: i64,
+		num_update_steps_to_appear_for: u16,
+		chance_of_appearing_when_updating: f64,
+
+		local_hours_24_start: u8,
+		local_hours_24_end: u8,
+
+		flicker_window: bool
+	}
+
+	#[derive(Deserialize)]
+	struct Params {
+		artificial_triggering_socket_path: String,
+		surprises: Vec<SurpriseParams>
+	}
+
+	let params: Params = parse_params(layout)?;
+
+	let surprise_creation_info: Vec<SurpriseCreationInfo> = params.surprises.iter().map(|surprise| {
+		SurpriseCreationInfo {
+			texture_path: &surprise.texture_path,
+			texture_blend_mode: surprise.texture_blend_mode.into(),
+
+			update_rate: chrono::Duration::milliseconds(surprise.update_rate_ms),
+			num_update_steps_to_appear_for: surprise.num_update_steps_to_appear_for,
+			chance_of_appearing_when_updating: surprise.chance_of_appearing_when_updating,
+
+			local_hours_24_start: surprise.local_hours_24_start,
+			local_hours_24_end: surprise.local_hours_24_end,
+
+			flicker_window: surprise.flicker_window
+		}
+	}).collect();
+
+	make_surprise_window(
+		layout.top_left, layout.size,
+		&params.artificial_triggering_socket_path,
+		&surprise_creation_info,
+		context.update_rate_creator,
+		context.texture_pool
+	)
+}
@@ -0,0 +1,235 @@
+use std::{
+	borrow::Cow,
+	io::Read
+};
+
+use interprocess::local_socket::{
+	ListenerNonblockingMode,
+	prelude::LocalSocketListener
+};
+
+use crate::{
+	utility_types::{
+		vec2f::Vec2f,
+		json_utils,
+		command_socket,
+		generic_result::*,
+		update_rate::UpdateRate
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		FrameTime,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		clock,
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This reminds the DJ to give a legal station ID near the top of every hour (which FCC rules require).
+The reminder shows up a bit before the hour, and stays up until it's acknowledged (with a key, or by
+writing anything to a socket), or until a bit after the hour. Each reminder is logged to an audit file
+(one JSON object per line), with whether it was acknowledged, so that station management can check compliance.
+
+To acknowledge the reminder from another program, run something like `echo ack | nc -U <socket path>`. */
+
+pub struct LegalIdReminderCreationInfo<'a> {
+	pub text: &'a str,
+	pub time_before_hour: chrono::Duration,
+	pub time_after_hour: chrono::Duration,
+
+	pub acknowledgement_key: sdl2::keyboard::Keycode,
+	pub acknowledgement_socket_path: &'a str,
+	pub audit_log_path: &'a str,
+
+	pub text_color: ColorSDL,
+	pub background_color: ColorSDL
+}
+
+struct ActiveReminder {
+	top_of_hour: FrameTime,
+	shown_at: FrameTime
+}
+
+struct LegalIdReminderState {
+	text: String,
+	time_before_hour: chrono::Duration,
+	time_after_hour: chrono::Duration,
+
+	acknowledgement_key: sdl2::keyboard::Keycode,
+	acknowledgement_listener: LocalSocketListener,
+	audit_log_path: String,
+
+	maybe_active_reminder: Option<ActiveReminder>,
+	maybe_last_handled_top_of_hour: Option<FrameTime>
+}
+
+// The state is shared via an `Rc`, since the text window pattern needs its state to be cloneable
+#[derive(Clone)]
+struct SharedLegalIdReminderState(std::rc::Rc<std::cell::RefCell<LegalIdReminderState>>);
+
+//////////
+
+impl LegalIdReminderState {
+	// This returns how the reminder was acknowledged since the last frame (if it was)
+	fn check_for_acknowledgement(&mut self, keys_pressed_this_frame: &[sdl2::keyboard::Keycode]) -> Option<&'static str> {
+		// Any connection to the socket counts as an acknowledgement (and the connections are always drained)
+		let mut acknowledged_via_socket = false;
+
+		while let Some(Ok(mut stream)) = self.acknowledgement_listener.next() {
+			let mut ignored_message = Vec::new();
+			let _ = stream.read_to_end(&mut ignored_message);
+			acknowledged_via_socket = true;
+		}
+
+		if acknowledged_via_socket {Some("socket")}
+		else if keys_pressed_this_frame.contains(&self.acknowledgement_key) {Some("key")}
+		else {None}
+	}
+
+	fn write_audit_log_entry(&self, reminder: &ActiveReminder, maybe_acknowledgement: Option<(&str, FrameTime)>) {
+		let (acknowledged_via, acknowledged_at) = match maybe_acknowledgement {
+			Some((via, at)) => (Some(via), Some(at.to_rfc3339())),
+			None => (None, None)
+		};
+
+		let entry = serde_json::json!({
+			"top_of_hour": reminder.top_of_hour.to_rfc3339(),
+			"shown_at": reminder.shown_at.to_rfc3339(),
+			"acknowledged": maybe_acknowledgement.is_some(),
+			"acknowledged_via": acknowledged_via,
+			"acknowledged_at": acknowledged_at
+		});
+
+		if let Err(err) = json_utils::append_json_lines(&self.audit_log_path, [&entry]) {
+			log::error!("Could not write to the legal ID audit log at '{}': '{err}'.", self.audit_log_path);
+		}
+	}
+
+	// This returns true if the reminder was just shown (so that its text texture can be made)
+	fn update(&mut self, curr_time: FrameTime, keys_pressed_this_frame: &[sdl2::keyboard::Keycode]) -> bool {
+		let maybe_nearby_top_of_hour = clock::get_nearby_top_of_hour(curr_time, self.time_before_hour, self.time_after_hour);
+		let maybe_acknowledgement = self.check_for_acknowledgement(keys_pressed_this_frame);
+
+		if let Some(reminder) = &self.maybe_active_reminder {
+			if let Some(via) = maybe_acknowledgement {
+				log::info!("The legal ID reminder for {} was acknowledged (via {via}).", reminder.top_of_hour);
+				self.write_audit_log_entry(reminder, Some((via, curr_time)));
+			}
+			else if maybe_nearby_top_of_hour != Some(reminder.top_of_hour) {
+				log::warn!("The legal ID reminder for {} was never acknowledged!", reminder.top_of_hour);
+				self.write_audit_log_entry(reminder, None);
+			}
+			else {
+				return false;
+			}
+
+			self.maybe_last_handled_top_of_hour = Some(reminder.top_of_hour);
+			self.maybe_active_reminder = None;
+			return false;
+		}
+
+		if maybe_acknowledgement.is_some() {
+			log::info!("A legal ID reminder was acknowledged, but none was being shown.");
+		}
+
+		match maybe_nearby_top_of_hour {
+			Some(top_of_hour) if self.maybe_last_handled_top_of_hour != Some(top_of_hour) => {
+				log::info!("Showing the legal ID reminder for {top_of_hour}.");
+				self.maybe_active_reminder = Some(ActiveReminder {top_of_hour, shown_at: curr_time});
+				true
+			},
+
+			_ => false
+		}
+	}
+}
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for SharedLegalIdReminderState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let keys_pressed_this_frame = &updater_params.shared_window_state.get::<SharedWindowState>().keys_pressed_this_frame;
+
+		let wrapped_individual_state = updater_params.window.get_state
+			::<updatable_text_pattern::UpdatableTextWindowFields<SharedLegalIdReminderState>>();
+
+		let mut reminder_state = wrapped_individual_state.inner.0.borrow_mut();
+		let just_shown = reminder_state.update(updater_params.curr_time, keys_pressed_this_frame);
+		let is_active = reminder_state.maybe_active_reminder.is_some();
+		drop(reminder_state);
+
+		updater_params.window.set_draw_skipping(!is_active);
+
+		// The text texture only has to be made once
+		let WindowContents::Many(all_contents) = updater_params.window.get_contents()
+		else {panic!("The legal ID reminder window contents was expected to be a list!")};
+
+		!(just_shown && matches!(all_contents[1], WindowContents::Nothing))
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), " ")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Owned(self.0.borrow().text.clone())
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The legal ID reminder window contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+//////////
+
+// This is updated every frame, so that key presses are not missed
+pub fn make_legal_id_reminder_window(top_left: Vec2f, size: Vec2f,
+	creation_info: &LegalIdReminderCreationInfo) -> GenericResult<Window> {
+
+	let socket_path = creation_info.acknowledgement_socket_path;
+
+	// Like with the surprise socket, this is polled on the main thread
+	let acknowledgement_listener = command_socket::make_listener(socket_path,
+		"legal ID acknowledgement listener", ListenerNonblockingMode::Both)?;
+
+	let reminder_state = LegalIdReminderState {
+		text: creation_info.text.to_string(),
+		time_before_hour: creation_info.time_before_hour,
+		time_after_hour: creation_info.time_after_hour,
+
+		acknowledgement_key: creation_info.acknowledgement_key,
+		acknowledgement_listener,
+		audit_log_path: creation_info.audit_log_path.to_string(),
+
+		maybe_active_reminder: None,
+		maybe_last_handled_top_of_hour: None
+	};
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: SharedLegalIdReminderState(std::rc::Rc::new(std::cell::RefCell::new(reminder_state))),
+		text_color: creation_info.text_color,
+
+		scroll_fn: |seed, _| {
+			let repeat_rate_secs = 4.0;
+			((seed % repeat_rate_secs) / repeat_rate_secs, true)
+		},
+
+		update_rate: UpdateRate::ONCE_PER_FRAME,
+		maybe_border_color: None
+	};
+
+	let mut window = updatable_text_pattern::make_window(
+		fields, top_left, size,
+		WindowContents::Many(vec![WindowContents::Color(creation_info.background_color), WindowContents::Nothing])
+	);
+
+	window.set_draw_skipping(true);
+	Ok(window)
+}
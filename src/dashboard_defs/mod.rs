@@ -2,9 +2,23 @@ mod clock;
 mod error;
 mod credit;
 mod twilio;
+mod twilio_moderation;
+mod twilio_cleanup;
+mod twilio_replies;
+mod twilio_webhook;
 mod weather;
+mod weather_mock_server;
+mod layout;
 mod surprise;
 mod spinitron;
+mod spin_progress;
+mod spin_history;
+mod spin_badges;
+mod show_schedule;
+mod show_countdown;
+mod legal_id;
+mod explicit_content;
+mod astronomy;
 mod shared_window_state;
 mod updatable_text_pattern;
 pub mod dashboard;
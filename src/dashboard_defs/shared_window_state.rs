@@ -1,13 +1,19 @@
 use crate::{
     spinitron::state::SpinitronState,
     texture::{FontInfo, TextureCreationInfo},
-    dashboard_defs::{twilio::TwilioState, clock::ClockHands}
+    dashboard_defs::{
+        twilio::TwilioState,
+        weather::WeatherState,
+        astronomy::AstronomyState,
+        explicit_content::ExplicitContentGuard
+    }
 };
 
 pub struct SharedWindowState<'a> {
-	pub clock_hands: ClockHands,
 	pub spinitron_state: SpinitronState,
 	pub twilio_state: TwilioState<'a>,
+	pub maybe_weather_state: Option<WeatherState>,
+	pub maybe_astronomy_state: Option<AstronomyState>,
 
 	pub font_info: &'a FontInfo,
 
@@ -15,8 +21,16 @@ pub struct SharedWindowState<'a> {
 	pub fallback_texture_creation_info: &'a TextureCreationInfo<'a>,
 
 	pub curr_dashboard_error: Option<String>,
+	pub config_reload_error: Option<String>,
 
-	pub rand_generator: rand::rngs::ThreadRng
+	// The explicit content warning is also part of the dashboard error (but it can be shown on its own too)
+	pub maybe_explicit_content_guard: Option<ExplicitContentGuard>,
+	pub curr_explicit_content_warning: Option<String>,
+
+	pub rand_generator: rand::rngs::ThreadRng,
+
+	// These are the keys that were pressed since the last frame (windows that react to them should update every frame)
+	pub keys_pressed_this_frame: Vec<sdl2::keyboard::Keycode>
 
 	/* TODO: can I keep the texture pool here, instead of passing it in to
 	each window on its own (and the shared window state updater)? */
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+use crate::{
+	utility_types::{
+		vec2f::Vec2f,
+		update_rate::UpdateRate
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		FrameTime,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This counts down to the end of the current show, so that the DJ can wrap up in time for the next one.
+Once the time left drops under a warning's threshold, the background switches to that warning's
+color (and it may flash). The closest warning to the end of the show wins. */
+
+const FLASH_PERIOD_MS: i64 = 500;
+
+#[derive(Clone)]
+pub struct ShowCountdownWarning {
+	pub time_before_end: chrono::Duration,
+	pub background_color: ColorSDL,
+	pub flashing: bool
+}
+
+#[derive(Clone)]
+struct ShowCountdownState {
+	warnings: Vec<ShowCountdownWarning>,
+	text: String,
+	maybe_shown_background_color: Option<ColorSDL>
+}
+
+//////////
+
+// This returns the time left in the current show, or `None` if there's no countdown to show
+fn get_time_left_in_show(inner_shared_state: &SharedWindowState, curr_time: FrameTime) -> Option<chrono::Duration> {
+	let spinitron_state = &inner_shared_state.spinitron_state;
+
+	if spinitron_state.is_connecting() {
+		return None;
+	}
+
+	match spinitron_state.get_show().get_end_time() {
+		// The show may be over before the next one is polled
+		Ok(end_time) => Some(end_time.signed_duration_since(curr_time).max(chrono::Duration::zero())),
+
+		Err(err) => {
+			log::warn!("Could not get the end time of the current show: '{err}'.");
+			None
+		}
+	}
+}
+
+// This looks like "12:34", or "1:02:03" for an hour or more
+fn format_time_left(time_left: chrono::Duration) -> String {
+	let secs = time_left.num_seconds();
+	let (hours, minutes, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+
+	if hours == 0 {format!("{minutes}:{secs:02}")}
+	else {format!("{hours}:{minutes:02}:{secs:02}")}
+}
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for ShowCountdownState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let inner_shared_state = updater_params.shared_window_state.get::<SharedWindowState>();
+		let curr_time = updater_params.curr_time;
+
+		let Some(time_left) = get_time_left_in_show(inner_shared_state, curr_time) else {
+			updater_params.window.set_draw_skipping(true);
+			return true;
+		};
+
+		updater_params.window.set_draw_skipping(false);
+
+		let wrapped_individual_state = updater_params.window.get_state_mut
+			::<updatable_text_pattern::UpdatableTextWindowFields<ShowCountdownState>>();
+
+		let countdown_state = &mut wrapped_individual_state.inner;
+
+		////////// Updating the background, based on the closest warning that was passed
+
+		let maybe_warning = countdown_state.warnings.iter()
+			.filter(|warning| time_left <= warning.time_before_end)
+			.min_by_key(|warning| warning.time_before_end);
+
+		let maybe_background_color = maybe_warning.and_then(|warning| {
+			let flashed_off = warning.flashing && (curr_time.timestamp_millis() / FLASH_PERIOD_MS) % 2 == 1;
+			(!flashed_off).then_some(warning.background_color)
+		});
+
+		let background_changed = maybe_background_color != countdown_state.maybe_shown_background_color;
+		countdown_state.maybe_shown_background_color = maybe_background_color;
+
+		////////// Updating the text (which only changes once a second)
+
+		let text = format!("Show ends in {}", format_time_left(time_left));
+		let text_changed = text != countdown_state.text;
+		countdown_state.text = text;
+
+		if background_changed {
+			let WindowContents::Many(all_contents) = updater_params.window.get_contents_mut()
+			else {panic!("The show countdown window contents was expected to be a list!")};
+
+			all_contents[0] = maybe_background_color.map_or(WindowContents::Nothing, WindowContents::Color);
+		}
+
+		!text_changed
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), "")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Borrowed(&self.text)
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The show countdown window contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+//////////
+
+// This is updated every frame, so that flashing is smooth
+pub fn make_show_countdown_window(top_left: Vec2f, size: Vec2f,
+	text_color: ColorSDL, warnings: Vec<ShowCountdownWarning>) -> Window {
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: ShowCountdownState {warnings, text: String::new(), maybe_shown_background_color: None},
+		text_color,
+		scroll_fn: |_, _| (0.0, false),
+		update_rate: UpdateRate::ONCE_PER_FRAME,
+		maybe_border_color: None
+	};
+
+	let mut window = updatable_text_pattern::make_window(
+		fields, top_left, size,
+		WindowContents::Many(vec![WindowContents::Nothing, WindowContents::Nothing])
+	);
+
+	window.set_draw_skipping(true);
+	window
+}
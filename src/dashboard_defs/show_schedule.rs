@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use crate::{
+	spinitron::{
+		model::{Show, SpinitronModel, SpinitronModelName},
+		wrapper_types::SpinitronModelId
+	},
+
+	utility_types::{
+		vec2f::Vec2f,
+		update_rate::UpdateRate,
+		dynamic_optional::DynamicOptional
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This lists the upcoming shows for the rest of the day (one per row), with their start times
+and category emojis. The current show's row is highlighted. The rows only change when the list
+of upcoming shows does (which happens on show boundaries). */
+
+// This is how many upcoming shows the Spinitron state keeps (so a window can't show any more rows than this)
+pub const MAX_NUM_ROWS: usize = 8;
+
+#[derive(Clone)]
+struct ShowScheduleRowState {
+	index: usize, // This is the index into the upcoming shows
+	highlight_color: ColorSDL,
+
+	// This is the id of the shown show, and whether it was the current one
+	maybe_shown: Option<(SpinitronModelId, bool)>,
+	text: String
+}
+
+//////////
+
+// This looks like "10:00 PM 🎸 Polar Rock Hour"
+fn format_row_text(show: &Show) -> String {
+	let start_time = match show.get_start_time() {
+		Ok(start_time) => start_time.with_timezone(&chrono::Local).format("%-I:%M %p").to_string(),
+
+		Err(err) => {
+			log::warn!("Could not get the start time of the show '{}': '{err}'.", show.get_title());
+			"?:??".to_string()
+		}
+	};
+
+	match show.get_category_emojis() {
+		Some(emojis) => format!("{start_time} {emojis} {}", show.get_title()),
+		None => format!("{start_time} {}", show.get_title())
+	}
+}
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for ShowScheduleRowState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let spinitron_state = &updater_params.shared_window_state.get::<SharedWindowState>().spinitron_state;
+		let curr_show_id = spinitron_state.get_model_by_name(SpinitronModelName::Show).get_id();
+
+		let wrapped_individual_state = updater_params.window.get_state_mut
+			::<updatable_text_pattern::UpdatableTextWindowFields<ShowScheduleRowState>>();
+
+		let row_state = &mut wrapped_individual_state.inner;
+		let maybe_show = spinitron_state.get_upcoming_shows().get(row_state.index);
+		let maybe_curr = maybe_show.map(|show| (show.get_id(), show.get_id() == curr_show_id));
+
+		if maybe_curr == row_state.maybe_shown {
+			return true;
+		}
+
+		row_state.maybe_shown = maybe_curr;
+
+		let Some(show) = maybe_show else {
+			updater_params.window.set_draw_skipping(true);
+			return true;
+		};
+
+		row_state.text = format_row_text(show);
+
+		let background = match maybe_curr {
+			Some((_, true)) => WindowContents::Color(row_state.highlight_color),
+			_ => WindowContents::Nothing
+		};
+
+		let WindowContents::Many(all_contents) = updater_params.window.get_contents_mut()
+		else {panic!("The show schedule row contents was expected to be a list!")};
+
+		all_contents[0] = background;
+		updater_params.window.set_draw_skipping(false);
+
+		false
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), " ")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Borrowed(&self.text)
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The show schedule row contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+//////////
+
+// The rows are stacked from the top, and each one takes up an equal part of the window
+pub fn make_show_schedule_window(top_left: Vec2f, size: Vec2f, num_rows: usize,
+	update_rate: UpdateRate, text_color: ColorSDL, highlight_color: ColorSDL) -> Window {
+
+	let row_height = 1.0 / num_rows as f32;
+
+	let row_windows = (0..num_rows).map(|index| {
+		let fields = updatable_text_pattern::UpdatableTextWindowFields {
+			inner: ShowScheduleRowState {index, highlight_color, maybe_shown: None, text: String::new()},
+			text_color,
+			scroll_fn: |seed, _| (seed.sin() * 0.5 + 0.5, false),
+			update_rate,
+			maybe_border_color: None
+		};
+
+		let mut row_window = updatable_text_pattern::make_window(
+			fields,
+			Vec2f::new(0.0, index as f32 * row_height),
+			Vec2f::new(1.0, row_height),
+			WindowContents::Many(vec![WindowContents::Nothing, WindowContents::Nothing])
+		);
+
+		row_window.set_draw_skipping(true);
+		row_window
+	}).collect();
+
+	Window::new(
+		None,
+		DynamicOptional::NONE,
+		WindowContents::Nothing,
+		None,
+		top_left,
+		size,
+		Some(row_windows)
+	)
+}
@@ -0,0 +1,107 @@
+use std::{
+	borrow::Cow,
+	collections::HashMap
+};
+
+use crate::{
+	spinitron::model::SpinBadge,
+
+	utility_types::{
+		vec2f::Vec2f,
+		update_rate::UpdateRate
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This shows the badges that the current spin was marked with (local, new, requested, or various artists),
+so that the DJ can call them out on air. The badges are joined into one line of text, and the window is
+hidden when there are none (or when the spin is expired). */
+
+#[derive(Clone)]
+struct SpinBadgesState {
+	badge_texts: HashMap<SpinBadge, String>,
+	separator: String,
+	text: String
+}
+
+//////////
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for SpinBadgesState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let spinitron_state = &updater_params.shared_window_state.get::<SharedWindowState>().spinitron_state;
+
+		let badges = if spinitron_state.is_connecting() || spinitron_state.spin_is_expired() {Vec::new()}
+		else {spinitron_state.get_spin().get_badges()};
+
+		let wrapped_individual_state = updater_params.window.get_state_mut
+			::<updatable_text_pattern::UpdatableTextWindowFields<SpinBadgesState>>();
+
+		let badges_state = &mut wrapped_individual_state.inner;
+
+		let text = badges.iter()
+			.map(|badge| badges_state.badge_texts.get(badge).map_or(badge.get_default_text(), String::as_str))
+			.collect::<Vec<_>>()
+			.join(&badges_state.separator);
+
+		if text == badges_state.text {
+			return true;
+		}
+
+		badges_state.text = text;
+
+		let no_badges = badges_state.text.is_empty();
+		updater_params.window.set_draw_skipping(no_badges);
+		no_badges
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), "")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Borrowed(&self.text)
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The spin badges window contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+//////////
+
+// Any badge without a text in the given mapping uses its default text
+pub fn make_spin_badges_window(top_left: Vec2f, size: Vec2f,
+	update_rate: UpdateRate, badge_texts: HashMap<SpinBadge, String>, separator: String,
+	text_color: ColorSDL, maybe_background_color: Option<ColorSDL>) -> Window {
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: SpinBadgesState {badge_texts, separator, text: String::new()},
+		text_color,
+		scroll_fn: |_, _| (0.0, false),
+		update_rate,
+		maybe_border_color: None
+	};
+
+	let background = maybe_background_color.map_or(WindowContents::Nothing, WindowContents::Color);
+
+	let mut window = updatable_text_pattern::make_window(
+		fields, top_left, size,
+		WindowContents::Many(vec![background, WindowContents::Nothing])
+	);
+
+	window.set_draw_skipping(true);
+	window
+}
@@ -0,0 +1,149 @@
+use crate::{
+	spinitron::{
+		model::SpinitronModel,
+		wrapper_types::SpinitronModelId
+	},
+
+	utility_types::{
+		vec2f::Vec2f,
+		generic_result::*,
+		update_rate::UpdateRate,
+		dynamic_optional::DynamicOptional
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		FrameTime,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::shared_window_state::SharedWindowState
+};
+
+/* This shows the album art of the spins before the current one, as a row of covers
+(the newest one is on the left). Each cover is in a slot, which is a fixed part of the row;
+when a spin changes, the newest cover grows from the middle of its slot to fill it. */
+
+// This is how many spins the Spinitron state keeps in its history (so a window can't show any more covers than this)
+pub const MAX_NUM_COVERS: usize = 5;
+
+struct SpinHistoryCoverState {
+	index: usize, // This is the index into the spin history
+	maybe_shown_spin_id: Option<SpinitronModelId>,
+	maybe_animation_start: Option<FrameTime>,
+	animation_duration: chrono::Duration
+}
+
+//////////
+
+// This returns the fraction of the slot that the cover should take up, given the time since the animation started
+fn get_cover_scale(time_since_start: chrono::Duration, animation_duration: chrono::Duration) -> f32 {
+	let progress = time_since_start.num_milliseconds() as f32 / animation_duration.num_milliseconds() as f32;
+	let clamped_progress = progress.clamp(0.0, 1.0);
+
+	// This is an ease-out curve (so the cover slows down as it settles in)
+	1.0 - (1.0 - clamped_progress).powi(3)
+}
+
+fn cover_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+	let inner_shared_state = params.shared_window_state.get::<SharedWindowState>();
+	let spinitron_state = &inner_shared_state.spinitron_state;
+
+	let cover_state = params.window.get_state::<SpinHistoryCoverState>();
+	let index = cover_state.index;
+	let maybe_spin_id = spinitron_state.get_spin_history().get(index).map(|spin| spin.get_id());
+
+	////////// Remaking the texture when the spin at this index changes
+
+	if maybe_spin_id != cover_state.maybe_shown_spin_id {
+		// Only the newest cover is animated, and not when the history is first loaded
+		let should_animate = index == 0 && cover_state.maybe_shown_spin_id.is_some();
+
+		let cover_state = params.window.get_state_mut::<SpinHistoryCoverState>();
+		cover_state.maybe_shown_spin_id = maybe_spin_id;
+		cover_state.maybe_animation_start = should_animate.then_some(params.curr_time);
+
+		params.window.set_draw_skipping(maybe_spin_id.is_none());
+
+		if maybe_spin_id.is_some() {
+			params.window.get_contents_mut().update_as_texture(
+				true,
+				params.texture_pool,
+				&spinitron_state.get_cached_spin_history_texture_creation_info(index),
+				inner_shared_state.fallback_texture_creation_info
+			)?;
+		}
+	}
+
+	////////// Animating the cover in
+
+	let cover_state = params.window.get_state_mut::<SpinHistoryCoverState>();
+
+	if let Some(animation_start) = cover_state.maybe_animation_start {
+		let time_since_start = params.curr_time.signed_duration_since(animation_start);
+		let scale = get_cover_scale(time_since_start, cover_state.animation_duration);
+
+		if time_since_start >= cover_state.animation_duration {
+			cover_state.maybe_animation_start = None;
+		}
+
+		params.window.set_top_left(Vec2f::new_scalar((1.0 - scale) * 0.5));
+		params.window.set_size(Vec2f::new_scalar(scale));
+	}
+
+	Ok(())
+}
+
+//////////
+
+/* The padding is the fraction of each slot (on each side) that is left empty around its cover.
+The covers keep their aspect ratios within their slots. */
+pub fn make_spin_history_window(top_left: Vec2f, size: Vec2f,
+	num_covers: usize, cover_padding: f32, animation_duration: chrono::Duration,
+	maybe_cover_border_color: Option<ColorSDL>) -> Window {
+
+	let slot_width = 1.0 / num_covers as f32;
+
+	let slot_windows = (0..num_covers).map(|index| {
+		let mut cover_window = Window::new(
+			Some((cover_updater_fn, UpdateRate::ONCE_PER_FRAME)),
+
+			DynamicOptional::new(SpinHistoryCoverState {
+				index,
+				maybe_shown_spin_id: None,
+				maybe_animation_start: None,
+				animation_duration
+			}),
+
+			WindowContents::Nothing,
+			maybe_cover_border_color,
+			Vec2f::ZERO,
+			Vec2f::ONE,
+			None
+		);
+
+		cover_window.set_draw_skipping(true);
+
+		Window::new(
+			None,
+			DynamicOptional::NONE,
+			WindowContents::Nothing,
+			None,
+			Vec2f::new(index as f32 * slot_width + slot_width * cover_padding, cover_padding),
+			Vec2f::new(slot_width * (1.0 - cover_padding * 2.0), 1.0 - cover_padding * 2.0),
+			Some(vec![cover_window])
+		)
+	}).collect();
+
+	Window::new(
+		None,
+		DynamicOptional::NONE,
+		WindowContents::Nothing,
+		None,
+		top_left,
+		size,
+		Some(slot_windows)
+	)
+}
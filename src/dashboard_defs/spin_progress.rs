@@ -0,0 +1,172 @@
+use std::borrow::Cow;
+
+use crate::{
+	utility_types::{
+		vec2f::Vec2f,
+		generic_result::*,
+		update_rate::UpdateRate,
+		dynamic_optional::DynamicOptional
+	},
+
+	window_tree::{
+		Window,
+		ColorSDL,
+		FrameTime,
+		WindowContents,
+		WindowUpdaterParams
+	},
+
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
+};
+
+/* This shows how far along the current spin is, as a bar (with the elapsed part filled in),
+and as text under it (like "2:13 / 3:45"). It is updated every frame from the frame time,
+and it is hidden while connecting to Spinitron, and while the current spin is expired. */
+
+// This returns the elapsed time and duration of the current spin, or `None` if no progress should be shown
+fn get_spin_progress(shared_window_state: &DynamicOptional, curr_time: FrameTime) -> Option<(chrono::Duration, chrono::Duration)> {
+	let spinitron_state = &shared_window_state.get::<SharedWindowState>().spinitron_state;
+
+	if spinitron_state.is_connecting() || spinitron_state.spin_is_expired() {
+		return None;
+	}
+
+	match spinitron_state.get_spin().get_progress(curr_time.into()) {
+		Ok((_, duration)) if duration.is_zero() => None,
+		Ok(progress) => Some(progress),
+
+		Err(err) => {
+			log::warn!("Could not get the progress of the current spin: '{err}'.");
+			None
+		}
+	}
+}
+
+// This formats a duration like "2:13" (longer spins just have more minutes)
+fn format_duration(duration: chrono::Duration) -> String {
+	let secs = duration.num_seconds();
+	format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+//////////
+
+fn make_progress_bar_window(top_left: Vec2f, size: Vec2f, elapsed_color: ColorSDL, remaining_color: ColorSDL) -> Window {
+	fn background_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+		let maybe_progress = get_spin_progress(params.shared_window_state, params.curr_time);
+		params.window.set_draw_skipping(maybe_progress.is_none());
+		Ok(())
+	}
+
+	// The elapsed part of the bar is stretched horizontally to match the progress
+	fn elapsed_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+		let maybe_progress = get_spin_progress(params.shared_window_state, params.curr_time);
+		params.window.set_draw_skipping(maybe_progress.is_none());
+
+		if let Some((elapsed, duration)) = maybe_progress {
+			let fraction = elapsed.num_milliseconds() as f32 / duration.num_milliseconds() as f32;
+			params.window.set_size(Vec2f::new(fraction, 1.0));
+		}
+
+		Ok(())
+	}
+
+	let elapsed_window = Window::new(
+		Some((elapsed_updater_fn, UpdateRate::ONCE_PER_FRAME)),
+		DynamicOptional::NONE,
+		WindowContents::Color(elapsed_color),
+		None,
+		Vec2f::ZERO,
+		Vec2f::new(0.0, 1.0),
+		None
+	);
+
+	Window::new(
+		Some((background_updater_fn, UpdateRate::ONCE_PER_FRAME)),
+		DynamicOptional::NONE,
+		WindowContents::Color(remaining_color),
+		None,
+		top_left,
+		size,
+		Some(vec![elapsed_window])
+	)
+}
+
+fn make_progress_text_window(top_left: Vec2f, size: Vec2f, text_color: ColorSDL) -> Window {
+	// This is the text that was last shown (if any)
+	#[derive(Clone)]
+	struct ProgressTextWindowState(Option<String>);
+
+	impl updatable_text_pattern::UpdatableTextWindowMethods for ProgressTextWindowState {
+		// The text texture is only remade when the text changes (which is about once a second)
+		fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+			let curr_text = get_spin_progress(updater_params.shared_window_state, updater_params.curr_time)
+				.map(|(elapsed, duration)| format!("{} / {}", format_duration(elapsed), format_duration(duration)));
+
+			let wrapped_individual_state = updater_params.window.get_state_mut
+				::<updatable_text_pattern::UpdatableTextWindowFields<ProgressTextWindowState>>();
+
+			if curr_text == wrapped_individual_state.inner.0 {
+				return true;
+			}
+
+			let skip_update = curr_text.is_none();
+			wrapped_individual_state.inner.0 = curr_text;
+			updater_params.window.set_draw_skipping(skip_update);
+			skip_update
+		}
+
+		fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+			(Cow::Borrowed(inner_shared_state.font_info), "")
+		}
+
+		fn extract_text(&self) -> Cow<'_, str> {
+			Cow::Borrowed(self.0.as_ref().unwrap())
+		}
+
+		fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+			window_contents
+		}
+	}
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: ProgressTextWindowState(None),
+		text_color,
+		scroll_fn: |_, _| (0.0, false),
+		update_rate: UpdateRate::ONCE_PER_FRAME,
+		maybe_border_color: None
+	};
+
+	let mut window = updatable_text_pattern::make_window(fields, top_left, size, WindowContents::Nothing);
+	window.set_draw_skipping(true);
+	window
+}
+
+//////////
+
+// The bar takes up the top part of the window (given by `bar_height`), and the text takes up the rest
+pub fn make_spin_progress_window(top_left: Vec2f, size: Vec2f, bar_height: f32,
+	elapsed_color: ColorSDL, remaining_color: ColorSDL, text_color: ColorSDL) -> Window {
+
+	let bar_window = make_progress_bar_window(
+		Vec2f::ZERO, Vec2f::new(1.0, bar_height),
+		elapsed_color, remaining_color
+	);
+
+	let text_window = make_progress_text_window(
+		Vec2f::new(0.0, bar_height), Vec2f::new(1.0, 1.0 - bar_height),
+		text_color
+	);
+
+	Window::new(
+		None,
+		DynamicOptional::NONE,
+		WindowContents::Nothing,
+		None,
+		top_left,
+		size,
+		Some(vec![bar_window, text_window])
+	)
+}
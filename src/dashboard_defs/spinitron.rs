@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use crate::{
 	dashboard_defs::shared_window_state::SharedWindowState,
 
-	spinitron::model::{Spin, SpinitronModelName, NUM_SPINITRON_MODEL_TYPES},
+	spinitron::model::{Spin, SpinitronModelName},
 
 	texture::{
 		DisplayText,
@@ -22,8 +22,7 @@ use crate::{
 		Window,
 		ColorSDL,
 		WindowContents,
-		WindowUpdaterParams,
-		PossibleWindowUpdater
+		WindowUpdaterParams
 	}
 };
 
@@ -32,24 +31,15 @@ struct SpinitronModelWindowState {
 	maybe_text_color: Option<ColorSDL> // If this is `None`, it is not a text window
 }
 
-pub struct SpinitronModelWindowInfo {
-	pub tl: Vec2f,
-	pub size: Vec2f,
-	pub border_color: Option<ColorSDL>
-}
-
-pub struct SpinitronModelWindowsInfo {
-	pub model_name: SpinitronModelName,
-	pub texture_window: Option<SpinitronModelWindowInfo>,
-	pub text_window: Option<SpinitronModelWindowInfo>,
-	pub text_color: ColorSDL
-}
-
 //////////
 
-pub fn make_spinitron_windows(
-	all_model_windows_info: &[SpinitronModelWindowsInfo; NUM_SPINITRON_MODEL_TYPES],
-	model_update_rate: UpdateRate) -> Vec<Window> {
+// If a text color is passed in, this makes a text window for the model; otherwise, it makes a texture window.
+pub fn make_spinitron_model_window(
+	model_name: SpinitronModelName,
+	maybe_text_color: Option<ColorSDL>,
+	top_left: Vec2f, size: Vec2f,
+	maybe_border_color: Option<ColorSDL>,
+	model_update_rate: UpdateRate) -> Window {
 
 	/* Note: the drawn size passed into this does not account for aspect ratio correction.
 	For Spinitron models, the size is only needed for spin textures all and text textures.
@@ -75,9 +65,15 @@ pub fn make_spinitron_windows(
 		//////////
 
 		let texture_creation_info = if let Some(text_color) = individual_window_state.maybe_text_color {
-			let text = if spinitron_state.is_spin_and_just_expired(model_name) {
+			let text = if spinitron_state.is_connecting() {
+				Cow::Borrowed(Spin::to_string_when_connecting())
+			}
+			else if spinitron_state.is_spin_and_just_expired(model_name) {
 				Cow::Borrowed(Spin::to_string_when_spin_is_expired())
 			}
+			else if spinitron_state.is_showing_stale_cache() {
+				Cow::Owned(format!("{} (last known)", spinitron_state.get_model_by_name(model_name).to_string()))
+			}
 			else {
 				Cow::Owned(spinitron_state.get_model_by_name(model_name).to_string())
 			};
@@ -118,38 +114,15 @@ pub fn make_spinitron_windows(
 		)
 	}
 
-	////////// Making the model windows
-
-	let spinitron_model_window_updater: PossibleWindowUpdater = Some((spinitron_model_window_updater_fn, model_update_rate));
-
-	// TODO: perhaps for making multiple model windows, allow for an option to have sub-model-windows
-	all_model_windows_info.iter().flat_map(|general_info| {
-		let mut output_windows = Vec::new();
-
-		let mut maybe_make_model_window =
-			|maybe_info: &Option<SpinitronModelWindowInfo>, maybe_text_color: Option<ColorSDL>| {
-
-			if let Some(info) = maybe_info {
-				output_windows.push(Window::new(
-					spinitron_model_window_updater,
-
-					DynamicOptional::new(SpinitronModelWindowState {
-						model_name: general_info.model_name,
-						maybe_text_color
-					}),
-
-					WindowContents::Nothing,
-					info.border_color,
-					info.tl,
-					info.size,
-					None
-				));
-			}
-		};
-
-		maybe_make_model_window(&general_info.texture_window, None);
-		maybe_make_model_window(&general_info.text_window, Some(general_info.text_color));
-
-		output_windows
-	}).collect()
+	////////// Making the model window
+
+	Window::new(
+		Some((spinitron_model_window_updater_fn, model_update_rate)),
+		DynamicOptional::new(SpinitronModelWindowState {model_name, maybe_text_color}),
+		WindowContents::Nothing,
+		maybe_border_color,
+		top_left,
+		size,
+		None
+	)
 }
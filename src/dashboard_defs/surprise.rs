@@ -9,10 +9,6 @@ use std::{
 use chrono::Timelike;
 
 use interprocess::local_socket::{
-	ToFsName,
-	GenericFilePath,
-	ListenerOptions,
-	traits::Listener,
 	ListenerNonblockingMode,
 	prelude::LocalSocketListener
 };
@@ -25,6 +21,7 @@ use crate::{
 	},
 
 	utility_types::{
+		command_socket,
 		generic_result::*,
 		dynamic_optional::DynamicOptional,
 		vec2f::{Vec2f, assert_in_unit_interval},
@@ -191,22 +188,9 @@ pub fn make_surprise_window(
 
 	const SURPRISE_STREAM_PATH_BUFFER_INITIAL_SIZE: usize = 64;
 
-	let options = ListenerOptions::new().name(artificial_triggering_socket_path.to_fs_name::<GenericFilePath>()?);
-
-	let surprise_stream_listener = match options.create_sync() {
-		Ok(listener) => listener,
-
-		Err(err) => {
-			return error_msg!(
-				"Could not create a surprise stream listener. \
-				Perhaps the socket at '{artificial_triggering_socket_path}' is already in use, or \
-				maybe it was still around from a crash? \
-				Official error: '{err}'."
-			);
-		}
-	};
-
-	surprise_stream_listener.set_nonblocking(ListenerNonblockingMode::Both)?;
+	// See `command_socket.rs` for how the socket is made
+	let surprise_stream_listener = command_socket::make_listener(artificial_triggering_socket_path,
+		"surprise stream listener", ListenerNonblockingMode::Both)?;
 
 	let shared_surprise_info = Rc::new(RefCell::new(SharedSurpriseInfo {
 		surprise_path_set,
@@ -1,5 +1,5 @@
 use chrono::DateTime;
-use std::{sync::Arc, borrow::Cow, collections::HashMap};
+use std::{sync::{Arc, Mutex}, borrow::Cow, collections::{HashMap, HashSet}};
 
 use crate::{
 	request,
@@ -12,8 +12,15 @@ use crate::{
 		thread_task::{ContinuallyUpdated, Updatable}
 	},
 
-	dashboard_defs::shared_window_state::SharedWindowState,
-	window_tree::{ColorSDL, Window, WindowContents, WindowUpdaterParams},
+	dashboard_defs::{
+		shared_window_state::SharedWindowState,
+		twilio_moderation::{TwilioModerator, TwilioModerationConfig},
+		twilio_cleanup::{TwilioCleanup, TwilioCleanupConfig},
+		twilio_replies::{TwilioReplier, TwilioReplyConfig, ReplyTarget},
+		twilio_webhook::{TwilioWebhook, TwilioWebhookConfig}
+	},
+
+	window_tree::{ColorSDL, Window, FrameTime, WindowContents, WindowUpdaterParams},
 	texture::{FontInfo, DisplayText, TextDisplayInfo, TextureCreationInfo, TextureHandle, TexturePool}
 };
 
@@ -42,8 +49,9 @@ impl TextureSubpoolManager {
 			for (texture, is_used) in &mut self.subpool {
 				if !*is_used {
 					// println!("(request) doing re-request, and setting {:?} to used", texture);
-					*is_used = true;
+					// This is only marked as used once remaking succeeds, so that a failed request can be retried with other info
 					texture_pool.remake_texture(texture_creation_info, texture)?;
+					*is_used = true;
 					return Ok(texture.clone());
 				}
 			}
@@ -95,7 +103,7 @@ impl TextureSubpoolManager {
 
 //////////
 
-type MessageID = Arc<str>;
+pub type MessageID = Arc<str>;
 
 enum SyncedMessageMapAction<'a, V, OffshoreV> {
 	ExpireLocal(&'a V),
@@ -163,30 +171,73 @@ impl<V> SyncedMessageMap<V> {
 
 //////////
 
-// TODO: support texter blocking somehow (this code may turn out ugly to write; make it still work without the connected peripheral)
-
 type Timezone = chrono::Utc; // This should not be changed (Twilio uses UTC by default)
 type Timestamp = chrono::DateTime<Timezone>; // It seems like local time works too!
 type MessageAgeData = Option<(&'static str, &'static str, i64)>;
 
-// TODO: should/could I include caller ID, and an image, if sent?
+pub const TWILIO_API_BASE_URL: &str = "https://api.twilio.com";
+
+// The polled page is this many times the history size (so that a few replies in between texts don't push any texts out)
+const POLL_PAGE_SIZE_MULTIPLIER: usize = 4;
+
+// This is shown for attachments that aren't images (and for images that could not be downloaded or loaded)
+const ATTACHMENT_PLACEHOLDER_PATH: &str = "assets/non_image_attachment.png";
+
+/* Only the first image of a message is shown (and the image bytes
+are in an `Arc`, so that they are not copied during the continual updating). */
+#[derive(Clone)]
+enum MessageAttachment {
+	Image(Arc<Vec<u8>>),
+	Placeholder
+}
+
+// This is a message from Twilio, before it's made into a `MessageInfo`
+struct IncomingMessage<'a> {
+	maybe_from: Option<&'a str>, // This is `None` if the message identity is hidden
+	sender_number: &'a str,
+	station_number: &'a str,
+	body: Cow<'a, str>,
+	time_sent: DateTime<chrono::FixedOffset>,
+	time_loaded_by_app: Timestamp,
+	maybe_media_list_uri: Option<&'a str> // This is `None` if there is no media
+}
+
+// TODO: should/could I include caller ID?
 #[derive(Clone)]
 struct MessageInfo {
 	age_data: MessageAgeData,
 	display_text: String,
 	maybe_from: Option<String>, // This is `None` if the message identity is hidden
 	body: String, // TODO: trim and preceding or trailing whitespace
+	sender_number: String, // Unlike `maybe_from`, this is always set (it's used for replying)
+	station_number: String,
 	time_sent: Timestamp,
 	time_loaded_by_app: Timestamp, // This includes sub-second precision, while the time sent above does not
+	maybe_attachment: Option<MessageAttachment>,
 	just_updated: bool
 }
 
+// Each of these optional features is off when its config is `None`
+#[derive(Clone, Copy)]
+pub struct TwilioFeatureConfigs<'a> {
+	pub maybe_moderation: Option<&'a TwilioModerationConfig>,
+	pub maybe_cleanup: Option<&'a TwilioCleanupConfig>,
+	pub maybe_replies: Option<&'a TwilioReplyConfig>,
+	pub maybe_webhook: Option<&'a TwilioWebhookConfig>
+}
+
 struct ImmutableTwilioStateData {
 	account_sid: String,
 	request_auth: String,
 	max_num_messages_in_history: usize,
 	message_history_duration: chrono::Duration,
-	reveal_texter_identities: bool
+	reveal_texter_identities: bool,
+
+	// Messages are moderated before they're synced (so that hidden and held ones never reach the local message map)
+	maybe_moderator: Option<Arc<Mutex<TwilioModerator>>>,
+
+	// With a webhook, messages come in right away, and polling only happens now and then (to reconcile)
+	maybe_webhook: Option<TwilioWebhook>
 }
 
 #[derive(Clone)]
@@ -195,7 +246,14 @@ struct TwilioStateData {
 	immutable: Arc<ImmutableTwilioStateData>,
 
 	// Mutable fields:
-	curr_messages: SyncedMessageMap<MessageInfo>
+	curr_messages: SyncedMessageMap<MessageInfo>,
+	last_polled_messages: Arc<Vec<serde_json::Value>>, // These are newest first
+	next_poll_time: Timestamp
+}
+
+struct MessageTextures {
+	text: TextureHandle,
+	maybe_thumbnail: Option<TextureHandle>
 }
 
 // TODO: put the non-continually-updated fields in their own struct
@@ -208,37 +266,54 @@ pub struct TwilioState<'a> {
 	made by the creator of the continually updated object will be overwritten with all
 	newly computed data. */
 	texture_subpool_manager: TextureSubpoolManager,
-	id_to_texture_map: SyncedMessageMap<TextureHandle>, // TODO: integrate the subpool manager into this with the searching operations
+	thumbnail_texture_subpool_manager: TextureSubpoolManager,
+	id_to_texture_map: SyncedMessageMap<MessageTextures>, // TODO: integrate the subpool managers into this with the searching operations
 	historically_sorted_messages_by_id: Vec<MessageID>, // TODO: avoid resorting with smart insertions and deletions?
-	text_texture_creation_info_cache: Option<((u32, u32), &'a FontInfo, ColorSDL)>
+	text_texture_creation_info_cache: Option<((u32, u32), &'a FontInfo, ColorSDL)>,
+
+	// Old messages are deleted on a schedule, on a separate thread from the message fetching
+	maybe_cleanup: Option<TwilioCleanup>,
+
+	// Replies are sent from the replier's own command listener thread
+	maybe_replier: Option<TwilioReplier>
 }
 
 //////////
 
+fn make_request_auth(account_sid: &str, auth_token: &str) -> String {
+	use base64::{engine::general_purpose::STANDARD, Engine};
+	"Basic ".to_string() + &STANDARD.encode(format!("{account_sid}:{auth_token}"))
+}
+
 impl TwilioStateData {
 	fn new(account_sid: &str, auth_token: &str,
 		max_num_messages_in_history: usize,
 		message_history_duration: chrono::Duration,
-		reveal_texter_identities: bool) -> Self {
+		reveal_texter_identities: bool,
+		feature_configs: TwilioFeatureConfigs) -> GenericResult<Self> {
 
-		use base64::{engine::general_purpose::STANDARD, Engine};
-		let request_auth_base64 = STANDARD.encode(format!("{account_sid}:{auth_token}"));
-
-		Self {
+		Ok(Self {
 			immutable: Arc::new(ImmutableTwilioStateData {
 				account_sid: account_sid.to_string(),
-				request_auth: "Basic ".to_string() + &request_auth_base64,
+				request_auth: make_request_auth(account_sid, auth_token),
 				max_num_messages_in_history,
 				message_history_duration,
-				reveal_texter_identities
+				reveal_texter_identities,
+				maybe_moderator: feature_configs.maybe_moderation.map(TwilioModerator::new).transpose()?,
+
+				maybe_webhook: feature_configs.maybe_webhook.map(|webhook_config|
+					TwilioWebhook::new(webhook_config, account_sid, auth_token)
+				).transpose()?
 			}),
 
-			curr_messages: SyncedMessageMap::new(max_num_messages_in_history)
-		}
+			curr_messages: SyncedMessageMap::new(max_num_messages_in_history),
+			last_polled_messages: Arc::new(Vec::new()),
+			next_poll_time: Timezone::now()
+		})
 	}
 
 	fn do_twilio_request(&self, endpoint: &str, path_params: &[Cow<str>], query_params: &[(&str, Cow<str>)]) -> GenericResult<serde_json::Value> {
-		let base_url = format!("https://api.twilio.com/2010-04-01/Accounts/{}/{endpoint}.json", self.immutable.account_sid);
+		let base_url = format!("{TWILIO_API_BASE_URL}/2010-04-01/Accounts/{}/{endpoint}.json", self.immutable.account_sid);
 		let request_url = request::build_url(&base_url, path_params, query_params);
 
 		request::as_type(request::get_with_maybe_header(
@@ -247,6 +322,32 @@ impl TwilioStateData {
 		))
 	}
 
+	// This downloads the first image attached to a message (the media list URI is relative to the API base URL)
+	fn get_attachment(immutable: &ImmutableTwilioStateData, media_list_uri: &str) -> GenericResult<MessageAttachment> {
+		let auth_header = Some(("Authorization", immutable.request_auth.as_str()));
+
+		let json: serde_json::Value = request::as_type(
+			request::get_with_maybe_header(&format!("{TWILIO_API_BASE_URL}{media_list_uri}"), auth_header)
+		)?;
+
+		let media_list = json["media_list"].as_array().context("Expected the Twilio media list to be an array")?;
+
+		let maybe_image = media_list.iter().find(|media|
+			media["content_type"].as_str().is_some_and(|content_type| content_type.starts_with("image/"))
+		);
+
+		let Some(image) = maybe_image else {
+			return Ok(MessageAttachment::Placeholder);
+		};
+
+		// The media's URI is for its metadata, and the URI without the extension is for its contents
+		let metadata_uri = image["uri"].as_str().context("Expected the Twilio media URI to be a string")?;
+		let contents_url = format!("{TWILIO_API_BASE_URL}{}", metadata_uri.trim_end_matches(".json"));
+
+		let bytes = request::get_with_maybe_header(&contents_url, auth_header)?.into_bytes();
+		Ok(MessageAttachment::Image(Arc::new(bytes)))
+	}
+
 	//////////
 
 	fn get_message_age_data(curr_time: Timestamp, time_sent: Timestamp) -> MessageAgeData {
@@ -322,56 +423,109 @@ impl Updatable for TwilioStateData {
 
 		let max_messages = self.immutable.max_num_messages_in_history;
 
-		let json = self.do_twilio_request("Messages", &[],
-			&[
-				("PageSize", Cow::Borrowed(&max_messages.to_string())),
-				("DateSent%3E", Cow::Borrowed(&history_cutoff_day.to_string())) // Note: the '%3E' is a URL-encoded '>'
-			]
-		)?;
+		// More messages are polled than are shown, since replies are in the list too (and they're left out below)
+		let poll_page_size = max_messages * POLL_PAGE_SIZE_MULTIPLIER;
+		let maybe_webhook = self.immutable.maybe_webhook.as_ref();
+
+		if maybe_webhook.is_none() || curr_time >= self.next_poll_time {
+			// If this poll fails, the next one still waits for the reconciliation interval (the webhook keeps working until then)
+			if let Some(webhook) = maybe_webhook {
+				self.next_poll_time = curr_time + webhook.get_reconciliation_interval();
+			}
+
+			let mut json = self.do_twilio_request("Messages", &[],
+				&[
+					("PageSize", Cow::Borrowed(&poll_page_size.to_string())),
+					("DateSent%3E", Cow::Borrowed(&history_cutoff_day.to_string())) // Note: the '%3E' is a URL-encoded '>'
+				]
+			)?;
+
+			self.last_polled_messages = Arc::new(std::mem::take(json["messages"].as_array_mut().unwrap()));
+		}
 
 		////////// Creating a map of incoming messages
 
-		// This will always be in the range of 0 <= num_messages <= self.num_messages_in_history
-		let json_messages = json["messages"].as_array().unwrap();
+		// This will always be in the range of 0 <= num_messages <= poll_page_size
+		let json_messages = match maybe_webhook {
+			Some(webhook) => Cow::Owned(webhook.merge_with_polled_messages(&self.last_polled_messages, poll_page_size)),
+			None => Cow::Borrowed(self.last_polled_messages.as_slice())
+		};
 
-		let incoming_message_map = HashMap::from_iter(
-			json_messages.iter().filter_map(|message| {
-				let message_field = |name| message[name].as_str().unwrap();
+		let mut maybe_moderator = self.immutable.maybe_moderator.as_ref().map(|moderator| moderator.lock().unwrap());
+		let mut seen_message_ids = HashSet::new();
 
+		let incoming_message_map: HashMap<MessageID, IncomingMessage> = HashMap::from_iter(
+			json_messages.iter().filter_map(|message| {
 				// Using the date created instead, since it is never null at the beginning (unlike the date sent)
-				let unparsed_time_sent = message_field("date_created");
+				let unparsed_time_sent = message["date_created"].as_str().unwrap();
 				let time_sent = DateTime::parse_from_rfc2822(unparsed_time_sent).unwrap();
 
+				// Replies from the station are outbound, so they're not shown
+				let is_outbound = message["direction"].as_str().unwrap().starts_with("outbound");
+
 				// TODO: see that the manual date filtering logic works
-				if time_sent >= history_cutoff_time {
-					let id = message_field("uri");
+				(time_sent >= history_cutoff_time && !is_outbound).then_some((message, time_sent))
+			})
+			.filter_map(|(message, time_sent)| {
+				let message_field = |name| message[name].as_str().unwrap();
 
-					// If a key on the heap already existed, reuse it
-					let (id_on_heap, time_loaded_by_app) =
-						if let Some((already_id, already_message)) = self.curr_messages.map.get_key_value(id) {
-							(already_id.clone(), already_message.time_loaded_by_app)
-						}
-						else {
-							(id.into(), Timezone::now())
-						};
+				let id = message_field("uri");
 
-					let maybe_from = if self.immutable.reveal_texter_identities {
-						Some(message_field("from"))
+				// If a key on the heap already existed, reuse it
+				let (id_on_heap, time_loaded_by_app) =
+					if let Some((already_id, already_message)) = self.curr_messages.map.get_key_value(id) {
+						(already_id.clone(), already_message.time_loaded_by_app)
 					}
 					else {
-						None
+						(id.into(), Timezone::now())
 					};
 
-					Some((id_on_heap, (maybe_from, message_field("body"), time_sent, time_loaded_by_app)))
+				// Hidden and held messages are left out here (and masked ones are shown with their masked body)
+				let body = match &mut maybe_moderator {
+					Some(moderator) => {
+						seen_message_ids.insert(id_on_heap.clone());
+						Cow::Owned(moderator.moderate(&id_on_heap, message_field("from"), message_field("body"))?)
+					},
+
+					None => Cow::Borrowed(message_field("body"))
+				};
+
+				let maybe_from = if self.immutable.reveal_texter_identities {
+					Some(message_field("from"))
 				}
 				else {
 					None
-				}
+				};
+
+				// Twilio gives the number of media items as a string
+				let has_media = message["num_media"].as_str().is_some_and(|num_media| num_media != "0");
+				let maybe_media_list_uri = message["subresource_uris"]["media"].as_str().filter(|_| has_media);
+
+				Some((id_on_heap, IncomingMessage {
+					maybe_from,
+					sender_number: message_field("from"),
+					station_number: message_field("to"),
+					body,
+					time_sent,
+					time_loaded_by_app,
+					maybe_media_list_uri
+				}))
 			})
+			/* This is after the filtering (of outbound messages above, and hidden or held messages just before),
+			so that neither replies nor moderated messages take up any of the history. */
+			.take(max_messages)
 		);
 
+		if let Some(moderator) = &mut maybe_moderator {
+			moderator.forget_unseen_messages(&seen_message_ids);
+		}
+
+		drop(maybe_moderator);
+
 		//////////
 
+		let immutable = &self.immutable;
+
 		self.curr_messages.sync(
 			max_messages,
 			&SyncedMessageMap::from(incoming_message_map, max_messages),
@@ -395,19 +549,30 @@ impl Updatable for TwilioStateData {
 						}
 					},
 
-					SyncedMessageMapAction::MakeLocalFromOffshore((maybe_from, body, wrongly_typed_time_sent, time_loaded_by_app)) => {
-						let time_sent = (*wrongly_typed_time_sent).into();
+					SyncedMessageMapAction::MakeLocalFromOffshore(incoming) => {
+						let time_sent = incoming.time_sent.into();
 						let age_data = Self::get_message_age_data(curr_time, time_sent);
 
-						let boxed_maybe_from = maybe_from.map(|from| from.to_string());
+						let boxed_maybe_from = incoming.maybe_from.map(|from| from.to_string());
+
+						// Attachments are only downloaded once, when the message first comes in
+						let maybe_attachment = incoming.maybe_media_list_uri.map(|media_list_uri| {
+							Self::get_attachment(immutable, media_list_uri).unwrap_or_else(|err| {
+								log::warn!("Could not get the attachment for a Twilio message, so showing a placeholder: '{err}'.");
+								MessageAttachment::Placeholder
+							})
+						});
 
 						return Ok(Some(MessageInfo {
 							age_data,
-							display_text: Self::make_message_display_text(age_data, body, *maybe_from),
+							display_text: Self::make_message_display_text(age_data, &incoming.body, incoming.maybe_from),
 							maybe_from: boxed_maybe_from,
-							body: body.to_string(),
+							body: incoming.body.to_string(),
+							sender_number: incoming.sender_number.to_string(),
+							station_number: incoming.station_number.to_string(),
 							time_sent,
-							time_loaded_by_app: *time_loaded_by_app,
+							time_loaded_by_app: incoming.time_loaded_by_app,
+							maybe_attachment,
 							just_updated: true
 						}));
 					}
@@ -426,31 +591,49 @@ impl TwilioState<'_> {
 		account_sid: &str, auth_token: &str,
 		max_num_messages_in_history: usize,
 		message_history_duration: chrono::Duration,
-		reveal_texter_identities: bool) -> Self {
+		reveal_texter_identities: bool,
+		feature_configs: TwilioFeatureConfigs) -> GenericResult<Self> {
+
+		let request_auth = make_request_auth(account_sid, auth_token);
 
 		let data = TwilioStateData::new(
 			account_sid, auth_token, max_num_messages_in_history,
-			message_history_duration, reveal_texter_identities
-		);
+			message_history_duration, reveal_texter_identities,
+			feature_configs
+		)?;
 
-		Self {
+		Ok(Self {
 			continually_updated: ContinuallyUpdated::new(&data, &(), "Twilio"),
 			texture_subpool_manager: TextureSubpoolManager::new(max_num_messages_in_history),
+			thumbnail_texture_subpool_manager: TextureSubpoolManager::new(max_num_messages_in_history),
 			id_to_texture_map: SyncedMessageMap::new(max_num_messages_in_history),
 			historically_sorted_messages_by_id: Vec::new(),
-			text_texture_creation_info_cache: None
-		}
+			text_texture_creation_info_cache: None,
+
+			maybe_cleanup: feature_configs.maybe_cleanup.map(|cleanup_config|
+				TwilioCleanup::new(cleanup_config, account_sid, &request_auth)
+			).transpose()?,
+
+			maybe_replier: feature_configs.maybe_replies.map(|reply_config|
+				TwilioReplier::new(reply_config, account_sid, &request_auth)
+			).transpose()?
+		})
 	}
 
 	// This returns false if something failed with the continual updater.
 	pub fn update(&mut self, texture_pool: &mut TexturePool) -> GenericResult<bool> {
+		let cleanup_succeeded = match &mut self.maybe_cleanup {
+			Some(cleanup) => cleanup.update()?,
+			None => true
+		};
+
 		// TODO: change other instances of `if-let` to this form
 		let Some((pixel_area, font_info, text_color)) = self.text_texture_creation_info_cache else {
 			// println!("It has not been cached yet, so wait for the next iteration");
-			return Ok(true);
+			return Ok(cleanup_succeeded);
 		};
 
-		let continual_updater_succeeded = self.continually_updated.update(&())?;
+		let continual_updater_succeeded = self.continually_updated.update(&())? && cleanup_succeeded;
 		let curr_continual_data = self.continually_updated.get_data();
 
 		let local = &mut self.id_to_texture_map;
@@ -487,21 +670,27 @@ impl TwilioState<'_> {
 				let mut update_texture_creation_info = |offshore_message_info: &MessageInfo| {
 					if let TextureCreationInfo::Text((_, ref mut text_display_info)) = &mut texture_creation_info {
 						// println!(">>> Update texture display info");
-						text_display_info.text = DisplayText::new(&offshore_message_info.display_text).with_padding("", " ")
+						text_display_info.text = DisplayText::new(&offshore_message_info.display_text).with_padding("", " ");
+						text_display_info.pixel_area = get_text_pixel_area(pixel_area, offshore_message_info.maybe_attachment.is_some());
 					}
 				};
 
 				match action_type {
-					SyncedMessageMapAction::ExpireLocal(local_texture) => {
+					SyncedMessageMapAction::ExpireLocal(local_textures) => {
 						// println!(">>> Give texture slot back");
-						self.texture_subpool_manager.give_back_slot(local_texture);
+						self.texture_subpool_manager.give_back_slot(&local_textures.text);
+
+						if let Some(thumbnail) = &local_textures.maybe_thumbnail {
+							self.thumbnail_texture_subpool_manager.give_back_slot(thumbnail);
+						}
 					},
 
-					SyncedMessageMapAction::MaybeUpdateLocal(local_texture, offshore_message_info) => {
+					SyncedMessageMapAction::MaybeUpdateLocal(local_textures, offshore_message_info) => {
+						// Attachments never change, so only the text is remade here
 						if offshore_message_info.just_updated {
 							// println!(">>> Update local texture");
 							update_texture_creation_info(offshore_message_info);
-							self.texture_subpool_manager.re_request_slot(local_texture, &texture_creation_info, texture_pool)?;
+							self.texture_subpool_manager.re_request_slot(&local_textures.text, &texture_creation_info, texture_pool)?;
 						}
 					},
 
@@ -509,7 +698,25 @@ impl TwilioState<'_> {
 						// println!(">>> Allocate texture from base slot");
 						assert!(offshore_message_info.just_updated);
 						update_texture_creation_info(offshore_message_info);
-						return Ok(Some(self.texture_subpool_manager.request_slot(&texture_creation_info, texture_pool)?));
+
+						let text = self.texture_subpool_manager.request_slot(&texture_creation_info, texture_pool)?;
+
+						let maybe_thumbnail = offshore_message_info.maybe_attachment.as_ref().map(|attachment| {
+							let placeholder_creation_info = TextureCreationInfo::Path(Cow::Borrowed(ATTACHMENT_PLACEHOLDER_PATH));
+							let manager = &mut self.thumbnail_texture_subpool_manager;
+
+							let MessageAttachment::Image(bytes) = attachment else {
+								return manager.request_slot(&placeholder_creation_info, texture_pool);
+							};
+
+							// Some image formats (like HEIC) can't be loaded, so those get the placeholder too
+							manager.request_slot(&TextureCreationInfo::RawBytes(bytes), texture_pool).or_else(|err| {
+								log::warn!("Could not load an image attached to a Twilio message, so showing a placeholder: '{err}'.");
+								manager.request_slot(&placeholder_creation_info, texture_pool)
+							})
+						}).transpose()?;
+
+						return Ok(Some(MessageTextures {text, maybe_thumbnail}));
 					}
 				}
 
@@ -539,64 +746,118 @@ impl TwilioState<'_> {
 
 		assert!(self.historically_sorted_messages_by_id.len() == local.map.len());
 
+		// The replier lists messages in the order of the history
+		if let Some(replier) = &self.maybe_replier {
+			replier.set_targets(self.historically_sorted_messages_by_id.iter().map(|message_id| {
+				let message = &offshore.map[message_id];
+
+				ReplyTarget {
+					message_id: message_id.clone(),
+					sender_number: message.sender_number.clone(),
+					station_number: message.station_number.clone(),
+					body: message.body.clone()
+				}
+			}).collect());
+		}
+
 		Ok(continual_updater_succeeded)
 	}
 }
 
 //////////
 
-pub fn make_twilio_window(
-	twilio_state: &TwilioState,
-	update_rate: UpdateRate,
-	top_left: Vec2f, size: Vec2f,
-	top_box_height: f32,
-	top_box_contents: WindowContents,
-	message_background_contents_text_crop_factor: Vec2f,
-	overall_border_color: ColorSDL, text_color: ColorSDL,
-	message_background_contents: WindowContents) -> Window {
+// Thumbnails are square (so they're as wide as the text is tall), but they never take up more than half of a message's width
+fn get_thumbnail_width_fraction((width, height): (u32, u32)) -> f32 {
+	(height as f32 / width as f32).min(0.5)
+}
 
-	struct TwilioHistoryWindowState {
-		message_index: usize,
-		text_color: ColorSDL
+// Messages with attachments have their text moved over to make room for the thumbnail
+fn get_text_pixel_area(full_pixel_area: (u32, u32), has_attachment: bool) -> (u32, u32) {
+	if !has_attachment {
+		return full_pixel_area;
 	}
 
-	////////// Making a series of history windows
+	let text_width_fraction = 1.0 - get_thumbnail_width_fraction(full_pixel_area);
+	((full_pixel_area.0 as f32 * text_width_fraction) as u32, full_pixel_area.1)
+}
 
-	let max_num_messages_in_history = twilio_state.continually_updated.get_data().immutable.max_num_messages_in_history;
+struct TwilioHistoryWindowState {
+	message_index: usize,
+	text_color: ColorSDL
+}
 
-	fn history_updater_fn(params: WindowUpdaterParams) -> MaybeError {
-		let inner_shared_state = params.shared_window_state.get_mut::<SharedWindowState>();
-		let twilio_state = &mut inner_shared_state.twilio_state;
-		let individual_window_state = params.window.get_state::<TwilioHistoryWindowState>();
-		let sorted_message_ids = &twilio_state.historically_sorted_messages_by_id;
-
-		// Filling the text texture creation info cache
-		if twilio_state.text_texture_creation_info_cache.is_none() {
-			twilio_state.text_texture_creation_info_cache = Some((
-				params.area_drawn_to_screen,
-				inner_shared_state.font_info,
-				individual_window_state.text_color
-			));
-		}
+fn history_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+	let inner_shared_state = params.shared_window_state.get_mut::<SharedWindowState>();
+	let twilio_state = &mut inner_shared_state.twilio_state;
+	let individual_window_state = params.window.get_state::<TwilioHistoryWindowState>();
+	let sorted_message_ids = &twilio_state.historically_sorted_messages_by_id;
+
+	// Filling the text texture creation info cache
+	if twilio_state.text_texture_creation_info_cache.is_none() {
+		twilio_state.text_texture_creation_info_cache = Some((
+			params.area_drawn_to_screen,
+			inner_shared_state.font_info,
+			individual_window_state.text_color
+		));
+	}
 
-		// Then, possibly assigning a texture to the window contents
-		if individual_window_state.message_index < sorted_message_ids.len() {
-			let message_id = &sorted_message_ids[individual_window_state.message_index];
+	// Then, possibly assigning a texture to the window contents
+	if individual_window_state.message_index < sorted_message_ids.len() {
+		let message_id = &sorted_message_ids[individual_window_state.message_index];
 
-			// If this condition is not met, that means that the created texture is still pending
-			if let Some(message_texture) = twilio_state.id_to_texture_map.map.get(message_id) {
-				*params.window.get_contents_mut() = WindowContents::Texture(message_texture.clone());
-			}
-			else {
-				panic!("A message texture was not allocated when it should have been!");
-			}
+		// If this condition is not met, that means that the created texture is still pending
+		if let Some(message_textures) = twilio_state.id_to_texture_map.map.get(message_id) {
+			*params.window.get_contents_mut() = WindowContents::Texture(message_textures.text.clone());
+
+			// The text is moved over if there's a thumbnail to its left
+			let thumbnail_width_fraction = match (&message_textures.maybe_thumbnail, twilio_state.text_texture_creation_info_cache) {
+				(Some(_), Some((full_pixel_area, _, _))) => get_thumbnail_width_fraction(full_pixel_area),
+				_ => 0.0
+			};
+
+			params.window.set_top_left(Vec2f::new(thumbnail_width_fraction, 0.0));
+			params.window.set_size(Vec2f::new(1.0 - thumbnail_width_fraction, 1.0));
 		}
 		else {
-			*params.window.get_contents_mut() = WindowContents::Nothing;
+			panic!("A message texture was not allocated when it should have been!");
 		}
-
-		Ok(())
 	}
+	else {
+		*params.window.get_contents_mut() = WindowContents::Nothing;
+	}
+
+	Ok(())
+}
+
+fn thumbnail_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+	let twilio_state = &params.shared_window_state.get::<SharedWindowState>().twilio_state;
+	let message_index = params.window.get_state::<TwilioHistoryWindowState>().message_index;
+
+	let maybe_thumbnail = twilio_state.historically_sorted_messages_by_id.get(message_index)
+		.and_then(|message_id| twilio_state.id_to_texture_map.map.get(message_id))
+		.and_then(|message_textures| message_textures.maybe_thumbnail.as_ref());
+
+	let (Some(thumbnail), Some((full_pixel_area, _, _))) = (maybe_thumbnail, twilio_state.text_texture_creation_info_cache) else {
+		*params.window.get_contents_mut() = WindowContents::Nothing;
+		return Ok(());
+	};
+
+	*params.window.get_contents_mut() = WindowContents::Texture(thumbnail.clone());
+	params.window.set_size(Vec2f::new(get_thumbnail_width_fraction(full_pixel_area), 1.0));
+	Ok(())
+}
+
+/* This makes a series of history windows (with their message backgrounds), with the newest message at the top.
+Each one has a thumbnail window on its left, for the message's attachment (if it has one). The updaters are
+passed in so that the snapshot tests can lay out messages without a Twilio state. */
+fn make_history_windows(
+	max_num_messages_in_history: usize,
+	history_updater: fn(WindowUpdaterParams) -> MaybeError,
+	thumbnail_updater: fn(WindowUpdaterParams) -> MaybeError,
+	update_rate: UpdateRate,
+	message_background_contents_text_crop_factor: Vec2f,
+	text_color: ColorSDL,
+	message_background_contents: &WindowContents) -> Vec<Window> {
 
 	let (cropped_text_tl_in_history_window, cropped_text_size_in_history_window) = (
 		message_background_contents_text_crop_factor * Vec2f::new_scalar(0.5),
@@ -605,16 +866,37 @@ pub fn make_twilio_window(
 
 	let history_window_height = 1.0 / max_num_messages_in_history as f32;
 
-	let all_subwindows = (0..max_num_messages_in_history).rev().map(|i| {
-		// Note: I can't directly put the background contents into the history windows since it's sized differently
+	(0..max_num_messages_in_history).rev().map(|i| {
 		let history_window = Window::new(
-			Some((history_updater_fn, update_rate)),
+			Some((history_updater, update_rate)),
+			DynamicOptional::new(TwilioHistoryWindowState {message_index: i, text_color}),
+			WindowContents::Nothing,
+			None,
+			Vec2f::ZERO,
+			Vec2f::ONE,
+			None
+		);
+
+		// Its width is set by its updater
+		let thumbnail_window = Window::new(
+			Some((thumbnail_updater, update_rate)),
 			DynamicOptional::new(TwilioHistoryWindowState {message_index: i, text_color}),
 			WindowContents::Nothing,
 			None,
+			Vec2f::ZERO,
+			Vec2f::new(0.0, 1.0),
+			None
+		);
+
+		// Note: I can't directly put the background contents into this since it's sized differently
+		let cropped_window = Window::new(
+			None,
+			DynamicOptional::NONE,
+			WindowContents::Nothing,
+			None,
 			cropped_text_tl_in_history_window,
 			cropped_text_size_in_history_window,
-			None
+			Some(vec![thumbnail_window, history_window])
 		);
 
 		// This is just the history window with the background contents
@@ -625,21 +907,64 @@ pub fn make_twilio_window(
 			None,
 			Vec2f::new(0.0, history_window_height * i as f32),
 			Vec2f::new(1.0, history_window_height),
-			Some(vec![history_window])
+			Some(vec![cropped_window])
 		);
 
 		// Don't want to not stretch the message bubbles
 		with_background_contents.set_aspect_ratio_correction_skipping(true);
 
 		with_background_contents
-	}).collect();
+	}).collect()
+}
+
+pub fn make_twilio_window(
+	twilio_state: &TwilioState,
+	update_rate: UpdateRate,
+	top_left: Vec2f, size: Vec2f,
+	top_box_height: f32,
+	top_box_contents: WindowContents,
+	message_background_contents_text_crop_factor: Vec2f,
+	overall_border_color: ColorSDL, text_color: ColorSDL,
+	message_background_contents: WindowContents) -> Window {
+
+	let max_num_messages_in_history = twilio_state.continually_updated.get_data().immutable.max_num_messages_in_history;
+
+	let all_subwindows = make_history_windows(
+		max_num_messages_in_history, history_updater_fn, thumbnail_updater_fn, update_rate,
+		message_background_contents_text_crop_factor, text_color,
+		&message_background_contents
+	);
 
 	//////////
 
+	fn get_formatted_phone_number(twilio_state: &TwilioStateData) -> GenericResult<String> {
+		let json = twilio_state.do_twilio_request("IncomingPhoneNumbers", &[], &[])?;
+
+		let Some(phone_numbers) = json["incoming_phone_numbers"].as_array()
+		else {panic!("Expected the Twilio phone numbers to be an array!");};
+
+		assert!(phone_numbers.len() == 1);
+
+		let number = phone_numbers[0]["phone_number"].as_str().context("Expected the phone number to be a string!")?;
+		Ok(TwilioStateData::format_phone_number(number, "Messages to ", ":", ""))
+	}
+
+	struct TopBoxState {
+		text_color: ColorSDL,
+		maybe_next_phone_number_retry_time: Option<FrameTime>
+	}
+
 	fn top_box_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+		const SECONDS_BETWEEN_PHONE_NUMBER_RETRIES: i64 = 15;
+
 		let inner_shared_state = params.shared_window_state.get::<SharedWindowState>();
 		let twilio_state = inner_shared_state.twilio_state.continually_updated.get_data();
-		let text_color = *params.window.get_state::<ColorSDL>();
+		let top_box_state = params.window.get_state_mut::<TopBoxState>();
+		let text_color = top_box_state.text_color;
+
+		if top_box_state.maybe_next_phone_number_retry_time.is_some_and(|retry_time| params.curr_time < retry_time) {
+			return Ok(());
+		}
 
 		let WindowContents::Many(many) = params.window.get_contents_mut()
 		else {panic!("The top box for Twilio did not contain a vec of contents!");};
@@ -647,15 +972,19 @@ pub fn make_twilio_window(
 		if let WindowContents::Nothing = many[1] {
 			////////// Finding the phone number
 
-			let json = twilio_state.do_twilio_request("IncomingPhoneNumbers", &[], &[])?;
+			// If this fails (like when the network is down at startup), it's tried again a bit later
+			let formatted_number = match get_formatted_phone_number(twilio_state) {
+				Ok(formatted_number) => formatted_number,
 
-			let Some(phone_numbers) = json["incoming_phone_numbers"].as_array()
-			else {panic!("Expected the Twilio phone numbers to be an array!");};
+				Err(err) => {
+					log::warn!("Could not get the Twilio phone number yet: '{err}'.");
 
-			assert!(phone_numbers.len() == 1);
+					params.window.get_state_mut::<TopBoxState>().maybe_next_phone_number_retry_time =
+						Some(params.curr_time + chrono::Duration::seconds(SECONDS_BETWEEN_PHONE_NUMBER_RETRIES));
 
-			let number = phone_numbers[0]["phone_number"].as_str().context("Expected the phone number to be a string!")?;
-			let formatted_number = TwilioStateData::format_phone_number(number, "Messages to ", ":", "");
+					return Ok(());
+				}
+			};
 
 			//////////
 
@@ -680,7 +1009,7 @@ pub fn make_twilio_window(
 
 	let top_box = Window::new(
 		Some((top_box_updater_fn, update_rate)),
-		DynamicOptional::new(text_color),
+		DynamicOptional::new(TopBoxState {text_color, maybe_next_phone_number_retry_time: None}),
 		WindowContents::Many(vec![top_box_contents, WindowContents::Nothing]),
 		None,
 		Vec2f::new(top_left.x(), top_left.y() - top_box_height),
@@ -709,3 +1038,70 @@ pub fn make_twilio_window(
 		Some(vec![history_window_container, top_box])
 	)
 }
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::{
+		dashboard_defs::dashboard::FONT_INFO,
+		snapshot_testing::assert_matches_golden_image
+	};
+
+	// The shared window state here is just the display text of each message (newest first)
+	fn fixture_history_updater_fn(params: WindowUpdaterParams) -> MaybeError {
+		let display_texts = params.shared_window_state.get::<Vec<String>>();
+		let individual_window_state = params.window.get_state::<TwilioHistoryWindowState>();
+
+		let Some(display_text) = display_texts.get(individual_window_state.message_index) else {
+			*params.window.get_contents_mut() = WindowContents::Nothing;
+			return Ok(());
+		};
+
+		let texture = params.texture_pool.make_texture(&TextureCreationInfo::Text((
+			Cow::Borrowed(&FONT_INFO),
+
+			TextDisplayInfo {
+				text: DisplayText::new(display_text).with_padding("", " "),
+				color: individual_window_state.text_color,
+				pixel_area: params.area_drawn_to_screen,
+				scroll_fn: |_, _| (0.0, true)
+			}
+		)))?;
+
+		*params.window.get_contents_mut() = WindowContents::Texture(texture);
+		Ok(())
+	}
+
+	// There are fewer messages than history windows here, so the bottom ones are left empty
+	#[test]
+	fn history_layout() {
+		assert_matches_golden_image("twilio_history", (400, 300), |texture_pool| {
+			let display_texts = vec![
+				TwilioStateData::make_message_display_text(None, "Play some Talking Heads!", None),
+				TwilioStateData::make_message_display_text(Some(("min", "s", 12)), "Great set tonight", Some("+12075551234")),
+				TwilioStateData::make_message_display_text(Some(("day", "", 1)), "Is the studio open on Sunday?", None)
+			];
+
+			let history_windows = make_history_windows(
+				6, fixture_history_updater_fn, |_| Ok(()), UpdateRate::ONCE_PER_FRAME,
+				Vec2f::new(0.1, 0.45), ColorSDL::RGB(249, 236, 210),
+				&WindowContents::make_texture_contents("assets/text_bubble.png", texture_pool)?
+			);
+
+			let window = Window::new(
+				None,
+				DynamicOptional::NONE,
+				WindowContents::Nothing,
+				Some(ColorSDL::RGB(249, 236, 210)),
+				Vec2f::ZERO,
+				Vec2f::ONE,
+				Some(history_windows)
+			);
+
+			Ok((window, DynamicOptional::new(display_texts)))
+		});
+	}
+}
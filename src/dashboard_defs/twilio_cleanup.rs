@@ -0,0 +1,328 @@
+use std::{
+	sync::Arc,
+	borrow::Cow
+};
+
+use serde::Deserialize;
+
+use crate::{
+	request,
+
+	utility_types::{
+		json_utils,
+		generic_result::*,
+		thread_task::{Updatable, ContinuallyUpdated}
+	},
+
+	dashboard_defs::twilio::TWILIO_API_BASE_URL
+};
+
+/* This deletes old texts from the Twilio message history on a schedule (instead of running `scripts/delete_twilio_msgs.bash` by hand).
+Every `check_interval_hours`, it lists the messages older than `retention_days`, optionally archives each one to a local JSONL file
+(as the full Twilio message object, one per line), and then deletes them.
+
+- With `dry_run` set, the messages that would be deleted are only logged (and nothing is archived or deleted).
+- If archiving fails, nothing is deleted on that run. A failed run is retried after a short delay.
+- The API base URL can point to a local stand-in for Twilio (the tests below use one). */
+
+#[derive(Deserialize, Clone)]
+pub struct TwilioCleanupConfig {
+	pub retention_days: u32,
+
+	#[serde(default = "default_check_interval_hours")]
+	pub check_interval_hours: f64,
+
+	#[serde(default)]
+	pub maybe_archive_path: Option<String>,
+
+	#[serde(default)]
+	pub dry_run: bool,
+
+	#[serde(default = "default_api_base_url")]
+	pub api_base_url: String
+}
+
+const fn default_check_interval_hours() -> f64 {24.0}
+
+fn default_api_base_url() -> String {
+	TWILIO_API_BASE_URL.to_string()
+}
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+struct ImmutableTwilioCleanupData {
+	config: TwilioCleanupConfig,
+	account_sid: String,
+	request_auth: String
+}
+
+#[derive(Clone)]
+struct TwilioCleanupData {
+	immutable: Arc<ImmutableTwilioCleanupData>,
+	next_run_time: Timestamp
+}
+
+pub struct TwilioCleanup {
+	continually_updated: ContinuallyUpdated<TwilioCleanupData>
+}
+
+//////////
+
+const RETRY_DELAY_MINUTES: i64 = 10;
+const MAX_PAGE_SIZE: usize = 1000; // This is the largest page size that Twilio allows
+
+impl TwilioCleanupData {
+	fn do_twilio_request(&self, url: &str) -> GenericResult<serde_json::Value> {
+		request::as_type(request::get_with_maybe_header(url, Some(("Authorization", &self.immutable.request_auth))))
+	}
+
+	// This gets every message created before the cutoff time (following Twilio's paging)
+	fn get_messages_to_delete(&self, cutoff_time: Timestamp) -> GenericResult<Vec<serde_json::Value>> {
+		let immutable = &self.immutable;
+		let base_url = &immutable.config.api_base_url;
+
+		// The date filter is only precise to the day, so the messages are filtered manually below too
+		let mut maybe_url = Some(request::build_url(
+			&format!("{base_url}/2010-04-01/Accounts/{}/Messages.json", immutable.account_sid), &[],
+			&[
+				("PageSize", Cow::Owned(MAX_PAGE_SIZE.to_string())),
+				("DateSent%3C", Cow::Owned(cutoff_time.format("%Y-%m-%d").to_string())) // Note: the '%3C' is a URL-encoded '<'
+			]
+		));
+
+		let mut messages = Vec::new();
+
+		while let Some(url) = maybe_url {
+			let json = self.do_twilio_request(&url)?;
+			let page = json["messages"].as_array().context("Expected a list of messages from Twilio")?;
+
+			for message in page {
+				let unparsed_time_created = message["date_created"].as_str().context("Expected a message creation date from Twilio")?;
+				let time_created = chrono::DateTime::parse_from_rfc2822(unparsed_time_created)?;
+
+				if time_created < cutoff_time {
+					messages.push(message.clone());
+				}
+			}
+
+			maybe_url = json["next_page_uri"].as_str().map(|next_page_uri| format!("{base_url}{next_page_uri}"));
+		}
+
+		Ok(messages)
+	}
+
+	// This returns the number of messages that were deleted (or that would be, in a dry run)
+	fn run_cleanup(&self, curr_time: Timestamp) -> GenericResult<usize> {
+		let config = &self.immutable.config;
+		let cutoff_time = curr_time - chrono::Duration::days(config.retention_days.into());
+		let messages = self.get_messages_to_delete(cutoff_time)?;
+
+		if config.dry_run {
+			for message in &messages {
+				log::info!("Twilio cleanup dry run: would delete the message from {} at '{}': '{}'.",
+					message["from"], message["date_created"], message["body"]);
+			}
+
+			return Ok(messages.len());
+		}
+
+		if let Some(archive_path) = &config.maybe_archive_path {
+			json_utils::append_json_lines(archive_path, &messages)
+				.context(format!("Could not archive the old Twilio messages to '{archive_path}', so none were deleted"))?;
+		}
+
+		let mut num_failed = 0;
+
+		for message in &messages {
+			let uri = message["uri"].as_str().context("Expected a message URI from Twilio")?;
+			let url = format!("{}{uri}", config.api_base_url);
+
+			if let Err(err) = request::delete_with_maybe_header(&url, Some(("Authorization", &self.immutable.request_auth))) {
+				log::warn!("Could not delete the Twilio message at '{uri}': '{err}'.");
+				num_failed += 1;
+			}
+		}
+
+		if num_failed != 0 {
+			return error_msg!("{num_failed} of {} old Twilio messages could not be deleted", messages.len());
+		}
+
+		Ok(messages.len())
+	}
+}
+
+impl Updatable for TwilioCleanupData {
+	type Param = ();
+
+	fn update(&mut self, _: &Self::Param) -> MaybeError {
+		let curr_time = chrono::Utc::now();
+
+		if curr_time < self.next_run_time {
+			return Ok(());
+		}
+
+		// If this run fails, the next one happens after the retry delay
+		self.next_run_time = curr_time + chrono::Duration::minutes(RETRY_DELAY_MINUTES);
+
+		let num_messages = self.run_cleanup(curr_time)?;
+		let config = &self.immutable.config;
+
+		let interval_secs = (config.check_interval_hours * 3600.0) as i64;
+		self.next_run_time = curr_time + chrono::Duration::seconds(interval_secs);
+
+		let verb = if config.dry_run {"Would have deleted"} else {"Deleted"};
+		log::info!("{verb} {num_messages} Twilio messages older than {} days.", config.retention_days);
+
+		Ok(())
+	}
+}
+
+impl TwilioCleanup {
+	pub fn new(config: &TwilioCleanupConfig, account_sid: &str, request_auth: &str) -> GenericResult<Self> {
+		// Otherwise, the cleanup would run on every update
+		if config.check_interval_hours.is_nan() || config.check_interval_hours <= 0.0 {
+			return error_msg!("The Twilio cleanup check interval must be a positive number of hours, but it was {}", config.check_interval_hours);
+		}
+
+		let data = TwilioCleanupData {
+			immutable: Arc::new(ImmutableTwilioCleanupData {
+				config: config.clone(),
+				account_sid: account_sid.to_string(),
+				request_auth: request_auth.to_string()
+			}),
+
+			next_run_time: chrono::Utc::now()
+		};
+
+		Ok(Self {continually_updated: ContinuallyUpdated::new(&data, &(), "Twilio cleanup")})
+	}
+
+	// This returns false if something failed with the continual updater.
+	pub fn update(&mut self) -> GenericResult<bool> {
+		self.continually_updated.update(&())
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		sync::Mutex,
+		net::TcpListener,
+		io::{BufRead, BufReader, Write}
+	};
+
+	const ACCOUNT_SID: &str = "AC_test";
+
+	/* This is a small stand-in for the Twilio messages API. It lists the messages that it has (on one page,
+	ignoring the date filter, so that the manual filtering gets tested), and deletes them by their URIs. */
+	fn start_twilio_stand_in(messages: Vec<serde_json::Value>) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let base_url = format!("http://{}", listener.local_addr().unwrap());
+		let messages = Arc::new(Mutex::new(messages));
+		let server_messages = messages.clone();
+
+		std::thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let mut reader = BufReader::new(&stream);
+				let mut request_line = String::new();
+				reader.read_line(&mut request_line).unwrap();
+
+				// Skipping the headers
+				let mut header = String::new();
+				while reader.read_line(&mut header).unwrap() > 2 {header.clear();}
+
+				let mut parts = request_line.split_whitespace();
+				let (method, path) = (parts.next().unwrap(), parts.next().unwrap());
+				let mut messages = server_messages.lock().unwrap();
+
+				let (status, body) = if method == "DELETE" {
+					let num_messages_before = messages.len();
+					messages.retain(|message| message["uri"] != path);
+					if messages.len() < num_messages_before {("204 No Content", String::new())} else {("404 Not Found", String::new())}
+				}
+				else {
+					("200 OK", serde_json::json!({"messages": *messages, "next_page_uri": null}).to_string())
+				};
+
+				let response = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+				stream.write_all(response.as_bytes()).unwrap();
+			}
+		});
+
+		(base_url, messages)
+	}
+
+	fn make_message(sid: &str, days_old: i64, curr_time: Timestamp) -> serde_json::Value {
+		serde_json::json!({
+			"sid": sid,
+			"uri": format!("/2010-04-01/Accounts/{ACCOUNT_SID}/Messages/{sid}.json"),
+			"from": "+12075551234",
+			"body": format!("Message {sid}"),
+			"date_created": (curr_time - chrono::Duration::days(days_old)).to_rfc2822()
+		})
+	}
+
+	fn make_cleanup_data(base_url: String, maybe_archive_path: Option<String>, dry_run: bool) -> TwilioCleanupData {
+		TwilioCleanupData {
+			immutable: Arc::new(ImmutableTwilioCleanupData {
+				config: TwilioCleanupConfig {
+					retention_days: 14,
+					check_interval_hours: 24.0,
+					maybe_archive_path,
+					dry_run,
+					api_base_url: base_url
+				},
+
+				account_sid: ACCOUNT_SID.to_string(),
+				request_auth: "Basic test".to_string()
+			}),
+
+			next_run_time: chrono::Utc::now()
+		}
+	}
+
+	fn get_remaining_sids(messages: &Mutex<Vec<serde_json::Value>>) -> Vec<String> {
+		messages.lock().unwrap().iter().map(|message| message["sid"].as_str().unwrap().to_string()).collect()
+	}
+
+	#[test]
+	fn deletes_and_archives_old_messages() {
+		let curr_time = chrono::Utc::now();
+
+		let (base_url, messages) = start_twilio_stand_in(vec![
+			make_message("SM_old", 30, curr_time),
+			make_message("SM_recent", 1, curr_time),
+			make_message("SM_older", 15, curr_time)
+		]);
+
+		let archive_path = std::env::temp_dir().join(format!("twilio_cleanup_test_{}.jsonl", std::process::id()));
+		let archive_path = archive_path.to_str().unwrap().to_string();
+		let _ = std::fs::remove_file(&archive_path);
+
+		let data = make_cleanup_data(base_url, Some(archive_path.clone()), false);
+		assert_eq!(data.run_cleanup(curr_time).unwrap(), 2);
+		assert_eq!(get_remaining_sids(&messages), ["SM_recent"]);
+
+		let archive = std::fs::read_to_string(&archive_path).unwrap();
+		let archived_sids: Vec<String> = archive.lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["sid"].as_str().unwrap().to_string())
+			.collect();
+
+		assert_eq!(archived_sids, ["SM_old", "SM_older"]);
+		std::fs::remove_file(&archive_path).unwrap();
+	}
+
+	#[test]
+	fn dry_run_deletes_nothing() {
+		let curr_time = chrono::Utc::now();
+		let (base_url, messages) = start_twilio_stand_in(vec![make_message("SM_old", 30, curr_time)]);
+
+		let data = make_cleanup_data(base_url, None, true);
+		assert_eq!(data.run_cleanup(curr_time).unwrap(), 1);
+		assert_eq!(get_remaining_sids(&messages), ["SM_old"]);
+	}
+}
@@ -0,0 +1,435 @@
+use std::{
+	sync::{Arc, Mutex},
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet}
+};
+
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::{
+	utility_types::{
+		json_utils,
+		command_socket,
+		generic_result::*
+	},
+
+	dashboard_defs::twilio::MessageID
+};
+
+/* This moderates incoming texts before they're shown on the dashboard. Messages from numbers on the blocklist are hidden,
+and the content filter masks words from its word list, and applies its regex rules (each of which either hides, masks, or
+holds a matching message). Held messages wait in a moderation queue until a staff member approves or rejects them.
+
+- The blocklist is a JSON array of phone numbers (like `"+12075551234"`). It's saved whenever it's changed, so it persists across restarts.
+- The content filter is a JSON object with a `masked_words` array, and a `rules` array of `{"pattern": ..., "action": "hide" | "mask" | "hold"}`.
+Words and patterns are matched case-insensitively. When several rules match, hiding beats holding, which beats masking.
+- Every decision is logged to the decision log (one JSON object per line), once per message (and again if the decision changes).
+- Staff use the command socket, with something like `echo list | nc -U <socket path>`. The commands are `list`, `approve <code>`,
+`reject <code>`, `block <number>`, and `unblock <number>` (where the code is the one shown by `list`).
+- Staff reviews are only kept in memory, so held messages go back into the queue after a restart. */
+
+#[derive(Deserialize, Clone)]
+pub struct TwilioModerationConfig {
+	pub blocklist_path: String,
+	pub content_filter_path: String,
+	pub decision_log_path: String,
+	pub command_socket_path: String
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum ModerationAction {
+	// These are in order of increasing severity
+	Mask,
+	Hold,
+	Hide
+}
+
+#[derive(Deserialize)]
+struct ContentFilterRule {
+	pattern: String,
+	action: ModerationAction
+}
+
+#[derive(Deserialize)]
+struct ContentFilter {
+	#[serde(default)]
+	masked_words: Vec<String>,
+
+	#[serde(default)]
+	rules: Vec<ContentFilterRule>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Verdict {
+	Shown,
+	Masked,
+	Hidden,
+	Held,
+	Approved,
+	Rejected
+}
+
+struct HeldMessage {
+	id: MessageID,
+	from: String,
+	body: String
+}
+
+pub struct TwilioModerator {
+	blocklist_path: String,
+	blocked_numbers: BTreeSet<String>,
+
+	maybe_masked_words_regexp: Option<Regex>,
+	rules: Vec<(Regex, ModerationAction)>,
+
+	decision_log_path: String,
+
+	// The held messages are keyed by a short code, which staff use to refer to them
+	held_messages: BTreeMap<u32, HeldMessage>,
+	next_held_message_code: u32,
+
+	// This maps messages to whether staff approved them
+	staff_reviews: HashMap<MessageID, bool>,
+
+	// These are the last logged verdicts (so that each one is only logged once)
+	logged_verdicts: HashMap<MessageID, Verdict>
+}
+
+//////////
+
+impl Verdict {
+	const fn get_name(self) -> &'static str {
+		match self {
+			Self::Shown => "shown",
+			Self::Masked => "masked",
+			Self::Hidden => "hidden",
+			Self::Held => "held",
+			Self::Approved => "approved",
+			Self::Rejected => "rejected"
+		}
+	}
+}
+
+fn build_case_insensitive_regexp(pattern: &str) -> GenericResult<Regex> {
+	RegexBuilder::new(pattern).case_insensitive(true).build()
+		.context(format!("Invalid content filter pattern '{pattern}'"))
+}
+
+// The words are matched whole, so that masking "darn" does not mask part of "darnit"
+fn build_masked_words_regexp(masked_words: &[String]) -> GenericResult<Option<Regex>> {
+	(!masked_words.is_empty()).then(|| {
+		let escaped_words: Vec<String> = masked_words.iter().map(|word| regex::escape(word)).collect();
+		build_case_insensitive_regexp(&format!(r"\b(?:{})\b", escaped_words.join("|")))
+	}).transpose()
+}
+
+fn mask(regexp: &Regex, text: &str) -> String {
+	regexp.replace_all(text, |captures: &regex::Captures| "*".repeat(captures[0].chars().count())).into_owned()
+}
+
+impl TwilioModerator {
+	// This also starts listening for staff commands
+	pub fn new(config: &TwilioModerationConfig) -> GenericResult<Arc<Mutex<Self>>> {
+		// The blocklist is made on the first block, if it does not exist yet
+		let blocked_numbers = if std::path::Path::new(&config.blocklist_path).exists() {
+			json_utils::load_from_file(&config.blocklist_path)?
+		}
+		else {
+			BTreeSet::new()
+		};
+
+		let content_filter: ContentFilter = json_utils::load_from_file(&config.content_filter_path)
+			.context(format!("Could not load the texter content filter at '{}'", config.content_filter_path))?;
+
+		let maybe_masked_words_regexp = build_masked_words_regexp(&content_filter.masked_words)?;
+
+		let rules = content_filter.rules.iter().map(|rule| {
+			build_case_insensitive_regexp(&rule.pattern).map(|regexp| (regexp, rule.action))
+		}).collect::<GenericResult<_>>()?;
+
+		let moderator = Arc::new(Mutex::new(Self {
+			blocklist_path: config.blocklist_path.clone(),
+			blocked_numbers,
+
+			maybe_masked_words_regexp,
+			rules,
+
+			decision_log_path: config.decision_log_path.clone(),
+
+			held_messages: BTreeMap::new(),
+			next_held_message_code: 1,
+			staff_reviews: HashMap::new(),
+			logged_verdicts: HashMap::new()
+		}));
+
+		// The listener stops once the moderator is dropped (e.g. after the dashboard is rebuilt with a new config)
+		command_socket::start_command_listener(&config.command_socket_path, "texter moderation",
+			Arc::downgrade(&moderator), |moderator, command| moderator.lock().unwrap().run_command(command))?;
+
+		Ok(moderator)
+	}
+
+	////////// Logging and saving
+
+	fn log_decision(&self, decision: &str, by: &str, maybe_message: Option<(&MessageID, &str)>, from: &str, reason: &str) {
+		let (message_id, body) = maybe_message.unzip();
+
+		let entry = serde_json::json!({
+			"time": chrono::Local::now().to_rfc3339(),
+			"decision": decision,
+			"by": by,
+			"message_id": message_id.map(|id| id.as_ref()),
+			"from": from,
+			"body": body,
+			"reason": reason
+		});
+
+		if let Err(err) = json_utils::append_json_lines(&self.decision_log_path, [&entry]) {
+			log::error!("Could not write to the texter moderation log at '{}': '{err}'.", self.decision_log_path);
+		}
+	}
+
+	fn save_blocklist(&self) -> MaybeError {
+		if let Some(parent_dir) = std::path::Path::new(&self.blocklist_path).parent() {
+			std::fs::create_dir_all(parent_dir)?;
+		}
+
+		std::fs::write(&self.blocklist_path, serde_json::to_string_pretty(&self.blocked_numbers)?)?;
+		Ok(())
+	}
+
+	////////// Moderating messages
+
+	// This returns the verdict, the body to show, and why the verdict was reached
+	fn judge(&self, id: &MessageID, from: &str, body: &str) -> (Verdict, Option<String>, String) {
+		if self.blocked_numbers.contains(from) {
+			return (Verdict::Hidden, None, "blocked number".to_string());
+		}
+
+		match self.staff_reviews.get(id) {
+			Some(true) => return (Verdict::Approved, Some(body.to_string()), "approved by staff".to_string()),
+			Some(false) => return (Verdict::Rejected, None, "rejected by staff".to_string()),
+			None => {}
+		}
+
+		let maybe_strictest_rule = self.rules.iter()
+			.filter(|(regexp, _)| regexp.is_match(body))
+			.max_by_key(|(_, action)| *action);
+
+		let mut masked_body = match &self.maybe_masked_words_regexp {
+			Some(regexp) => mask(regexp, body),
+			None => body.to_string()
+		};
+
+		match maybe_strictest_rule {
+			Some((regexp, ModerationAction::Hide)) => (Verdict::Hidden, None, format!("matched the rule '{regexp}'")),
+			Some((regexp, ModerationAction::Hold)) => (Verdict::Held, None, format!("matched the rule '{regexp}'")),
+
+			_ => {
+				// Every masking rule applies (not just the first one)
+				for (regexp, _) in self.rules.iter().filter(|(_, action)| *action == ModerationAction::Mask) {
+					masked_body = mask(regexp, &masked_body);
+				}
+
+				if masked_body == body {(Verdict::Shown, Some(masked_body), String::new())}
+				else {(Verdict::Masked, Some(masked_body), "matched the content filter".to_string())}
+			}
+		}
+	}
+
+	// This returns the body to show for a message (which is `None` if it should not be shown)
+	pub fn moderate(&mut self, id: &MessageID, from: &str, body: &str) -> Option<String> {
+		let (verdict, maybe_body_to_show, reason) = self.judge(id, from, body);
+
+		if verdict == Verdict::Held && !self.held_messages.values().any(|held| &held.id == id) {
+			self.held_messages.insert(self.next_held_message_code, HeldMessage {
+				id: id.clone(), from: from.to_string(), body: body.to_string()
+			});
+
+			self.next_held_message_code += 1;
+		}
+
+		if self.logged_verdicts.get(id) != Some(&verdict) {
+			self.log_decision(verdict.get_name(), "filter", Some((id, body)), from, &reason);
+			self.logged_verdicts.insert(id.clone(), verdict);
+		}
+
+		maybe_body_to_show
+	}
+
+	// This forgets about any messages that have left the message history
+	pub fn forget_unseen_messages(&mut self, seen_ids: &HashSet<MessageID>) {
+		self.held_messages.retain(|_, held| seen_ids.contains(&held.id));
+		self.staff_reviews.retain(|id, _| seen_ids.contains(id));
+		self.logged_verdicts.retain(|id, _| seen_ids.contains(id));
+	}
+
+	////////// Handling staff commands
+
+	fn review_held_message(&mut self, code_arg: &str, approved: bool) -> String {
+		let Some(held) = code_arg.parse().ok().and_then(|code: u32| self.held_messages.remove(&code)) else {
+			return format!("There is no held message with the code '{code_arg}'.");
+		};
+
+		let verdict = if approved {Verdict::Approved} else {Verdict::Rejected};
+		self.log_decision(verdict.get_name(), "staff", Some((&held.id, &held.body)), &held.from, "");
+
+		// This is logged here already, so it's not logged again when the message is next moderated
+		self.logged_verdicts.insert(held.id.clone(), verdict);
+		self.staff_reviews.insert(held.id, approved);
+
+		format!("The message from {} was {}. It will update on the dashboard soon.", held.from, verdict.get_name())
+	}
+
+	fn set_number_blocking(&mut self, number: &str, block: bool) -> String {
+		let changed = if block {self.blocked_numbers.insert(number.to_string())} else {self.blocked_numbers.remove(number)};
+
+		if !changed {
+			return format!("{number} was already {}.", if block {"blocked"} else {"unblocked"});
+		}
+
+		let decision = if block {"block_number"} else {"unblock_number"};
+		self.log_decision(decision, "staff", None, number, "");
+
+		match self.save_blocklist() {
+			Ok(()) => format!("{number} is now {}.", if block {"blocked"} else {"unblocked"}),
+			Err(err) => format!("{number} is now {} until a restart, since the blocklist could not be saved: '{err}'.",
+				if block {"blocked"} else {"unblocked"})
+		}
+	}
+
+	fn run_command(&mut self, command: &str) -> String {
+		let mut words = command.split_whitespace();
+
+		match (words.next(), words.next()) {
+			(Some("list"), None) => {
+				if self.held_messages.is_empty() {
+					return "No messages are held.".to_string();
+				}
+
+				self.held_messages.iter()
+					.map(|(code, held)| format!("{code}: from {}: '{}'", held.from, held.body))
+					.collect::<Vec<_>>()
+					.join("\n")
+			},
+
+			(Some("approve"), Some(code)) => self.review_held_message(code, true),
+			(Some("reject"), Some(code)) => self.review_held_message(code, false),
+			(Some("block"), Some(number)) => self.set_number_blocking(number, true),
+			(Some("unblock"), Some(number)) => self.set_number_blocking(number, false),
+
+			_ => format!("Unknown command '{}'. The commands are `list`, `approve <code>`, \
+				`reject <code>`, `block <number>`, and `unblock <number>`.", command.trim())
+		}
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TEXTER: &str = "+12075551111";
+
+	fn make_moderator(name: &str, masked_words: &[&str], rules: &[(&str, ModerationAction)]) -> TwilioModerator {
+		let make_path = |kind: &str| {
+			let path = std::env::temp_dir().join(format!("twilio_moderation_test_{name}_{kind}_{}", std::process::id()));
+			let _ = std::fs::remove_file(&path);
+			path.to_str().unwrap().to_string()
+		};
+
+		let masked_words: Vec<String> = masked_words.iter().map(|word| word.to_string()).collect();
+
+		TwilioModerator {
+			blocklist_path: make_path("blocklist.json"),
+			blocked_numbers: BTreeSet::new(),
+
+			maybe_masked_words_regexp: build_masked_words_regexp(&masked_words).unwrap(),
+			rules: rules.iter().map(|(pattern, action)| (build_case_insensitive_regexp(pattern).unwrap(), *action)).collect(),
+
+			decision_log_path: make_path("decisions.jsonl"),
+
+			held_messages: BTreeMap::new(),
+			next_held_message_code: 1,
+			staff_reviews: HashMap::new(),
+			logged_verdicts: HashMap::new()
+		}
+	}
+
+	fn remove_files(moderator: &TwilioModerator) {
+		let _ = std::fs::remove_file(&moderator.blocklist_path);
+		let _ = std::fs::remove_file(&moderator.decision_log_path);
+	}
+
+	fn judge(moderator: &TwilioModerator, body: &str) -> (Verdict, Option<String>) {
+		let (verdict, maybe_body, _) = moderator.judge(&MessageID::from("SM_test"), TEXTER, body);
+		(verdict, maybe_body)
+	}
+
+	#[test]
+	fn hiding_beats_holding_which_beats_masking() {
+		let moderator = make_moderator("severity", &[], &[
+			("hello", ModerationAction::Mask),
+			("request", ModerationAction::Hold),
+			("spam", ModerationAction::Hide)
+		]);
+
+		assert_eq!(judge(&moderator, "Hello, here's some spam for my request"), (Verdict::Hidden, None));
+		assert_eq!(judge(&moderator, "Hello, here's my request"), (Verdict::Held, None));
+		assert_eq!(judge(&moderator, "Hello there"), (Verdict::Masked, Some("***** there".to_string())));
+		assert_eq!(judge(&moderator, "Hi there"), (Verdict::Shown, Some("Hi there".to_string())));
+	}
+
+	#[test]
+	fn masks_whole_words_ignoring_case() {
+		let moderator = make_moderator("masking", &["darn", "heck"], &[]);
+		assert_eq!(judge(&moderator, "Darn it, darnit, what the HECK"), (Verdict::Masked, Some("**** it, darnit, what the ****".to_string())));
+	}
+
+	#[test]
+	fn staff_approve_and_reject_held_messages() {
+		let mut moderator = make_moderator("queue", &[], &[("request", ModerationAction::Hold)]);
+		let (first_id, second_id) = (MessageID::from("SM_first"), MessageID::from("SM_second"));
+
+		assert_eq!(moderator.moderate(&first_id, TEXTER, "First request"), None);
+		assert_eq!(moderator.moderate(&second_id, TEXTER, "Second request"), None);
+
+		// Held messages are only queued once
+		assert_eq!(moderator.moderate(&first_id, TEXTER, "First request"), None);
+
+		assert_eq!(moderator.run_command("list\n"), format!("1: from {TEXTER}: 'First request'\n2: from {TEXTER}: 'Second request'"));
+
+		assert_eq!(moderator.run_command("approve 1"), format!("The message from {TEXTER} was approved. It will update on the dashboard soon."));
+		assert_eq!(moderator.run_command("reject 2"), format!("The message from {TEXTER} was rejected. It will update on the dashboard soon."));
+		assert_eq!(moderator.run_command("approve 2"), "There is no held message with the code '2'.");
+		assert_eq!(moderator.run_command("list"), "No messages are held.");
+
+		assert_eq!(moderator.moderate(&first_id, TEXTER, "First request").as_deref(), Some("First request"));
+		assert_eq!(moderator.moderate(&second_id, TEXTER, "Second request"), None);
+
+		remove_files(&moderator);
+	}
+
+	#[test]
+	fn blocklist_is_saved() {
+		let mut moderator = make_moderator("blocklist", &[], &[]);
+		let id = MessageID::from("SM_test");
+
+		assert_eq!(moderator.run_command(&format!("block {TEXTER}")), format!("{TEXTER} is now blocked."));
+		assert_eq!(moderator.run_command(&format!("block {TEXTER}")), format!("{TEXTER} was already blocked."));
+		assert_eq!(moderator.moderate(&id, TEXTER, "Hi there"), None);
+
+		let saved_numbers: BTreeSet<String> = json_utils::load_from_file(&moderator.blocklist_path).unwrap();
+		assert_eq!(saved_numbers, BTreeSet::from([TEXTER.to_string()]));
+
+		assert_eq!(moderator.run_command(&format!("unblock {TEXTER}")), format!("{TEXTER} is now unblocked."));
+		assert_eq!(moderator.moderate(&id, TEXTER, "Hi there").as_deref(), Some("Hi there"));
+
+		let saved_numbers: BTreeSet<String> = json_utils::load_from_file(&moderator.blocklist_path).unwrap();
+		assert!(saved_numbers.is_empty());
+
+		remove_files(&moderator);
+	}
+}
@@ -0,0 +1,424 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::{
+	request,
+	utility_types::{
+		json_utils,
+		command_socket,
+		generic_result::*
+	},
+
+	dashboard_defs::twilio::{MessageID, TWILIO_API_BASE_URL}
+};
+
+/* This lets DJs reply to texts from the studio (e.g. to acknowledge song requests), through a local command socket.
+Messages are picked by a short code, which stays the same for as long as the message is in the message history
+(so that a new text arriving in between `list` and `reply` can't change who gets the reply). Replies are sent from the
+station number that each message was sent to.
+
+- The commands are `list` (which lists the messages with their codes, in the order of the history), `canned` (which lists the canned replies),
+`reply <code> <text>`, and `reply_canned <code> <canned reply number>`. For example: `echo 'reply 3 Coming right up!' | nc -U <socket path>`.
+- Replies are rate-limited, both overall (with a minimum time between replies) and per texter (with a max number of replies per hour).
+- Every reply attempt is logged to the reply log (one JSON object per line), with whether it was sent.
+- Replies are outbound messages, so they're left out of the displayed message history.
+- The API base URL can point to a local stand-in for Twilio (the tests below use one). */
+
+#[derive(Deserialize, Clone)]
+pub struct TwilioReplyConfig {
+	pub command_socket_path: String,
+	pub reply_log_path: String,
+
+	#[serde(default)]
+	pub canned_replies: Vec<String>,
+
+	#[serde(default = "default_min_secs_between_replies")]
+	pub min_secs_between_replies: i64,
+
+	#[serde(default = "default_max_replies_per_texter_per_hour")]
+	pub max_replies_per_texter_per_hour: usize,
+
+	#[serde(default = "default_api_base_url")]
+	pub api_base_url: String
+}
+
+const fn default_min_secs_between_replies() -> i64 {10}
+const fn default_max_replies_per_texter_per_hour() -> usize {3}
+
+fn default_api_base_url() -> String {
+	TWILIO_API_BASE_URL.to_string()
+}
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+// This is a message in the history that can be replied to
+#[derive(Clone)]
+pub struct ReplyTarget {
+	pub message_id: MessageID,
+	pub sender_number: String,
+	pub station_number: String,
+	pub body: String
+}
+
+struct TwilioReplierState {
+	config: TwilioReplyConfig,
+	account_sid: String,
+	request_auth: String,
+
+	// These are keyed by their codes, and they're in the same order as the history windows
+	targets: Vec<(u32, ReplyTarget)>,
+	next_target_code: u32,
+
+	// These are the numbers that were replied to in the last hour, and when
+	recent_replies: Vec<(String, Timestamp)>
+}
+
+pub struct TwilioReplier {
+	state: Arc<Mutex<TwilioReplierState>>
+}
+
+//////////
+
+impl TwilioReplierState {
+	// Messages that were already targets keep their codes
+	fn set_targets(&mut self, targets: Vec<ReplyTarget>) {
+		let coded_targets = targets.into_iter().map(|target| {
+			let maybe_code = self.targets.iter()
+				.find(|(_, old_target)| old_target.message_id == target.message_id)
+				.map(|(code, _)| *code);
+
+			let code = maybe_code.unwrap_or_else(|| {
+				self.next_target_code += 1;
+				self.next_target_code - 1
+			});
+
+			(code, target)
+		}).collect();
+
+		self.targets = coded_targets;
+	}
+
+	fn log_reply(&self, target: &ReplyTarget, reply: &str, maybe_error: Option<&str>) {
+		let entry = serde_json::json!({
+			"time": chrono::Local::now().to_rfc3339(),
+			"to": target.sender_number,
+			"from": target.station_number,
+			"in_reply_to": target.body,
+			"body": reply,
+			"sent": maybe_error.is_none(),
+			"error": maybe_error
+		});
+
+		let log_path = &self.config.reply_log_path;
+
+		if let Err(err) = json_utils::append_json_lines(log_path, [&entry]) {
+			log::error!("Could not write to the Twilio reply log at '{log_path}': '{err}'.");
+		}
+	}
+
+	// This returns why a reply can't be sent to the given number right now (if it can't)
+	fn check_rate_limits(&mut self, number: &str, curr_time: Timestamp) -> Option<String> {
+		self.recent_replies.retain(|(_, time)| curr_time - *time < chrono::Duration::hours(1));
+
+		if let Some((_, last_time)) = self.recent_replies.last() {
+			let secs_until_allowed = self.config.min_secs_between_replies - (curr_time - *last_time).num_seconds();
+
+			if secs_until_allowed > 0 {
+				return Some(format!("Wait {secs_until_allowed} more seconds before sending another reply."));
+			}
+		}
+
+		let num_recent_replies_to_number = self.recent_replies.iter().filter(|(recent_number, _)| recent_number == number).count();
+
+		(num_recent_replies_to_number >= self.config.max_replies_per_texter_per_hour).then(|| format!(
+			"This texter already got {num_recent_replies_to_number} replies in the last hour, which is the limit."
+		))
+	}
+}
+
+fn send_reply(base_url: &str, account_sid: &str, request_auth: &str, target: &ReplyTarget, reply: &str) -> MaybeError {
+	let url = format!("{base_url}/2010-04-01/Accounts/{account_sid}/Messages.json");
+
+	request::post_form_with_maybe_header(&url, Some(("Authorization", request_auth)), &[
+		("To", &target.sender_number),
+		("From", &target.station_number),
+		("Body", reply)
+	])?;
+
+	Ok(())
+}
+
+// The state is only locked briefly here, so that sending a reply does not hold up the main thread
+fn reply(state: &Mutex<TwilioReplierState>, code_arg: &str, reply: &str) -> String {
+	let curr_time = chrono::Utc::now();
+
+	let (target, base_url, account_sid, request_auth) = {
+		let mut state = state.lock().unwrap();
+
+		let maybe_target = code_arg.parse().ok().and_then(|code: u32|
+			state.targets.iter().find(|(target_code, _)| *target_code == code).map(|(_, target)| target.clone())
+		);
+
+		let Some(target) = maybe_target else {
+			return format!("There is no message with the code '{code_arg}'. Use `list` to see the messages.");
+		};
+
+		if reply.trim().is_empty() {
+			return "The reply is empty.".to_string();
+		}
+
+		if let Some(reason) = state.check_rate_limits(&target.sender_number, curr_time) {
+			state.log_reply(&target, reply, Some(&reason));
+			return reason;
+		}
+
+		(target, state.config.api_base_url.clone(), state.account_sid.clone(), state.request_auth.clone())
+	};
+
+	let result = send_reply(&base_url, &account_sid, &request_auth, &target, reply);
+	let mut state = state.lock().unwrap();
+
+	match result {
+		Ok(()) => {
+			state.recent_replies.push((target.sender_number.clone(), curr_time));
+			state.log_reply(&target, reply, None);
+			format!("Replied to '{}'.", target.body)
+		},
+
+		Err(err) => {
+			let error = err.to_string();
+			state.log_reply(&target, reply, Some(&error));
+			format!("Could not send the reply: '{error}'.")
+		}
+	}
+}
+
+fn run_command(state: &Mutex<TwilioReplierState>, command: &str) -> String {
+	let command = command.trim();
+	let (name, args) = command.split_once(' ').unwrap_or((command, ""));
+	let (first_arg, rest) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+
+	match name {
+		"list" => {
+			let state = state.lock().unwrap();
+
+			if state.targets.is_empty() {
+				return "There are no messages.".to_string();
+			}
+
+			state.targets.iter()
+				.map(|(code, target)| format!("{code}: '{}'", target.body))
+				.collect::<Vec<_>>()
+				.join("\n")
+		},
+
+		"canned" => {
+			let state = state.lock().unwrap();
+
+			if state.config.canned_replies.is_empty() {
+				return "There are no canned replies.".to_string();
+			}
+
+			state.config.canned_replies.iter().enumerate()
+				.map(|(index, canned_reply)| format!("{}: '{canned_reply}'", index + 1))
+				.collect::<Vec<_>>()
+				.join("\n")
+		},
+
+		"reply" => reply(state, first_arg, rest),
+
+		"reply_canned" => {
+			let maybe_canned_reply = rest.trim().parse::<usize>().ok()
+				.and_then(|number| number.checked_sub(1))
+				.and_then(|index| state.lock().unwrap().config.canned_replies.get(index).cloned());
+
+			match maybe_canned_reply {
+				Some(canned_reply) => reply(state, first_arg, &canned_reply),
+				None => format!("There is no canned reply '{}'. Use `canned` to see them.", rest.trim())
+			}
+		},
+
+		_ => format!("Unknown command '{command}'. The commands are `list`, `canned`, \
+			`reply <code> <text>`, and `reply_canned <code> <canned reply number>`.")
+	}
+}
+
+impl TwilioReplier {
+	// This also starts listening for reply commands
+	pub fn new(config: &TwilioReplyConfig, account_sid: &str, request_auth: &str) -> GenericResult<Self> {
+		let state = Arc::new(Mutex::new(TwilioReplierState {
+			config: config.clone(),
+			account_sid: account_sid.to_string(),
+			request_auth: request_auth.to_string(),
+			targets: Vec::new(),
+			next_target_code: 1,
+			recent_replies: Vec::new()
+		}));
+
+		// Like the texter moderation listener, this stops once the replier is dropped
+		command_socket::start_command_listener(&config.command_socket_path, "Twilio reply", Arc::downgrade(&state), run_command)?;
+		Ok(Self {state})
+	}
+
+	// The targets should be in the same order as the history windows
+	pub fn set_targets(&self, targets: Vec<ReplyTarget>) {
+		self.state.lock().unwrap().set_targets(targets);
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		net::TcpListener,
+		io::{BufRead, BufReader, Read, Write}
+	};
+
+	const ACCOUNT_SID: &str = "AC_test";
+
+	fn make_state(name: &str, api_base_url: &str) -> TwilioReplierState {
+		let reply_log_path = std::env::temp_dir().join(format!("twilio_replies_test_{name}_{}.jsonl", std::process::id()));
+		let reply_log_path = reply_log_path.to_str().unwrap().to_string();
+		let _ = std::fs::remove_file(&reply_log_path);
+
+		TwilioReplierState {
+			config: TwilioReplyConfig {
+				command_socket_path: String::new(),
+				reply_log_path,
+				canned_replies: vec!["Thanks for listening!".to_string()],
+				min_secs_between_replies: 10,
+				max_replies_per_texter_per_hour: 3,
+				api_base_url: api_base_url.to_string()
+			},
+
+			account_sid: ACCOUNT_SID.to_string(),
+			request_auth: "Basic test".to_string(),
+			targets: Vec::new(),
+			next_target_code: 1,
+			recent_replies: Vec::new()
+		}
+	}
+
+	fn make_target(message_id: &str, sender_number: &str, body: &str) -> ReplyTarget {
+		ReplyTarget {
+			message_id: message_id.into(),
+			sender_number: sender_number.to_string(),
+			station_number: "+12075550000".to_string(),
+			body: body.to_string()
+		}
+	}
+
+	/* This is a small stand-in for the Twilio messages API, which answers one message creation request,
+	and then sends back the request line and body. */
+	fn start_twilio_stand_in() -> (String, std::sync::mpsc::Receiver<(String, String)>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let base_url = format!("http://{}", listener.local_addr().unwrap());
+		let (request_sender, request_receiver) = std::sync::mpsc::channel();
+
+		std::thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut reader = BufReader::new(&stream);
+
+			let mut request_line = String::new();
+			reader.read_line(&mut request_line).unwrap();
+
+			let mut content_length = 0;
+
+			loop {
+				let mut header = String::new();
+				reader.read_line(&mut header).unwrap();
+				if header.trim().is_empty() {break;}
+
+				if let Some((name, value)) = header.split_once(':') {
+					if name.eq_ignore_ascii_case("content-length") {content_length = value.trim().parse().unwrap();}
+				}
+			}
+
+			let mut body = vec![0; content_length];
+			reader.read_exact(&mut body).unwrap();
+
+			let response_body = r#"{"sid": "SM_reply"}"#;
+			let response = format!("HTTP/1.1 201 Created\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response_body}", response_body.len());
+			stream.write_all(response.as_bytes()).unwrap();
+
+			request_sender.send((request_line.trim().to_string(), String::from_utf8(body).unwrap())).unwrap();
+		});
+
+		(base_url, request_receiver)
+	}
+
+	#[test]
+	fn waits_between_replies() {
+		let mut state = make_state("gap", "");
+		let curr_time = chrono::Utc::now();
+
+		state.recent_replies.push(("+12075551111".to_string(), curr_time - chrono::Duration::seconds(4)));
+
+		// This applies to every texter, not just the last one
+		assert_eq!(state.check_rate_limits("+12075552222", curr_time).as_deref(),
+			Some("Wait 6 more seconds before sending another reply."));
+
+		assert_eq!(state.check_rate_limits("+12075552222", curr_time + chrono::Duration::seconds(6)), None);
+	}
+
+	#[test]
+	fn limits_replies_per_texter_per_hour() {
+		let mut state = make_state("hourly", "");
+		let curr_time = chrono::Utc::now();
+
+		for minutes_ago in [50, 30, 10] {
+			state.recent_replies.push(("+12075551111".to_string(), curr_time - chrono::Duration::minutes(minutes_ago)));
+		}
+
+		assert_eq!(state.check_rate_limits("+12075551111", curr_time).as_deref(),
+			Some("This texter already got 3 replies in the last hour, which is the limit."));
+
+		assert_eq!(state.check_rate_limits("+12075552222", curr_time), None);
+
+		// Once the oldest reply is over an hour old, it's forgotten
+		assert_eq!(state.check_rate_limits("+12075551111", curr_time + chrono::Duration::minutes(11)), None);
+		assert_eq!(state.recent_replies.len(), 2);
+	}
+
+	#[test]
+	fn keeps_target_codes_when_new_texts_arrive() {
+		let mut state = make_state("codes", "");
+
+		state.set_targets(vec![make_target("SM_a", "+12075551111", "First")]);
+		state.set_targets(vec![make_target("SM_b", "+12075552222", "Second"), make_target("SM_a", "+12075551111", "First")]);
+
+		let codes: Vec<(u32, &str)> = state.targets.iter().map(|(code, target)| (*code, target.body.as_str())).collect();
+		assert_eq!(codes, [(2, "Second"), (1, "First")]);
+	}
+
+	#[test]
+	fn sends_and_logs_a_canned_reply() {
+		let (base_url, request_receiver) = start_twilio_stand_in();
+		let mut state = make_state("send", &base_url);
+		let reply_log_path = state.config.reply_log_path.clone();
+
+		state.set_targets(vec![make_target("SM_a", "+12075551111", "Play some jazz")]);
+		let state = Mutex::new(state);
+
+		assert_eq!(run_command(&state, "reply_canned 1 1\n"), "Replied to 'Play some jazz'.");
+
+		let (request_line, body) = request_receiver.recv().unwrap();
+		assert_eq!(request_line, format!("POST /2010-04-01/Accounts/{ACCOUNT_SID}/Messages.json HTTP/1.1"));
+		assert_eq!(body, "To=%2B12075551111&From=%2B12075550000&Body=Thanks%20for%20listening%21");
+
+		// The next reply is refused, since it's too soon after this one
+		assert_eq!(run_command(&state, "reply 1 Coming right up"), "Wait 10 more seconds before sending another reply.");
+
+		let log_entries: Vec<serde_json::Value> = std::fs::read_to_string(&reply_log_path).unwrap()
+			.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+		assert_eq!(log_entries.iter().map(|entry| entry["sent"].as_bool().unwrap()).collect::<Vec<_>>(), [true, false]);
+		assert_eq!(log_entries[0]["body"], "Thanks for listening!");
+
+		std::fs::remove_file(&reply_log_path).unwrap();
+	}
+}
@@ -0,0 +1,301 @@
+use std::sync::{Arc, Mutex};
+
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+
+use crate::utility_types::{
+	generic_result::*,
+	http_server::{self, HttpRequest, HttpResponse}
+};
+
+/* This receives incoming texts through Twilio's incoming-message webhook, so that they show up right away
+(instead of waiting for the next poll of the message list). Each webhook request is checked against its
+`X-Twilio-Signature` (an HMAC-SHA1 of the URL and the form params, keyed with the auth token).
+
+- The `public_url` should be the exact URL that Twilio is configured with (the listening address is often
+behind a tunnel or a reverse proxy, so the URL can't be worked out from the request itself).
+- Received messages are shaped like the ones from the message list, and they're merged with the last poll.
+- Polling still happens every `reconciliation_interval_secs`, to catch any messages that the webhook missed
+(and once a poll has a message, it's no longer kept here). */
+
+#[derive(Deserialize, Clone)]
+pub struct TwilioWebhookConfig {
+	pub address: String, // This is the local address to listen on, like `0.0.0.0:8137`
+	pub public_url: String,
+
+	#[serde(default = "default_reconciliation_interval_secs")]
+	pub reconciliation_interval_secs: i64
+}
+
+const fn default_reconciliation_interval_secs() -> i64 {60}
+
+#[derive(Default)]
+struct WebhookServerState {
+	account_sid: String,
+	auth_token: String,
+	public_url: String,
+
+	// These are in the order that they came in
+	pending_messages: Vec<serde_json::Value>
+}
+
+type SharedWebhookServerState = Arc<Mutex<WebhookServerState>>;
+
+pub struct TwilioWebhook {
+	state: SharedWebhookServerState,
+	reconciliation_interval: chrono::Duration
+}
+
+// This is an empty TwiML response, which means that Twilio should not reply to the texter
+const EMPTY_TWIML_RESPONSE: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response></Response>";
+
+//////////
+
+// This decodes a form value (where spaces are pluses, and everything else special is percent-encoded)
+fn url_decode(text: &str) -> String {
+	let bytes = text.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+
+	while i < bytes.len() {
+		let maybe_escaped_byte = (bytes[i] == b'%')
+			.then(|| text.get(i + 1..i + 3))
+			.flatten()
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+		match (bytes[i], maybe_escaped_byte) {
+			(_, Some(escaped_byte)) => {decoded.push(escaped_byte); i += 3;},
+			(b'+', None) => {decoded.push(b' '); i += 1;},
+			(byte, None) => {decoded.push(byte); i += 1;}
+		}
+	}
+
+	String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_form(body: &str) -> Vec<(String, String)> {
+	body.split('&').filter(|pair| !pair.is_empty()).map(|pair| {
+		let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+		(url_decode(name), url_decode(value))
+	}).collect()
+}
+
+// The URL is followed by each param's name and value (sorted by the name), with no separators
+fn is_signature_valid(auth_token: &str, url: &str, params: &[(String, String)], signature: &str) -> bool {
+	use base64::{engine::general_purpose::STANDARD, Engine};
+
+	let Ok(decoded_signature) = STANDARD.decode(signature) else {
+		return false;
+	};
+
+	let mut sorted_params: Vec<_> = params.iter().collect();
+	sorted_params.sort();
+
+	let mut mac = Hmac::<sha1::Sha1>::new_from_slice(auth_token.as_bytes()).expect("HMAC takes keys of any size");
+	mac.update(url.as_bytes());
+
+	for (name, value) in sorted_params {
+		mac.update(name.as_bytes());
+		mac.update(value.as_bytes());
+	}
+
+	// This comparison takes constant time
+	mac.verify_slice(&decoded_signature).is_ok()
+}
+
+//////////
+
+// This returns the response status and body
+fn receive_message(state: &Mutex<WebhookServerState>, maybe_signature: Option<&str>, body: &str) -> (&'static str, &'static str) {
+	let params = parse_form(body);
+	let mut state = state.lock().unwrap();
+
+	let signature_is_valid = maybe_signature.is_some_and(|signature|
+		is_signature_valid(&state.auth_token, &state.public_url, &params, signature)
+	);
+
+	if !signature_is_valid {
+		return ("403 Forbidden", "Invalid signature");
+	}
+
+	let param = |name| params.iter().find(|(param_name, _)| param_name == name).map(|(_, value)| value.as_str());
+
+	let (Some(message_sid), Some(account_sid), Some(from), Some(to)) =
+		(param("MessageSid"), param("AccountSid"), param("From"), param("To")) else {
+
+		return ("400 Bad Request", "Expected a message SID, account SID, sender, and recipient");
+	};
+
+	if account_sid != state.account_sid {
+		return ("403 Forbidden", "Unknown account");
+	}
+
+	// These URIs are the same as the ones in the message list, so that polled messages can be matched up with these
+	let message_uri = format!("/2010-04-01/Accounts/{account_sid}/Messages/{message_sid}.json");
+
+	// Twilio may retry a request that it thinks failed
+	if state.pending_messages.iter().any(|message| message["uri"] == message_uri.as_str()) {
+		return ("200 OK", EMPTY_TWIML_RESPONSE);
+	}
+
+	let message = serde_json::json!({
+		"uri": message_uri,
+		"from": from,
+		"to": to,
+		"body": param("Body").unwrap_or(""),
+		"direction": "inbound",
+		"date_created": chrono::Utc::now().to_rfc2822(),
+		"num_media": param("NumMedia").unwrap_or("0"),
+		"subresource_uris": {"media": format!("/2010-04-01/Accounts/{account_sid}/Messages/{message_sid}/Media.json")}
+	});
+
+	state.pending_messages.push(message);
+	("200 OK", EMPTY_TWIML_RESPONSE)
+}
+
+fn handle_request(state: &Mutex<WebhookServerState>, request: &HttpRequest) -> HttpResponse {
+	if request.method != "POST" {
+		return HttpResponse::new("405 Method Not Allowed", "text/plain", "Only POST is supported");
+	}
+
+	let (status, body) = receive_message(state, request.get_header("x-twilio-signature"), &String::from_utf8_lossy(&request.body));
+	let content_type = if body == EMPTY_TWIML_RESPONSE {"text/xml"} else {"text/plain"};
+	HttpResponse::new(status, content_type, body)
+}
+
+/* This starts a server at the given address, or reuses the one that is already running there
+(in which case, its credentials and public URL are updated, and its pending messages are kept). */
+fn start_or_reuse_server(config: &TwilioWebhookConfig, account_sid: &str, auth_token: &str) -> GenericResult<SharedWebhookServerState> {
+	let state = http_server::start_or_reuse_server(&config.address, "Twilio webhook", handle_request)?;
+
+	let mut locked_state = state.lock().unwrap();
+	locked_state.account_sid = account_sid.to_string();
+	locked_state.auth_token = auth_token.to_string();
+	locked_state.public_url = config.public_url.clone();
+	drop(locked_state);
+
+	Ok(state)
+}
+
+//////////
+
+impl TwilioWebhook {
+	pub fn new(config: &TwilioWebhookConfig, account_sid: &str, auth_token: &str) -> GenericResult<Self> {
+		Ok(Self {
+			state: start_or_reuse_server(config, account_sid, auth_token)?,
+			reconciliation_interval: chrono::Duration::seconds(config.reconciliation_interval_secs)
+		})
+	}
+
+	pub const fn get_reconciliation_interval(&self) -> chrono::Duration {
+		self.reconciliation_interval
+	}
+
+	/* The polled messages should be newest first (like Twilio gives them). This returns them with
+	the received messages that they don't have yet, newest first, and with up to `max_messages`. */
+	pub fn merge_with_polled_messages(&self, polled_messages: &[serde_json::Value], max_messages: usize) -> Vec<serde_json::Value> {
+		let mut state = self.state.lock().unwrap();
+		let pending_messages = &mut state.pending_messages;
+
+		pending_messages.retain(|pending_message|
+			!polled_messages.iter().any(|polled_message| polled_message["uri"] == pending_message["uri"])
+		);
+
+		// Any others would never be shown anyways
+		let num_excess_messages = pending_messages.len().saturating_sub(max_messages);
+		pending_messages.drain(..num_excess_messages);
+
+		pending_messages.iter().rev().chain(polled_messages).take(max_messages).cloned().collect()
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		net::{TcpListener, TcpStream},
+		io::{Read, Write}
+	};
+
+	use super::*;
+
+	const ACCOUNT_SID: &str = "AC_test";
+	const AUTH_TOKEN: &str = "test_auth_token";
+	const PUBLIC_URL: &str = "https://dashboard.example.org/twilio/incoming";
+
+	// This signature was computed separately (with Python's `hmac` module) for the form body below
+	const SIGNATURE: &str = "PmpDOGjtkLbCVjIWLDxeqhMbx9o=";
+
+	const FORM_BODY: &str = "MessageSid=SM_test&AccountSid=AC_test&From=%2B12075551234\
+		&To=%2B12075550000&Body=Play+some+jazz+please&NumMedia=0";
+
+	// This is a small stand-in for Twilio, which sends a webhook request and returns the response
+	fn send_webhook_request(address: &str, signature: &str, body: &str) -> String {
+		let mut stream = TcpStream::connect(address).unwrap();
+
+		write!(stream,
+			"POST /twilio/incoming HTTP/1.1\r\nHost: {address}\r\nX-Twilio-Signature: {signature}\r\n\
+			Content-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{body}",
+			body.len()
+		).unwrap();
+
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	fn start_test_server() -> (String, TwilioWebhook) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap().to_string();
+
+		let state = Arc::new(Mutex::new(WebhookServerState {
+			account_sid: ACCOUNT_SID.to_string(),
+			auth_token: AUTH_TOKEN.to_string(),
+			public_url: PUBLIC_URL.to_string(),
+			pending_messages: Vec::new()
+		}));
+
+		http_server::serve(listener, "test Twilio webhook", state.clone(), handle_request);
+
+		(address, TwilioWebhook {state, reconciliation_interval: chrono::Duration::seconds(60)})
+	}
+
+	#[test]
+	fn validates_signatures() {
+		let params = parse_form(FORM_BODY);
+		assert!(is_signature_valid(AUTH_TOKEN, PUBLIC_URL, &params, SIGNATURE));
+		assert!(!is_signature_valid("wrong_auth_token", PUBLIC_URL, &params, SIGNATURE));
+		assert!(!is_signature_valid(AUTH_TOKEN, "https://dashboard.example.org/elsewhere", &params, SIGNATURE));
+		assert!(!is_signature_valid(AUTH_TOKEN, PUBLIC_URL, &parse_form(&FORM_BODY.replace("jazz", "polka")), SIGNATURE));
+		assert!(!is_signature_valid(AUTH_TOKEN, PUBLIC_URL, &params, "not base64!"));
+	}
+
+	#[test]
+	fn receives_and_merges_messages() {
+		let (address, webhook) = start_test_server();
+
+		assert!(send_webhook_request(&address, "AAAAAAAAAAAAAAAAAAAAAAAAAAA=", FORM_BODY).starts_with("HTTP/1.1 403"));
+		assert!(webhook.merge_with_polled_messages(&[], 5).is_empty());
+
+		// The retried request should not make a duplicate message
+		for _ in 0..2 {
+			assert!(send_webhook_request(&address, SIGNATURE, FORM_BODY).starts_with("HTTP/1.1 200"));
+		}
+
+		let message_uri = "/2010-04-01/Accounts/AC_test/Messages/SM_test.json";
+		let polled_message = serde_json::json!({"uri": "/2010-04-01/Accounts/AC_test/Messages/SM_old.json"});
+
+		let merged = webhook.merge_with_polled_messages(std::slice::from_ref(&polled_message), 5);
+		assert_eq!(merged.len(), 2);
+		assert_eq!(merged[0]["uri"], message_uri);
+		assert_eq!(merged[0]["from"], "+12075551234");
+		assert_eq!(merged[0]["body"], "Play some jazz please");
+		assert_eq!(merged[1], polled_message);
+
+		// Once a poll has the message, it's no longer pending
+		let merged = webhook.merge_with_polled_messages(&[serde_json::json!({"uri": message_uri})], 5);
+		assert_eq!(merged.len(), 1);
+		assert!(webhook.state.lock().unwrap().pending_messages.is_empty());
+	}
+}
@@ -1,192 +1,702 @@
-/* TODO:
-- Actually implement this
-- Make the general structure of the text updater fns less repetitive
-- Consider using an alternative API
-*/
-
 use std::borrow::Cow;
 
+use serde::Deserialize;
+
 use crate::{
-	// request,
+	request,
 
-	texture::{DisplayText, TextDisplayInfo, TextureCreationInfo},
+	texture::TextTextureScrollFn,
 
 	utility_types::{
 		vec2f::Vec2f,
 		generic_result::*,
+		update_rate::UpdateRate,
 		dynamic_optional::DynamicOptional,
-		update_rate::{UpdateRateCreator, Seconds}
+		thread_task::{Updatable, ContinuallyUpdated}
 	},
 
 	window_tree::{
-		ColorSDL,
 		Window,
+		ColorSDL,
 		WindowContents,
 		WindowUpdaterParams
 	},
 
-	dashboard_defs::shared_window_state::SharedWindowState
+	dashboard_defs::{
+		updatable_text_pattern,
+		shared_window_state::SharedWindowState
+	}
 };
 
-// TODO: fill this with stuff
-struct WeatherWindowState {
+/* This gets the current weather from OpenWeatherMap on a background thread, and shows it as a line of text
+(an emoji for the conditions, what the temperature feels like, and notes on wind and precipitation).
+It also gets a forecast (for the next few hours and days), and optionally any severe weather alerts.
+
+- 1000 API calls are free every day, which is around one every 1.44 minutes.
+- The data only updates every 10 minutes anyways (https://openweathermap.org/appid), so it's only requested that often.
+- Alerts come from the One Call API (which has its own subscription), so they're only requested if `check_alerts` is set.
+An active alert takes priority over the normal weather text, and it's shown in full by the alert banner window
+(since DJs are expected to read emergency weather on air).
+- For testing without the real API, the base URLs can point to the mock server (see `weather_mock_server.rs`). */
+
+const DEFAULT_API_BASE_URL: &str = "https://api.openweathermap.org/data/2.5";
+const DEFAULT_ALERTS_API_BASE_URL: &str = "https://api.openweathermap.org/data/3.0";
+const MIN_TIME_BETWEEN_REQUESTS: chrono::Duration = chrono::Duration::minutes(10);
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherUnits {
+	Metric, Imperial
+}
+
+// Note: the state code can be empty here!
+#[derive(Deserialize, Clone)]
+pub struct WeatherConfig {
+	pub city_name: String,
+	pub state_code: String,
+	pub country_code: String,
+	pub units: WeatherUnits,
+
+	#[serde(default = "default_api_base_url")]
+	pub api_base_url: String,
+
+	#[serde(default)]
+	pub check_alerts: bool,
+
+	#[serde(default = "default_alerts_api_base_url")]
+	pub alerts_api_base_url: String
+}
+
+fn default_api_base_url() -> String {
+	DEFAULT_API_BASE_URL.to_string()
+}
+
+fn default_alerts_api_base_url() -> String {
+	DEFAULT_ALERTS_API_BASE_URL.to_string()
+}
+
+////////// These are the parts of the OpenWeatherMap response that are used
+
+// Note: all of the fields are optional in the API docs, so they're optional here too
+
+#[derive(Deserialize, Clone)]
+struct WeatherDesc1 {
+	feels_like: Option<f32>,
+	temp: Option<f32>,
+	temp_min: Option<f32>,
+	temp_max: Option<f32>
+}
+
+#[derive(Deserialize, Clone)]
+struct WeatherDesc2 {
+	description: Option<String>,
+	icon: Option<String>
+}
+
+#[derive(Deserialize, Clone)]
+struct WindDesc {
+	gust: Option<f32>,
+	speed: Option<f32>
+}
+
+// This is used for both rain and snow
+#[derive(Deserialize, Clone)]
+struct PrecipitationDesc {
+	#[serde(rename = "1h")]
+	one_hour: Option<f32>
+}
+
+#[derive(Deserialize, Clone)]
+struct Coordinates {
+	lat: f32,
+	lon: f32
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WeatherInfo {
+	main: WeatherDesc1,
+	weather: Vec<WeatherDesc2>,
+
+	wind: Option<WindDesc>,
+	rain: Option<PrecipitationDesc>,
+	snow: Option<PrecipitationDesc>,
+
+	coord: Option<Coordinates> // This is needed for requesting alerts
+}
+
+// Each forecast entry is 3 hours apart
+#[derive(Deserialize, Clone)]
+struct ForecastEntry {
+	dt: i64, // This is a Unix timestamp
+	main: WeatherDesc1,
+	weather: Vec<WeatherDesc2>,
+	pop: Option<f32> // This is the probability of precipitation (from 0 to 1)
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ForecastInfo {
+	list: Vec<ForecastEntry>
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WeatherAlert {
+	sender_name: Option<String>,
+	event: String,
+	start: i64, // These are Unix timestamps
+	end: i64,
+	description: Option<String>
+}
+
+#[derive(Deserialize)]
+struct AlertsInfo {
+	#[serde(default)] // There's no `alerts` field when there are no alerts
+	alerts: Vec<WeatherAlert>
+}
+
+//////////
+
+#[derive(Clone)]
+struct WeatherStateData {
 	api_key: String,
-	location: String
-}
-
-pub fn weather_updater_fn(params: WindowUpdaterParams) -> MaybeError {
-	let weather_changed = true;
-	let weather_string = "Rain (32f). So cold.";
-	let weather_text_color = ColorSDL::BLACK;
-
-	/*
-	- 1000 API calls free every day
-	- That's 1000 per 24 hrs
-	- Our 41.666 per hour, or around once per 1.444 minutes
-	- To make stuff easy, do once every 2 minutes
-	- TODO: do it once every 10 minutes (that's how frequently the data updates: https://openweathermap.org/appid)
-	*/
-
-	// let individual_window_state = window.get_state::<WeatherWindowState>();
-	let inner_shared_state = params.shared_window_state.get::<SharedWindowState>();
-
-	/*
-	// TODO: perhaps don't build request urls, just build request objects directly
-	let url = request::build_url("https://api.openweathermap.org/data/2.5/weather",
-		&[],
-
-		&[
-			("q", Cow::Borrowed(&individual_window_state.location)),
-			("appid", Cow::Borrowed(&individual_window_state.api_key)),
-			("units", Cow::Borrowed("metric"))
-		]
-	);
+	config: WeatherConfig,
 
-	//////////
+	maybe_curr_weather: Option<WeatherInfo>,
+	maybe_forecast: Option<ForecastInfo>,
+	alerts: Vec<WeatherAlert>,
+	maybe_last_request_time: Option<chrono::DateTime<chrono::Utc>>
+}
 
-	// TODO: why are all the damn fields optional?
+pub struct WeatherState {
+	continually_updated: ContinuallyUpdated<WeatherStateData>,
+	units: WeatherUnits
+}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct WeatherDesc1 {
-		feels_like: Option<f32>,
-		temp: Option<f32>,
-		pressure: Option<i32>,
-		humidity: Option<i32>,
-		temp_min: Option<f32>,
-		temp_max: Option<f32>
+impl WeatherUnits {
+	const fn get_api_name(self) -> &'static str {
+		match self {
+			Self::Metric => "metric",
+			Self::Imperial => "imperial"
+		}
+	}
+
+	const fn get_temperature_suffix(self) -> &'static str {
+		match self {
+			Self::Metric => "°C",
+			Self::Imperial => "°F"
+		}
+	}
+
+	const fn get_speed_suffix(self) -> &'static str {
+		match self {
+			Self::Metric => "m/s",
+			Self::Imperial => "mph"
+		}
+	}
+}
+
+impl WeatherStateData {
+	// The location params are either a location name, or coordinates
+	fn do_weather_request<T: for<'de> Deserialize<'de>>(&self, base_url: &str,
+		endpoint: &str, location_params: &[(&str, Cow<str>)]) -> GenericResult<T> {
+
+		let mut query_params = location_params.to_vec();
+		query_params.push(("appid", Cow::Borrowed(&self.api_key)));
+		query_params.push(("units", Cow::Borrowed(self.config.units.get_api_name())));
+
+		// TODO: perhaps don't build request urls, just build request objects directly
+		let url = request::build_url(base_url, &[Cow::Borrowed(endpoint)], &query_params);
+		request::as_type(request::get(&url))
+	}
+
+	fn get_alerts(&self, coordinates: &Coordinates) -> GenericResult<Vec<WeatherAlert>> {
+		let alerts_info: AlertsInfo = self.do_weather_request(&self.config.alerts_api_base_url, "onecall",
+			&[
+				("lat", Cow::Owned(coordinates.lat.to_string())),
+				("lon", Cow::Owned(coordinates.lon.to_string())),
+				("exclude", Cow::Borrowed("current,minutely,hourly,daily"))
+			]
+		)?;
+
+		Ok(alerts_info.alerts)
 	}
+}
+
+impl Updatable for WeatherStateData {
+	type Param = ();
+
+	// The main thread asks for updates more often than the weather data changes, so most of these do nothing
+	fn update(&mut self, _: &Self::Param) -> MaybeError {
+		let curr_time = chrono::Utc::now();
+
+		if let Some(last_request_time) = self.maybe_last_request_time {
+			if curr_time.signed_duration_since(last_request_time) < MIN_TIME_BETWEEN_REQUESTS {
+				return Ok(());
+			}
+		}
+
+		// This is set before the requests, so that failing requests are not retried too often either
+		self.maybe_last_request_time = Some(curr_time);
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct WeatherDesc2 {
-		description: Option<String>,
-		icon: Option<String>,
-		id: Option<i32>,
-		main: Option<String>
-		// visibility: Option<i32>
+		let config = &self.config;
+		let location = [config.city_name.as_str(), &config.state_code, &config.country_code].join(",");
+		let location_params = [("q", Cow::Owned(location))];
+
+		let curr_weather: WeatherInfo = self.do_weather_request(&config.api_base_url, "weather", &location_params)?;
+		let maybe_coordinates = curr_weather.coord.clone();
+		self.maybe_curr_weather = Some(curr_weather);
+
+		/* The forecast and alerts failing is only logged (and the previous ones are kept), so that the current
+		weather still gets shown. The alerts need a One Call subscription, so they may fail on every update. */
+		match self.do_weather_request(&self.config.api_base_url, "forecast", &location_params) {
+			Ok(forecast) => self.maybe_forecast = Some(forecast),
+			Err(err) => log::error!("Could not get the weather forecast: '{err}'.")
+		}
+
+		if self.config.check_alerts {
+			let alerts_result = maybe_coordinates
+				.context("The current weather had no coordinates, so alerts can't be requested")
+				.and_then(|coordinates| self.get_alerts(&coordinates));
+
+			match alerts_result {
+				Ok(alerts) => self.alerts = alerts,
+				Err(err) => log::error!("Could not get the weather alerts: '{err}'.")
+			}
+		}
+
+		Ok(())
 	}
+}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct WindDesc {
-		deg: Option<i32>,
-		gust: Option<f32>,
-		speed: Option<f32>
+impl WeatherState {
+	pub fn new(api_key: &str, config: &WeatherConfig) -> Self {
+		let data = WeatherStateData {
+			api_key: api_key.to_string(),
+			config: config.clone(),
+			maybe_curr_weather: None,
+			maybe_forecast: None,
+			alerts: Vec::new(),
+			maybe_last_request_time: None
+		};
+
+		Self {
+			continually_updated: ContinuallyUpdated::new(&data, &(), "Weather"),
+			units: config.units
+		}
 	}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct CloudsDesc {
-		all: Option<i32>
+	// This returns false if something failed with the continual updater
+	pub fn update(&mut self) -> GenericResult<bool> {
+		self.continually_updated.update(&())
 	}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct RainDesc {
-		// all: i32
+	pub const fn get_curr_weather(&self) -> Option<&WeatherInfo> {
+		self.continually_updated.get_data().maybe_curr_weather.as_ref()
+	}
 
-		#[serde(rename = "1h")]
-		one_hour: Option<f32>
+	pub const fn get_forecast(&self) -> Option<&ForecastInfo> {
+		self.continually_updated.get_data().maybe_forecast.as_ref()
 	}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct SnowDesc {
-		all: Option<i32>
+	// These are the alerts that are in effect right now
+	pub fn get_active_alerts(&self) -> Vec<&WeatherAlert> {
+		let curr_timestamp = chrono::Utc::now().timestamp();
+
+		self.continually_updated.get_data().alerts.iter()
+			.filter(|alert| (alert.start..alert.end).contains(&curr_timestamp))
+			.collect()
 	}
 
-	#[derive(serde::Deserialize, Debug)] // TODO: remove `Debug`
-	struct WeatherInfo {
-		main: WeatherDesc1,
-		weather: [WeatherDesc2; 1],
+	pub const fn get_units(&self) -> WeatherUnits {
+		self.units
+	}
+}
 
-		wind: Option<WindDesc>,
-		clouds: Option<CloudsDesc>,
-		rain: Option<RainDesc>,
-		snow: Option<SnowDesc>
+//////////
+
+// The icon codes are listed here: https://openweathermap.org/weather-conditions
+fn get_icon_emoji(icon: &str) -> &'static str {
+	let is_night = icon.ends_with('n');
+
+	match icon.get(..2) {
+		Some("01") => if is_night {"🌙"} else {"☀️"},
+		Some("02") => if is_night {"☁️"} else {"⛅"},
+		Some("03" | "04") => "☁️",
+		Some("09") => "🌧️",
+		Some("10") => if is_night {"🌧️"} else {"🌦️"},
+		Some("11") => "⛈️",
+		Some("13") => "❄️",
+		Some("50") => "🌫️",
+		_ => "🌡️"
 	}
+}
+
+/* This looks like "🌦️ Light rain. Feels like 28°F. Wind at 12 mph, gusting to 20 mph. 1.2 mm of rain in the last hour."
+Anything that is missing from the weather info is left out (and if everything is, the text is empty, which hides the window). */
+fn make_weather_text(weather: &WeatherInfo, units: WeatherUnits) -> String {
+	let mut notes = Vec::new();
 
-	//////////
+	if let Some(conditions) = weather.weather.first() {
+		let emoji = conditions.icon.as_deref().map_or("🌡️", get_icon_emoji);
 
-	let json = request::as_type(request::get(&url))?;
-	let w: WeatherInfo = serde_json::from_value(json)?;
+		match &conditions.description {
+			Some(description) => {
+				let mut chars = description.chars();
+				let capitalized: String = chars.next().into_iter().flat_map(char::to_uppercase).chain(chars).collect();
+				notes.push(format!("{emoji} {capitalized}"));
+			},
 
-	fn maybe_add<T: std::fmt::Display>(string: &mut String, field: Option<T>, formatter: fn(T) -> String) {
-		if let Some(inner) = field {
-			*string += &(formatter(inner) + ". ");
+			None => notes.push(emoji.to_string())
 		}
 	}
 
-	let mut weather_string = String::new();
-	maybe_add(&mut weather_string, w.main.feels_like, |t| format!("It feels like {t}"));
-	println!("ws = {:?}", weather_string);
-	*/
+	let temperature_suffix = units.get_temperature_suffix();
 
-	/*
-	Deciding what data to show (I don't want to go overboard):
-	1. (MOST IMPORTANT) An emoji for the given icon (I have this data)
-	2. (SECOND-MOST IMPORTANT) What temperature it feels like
-	3. (MID-LATER) If there's high pressure or humidity, say "It's a scorcher!" Or "It's a hot one today!".
-	4. (LATER) If it's windy, show the wind gust and speed (same for rain, snow, etc.)
-	*/
+	match (weather.main.feels_like, weather.main.temp) {
+		(Some(feels_like), _) => notes.push(format!("Feels like {feels_like:.0}{temperature_suffix}")),
+		(None, Some(temp)) => notes.push(format!("It's {temp:.0}{temperature_suffix}")),
+		(None, None) => {}
+	}
 
-	let texture_creation_info = TextureCreationInfo::Text((
-		Cow::Borrowed(inner_shared_state.font_info),
+	if let Some(WindDesc {speed: Some(speed), gust}) = &weather.wind {
+		let speed_suffix = units.get_speed_suffix();
 
-		TextDisplayInfo {
-			text: DisplayText::new(weather_string),
-			color: weather_text_color,
-			pixel_area: params.area_drawn_to_screen,
+		notes.push(match gust {
+			Some(gust) if gust > speed => format!("Wind at {speed:.0} {speed_suffix}, gusting to {gust:.0} {speed_suffix}"),
+			_ => format!("Wind at {speed:.0} {speed_suffix}")
+		});
+	}
 
-			scroll_fn: |seed, _| {
-				let repeat_rate_secs = 3.0;
-				let base_scroll = (seed % repeat_rate_secs) / repeat_rate_secs;
-				(1.0 - base_scroll, true)
-			}
+	for (maybe_precipitation, name) in [(&weather.rain, "rain"), (&weather.snow, "snow")] {
+		if let Some(PrecipitationDesc {one_hour: Some(amount)}) = maybe_precipitation {
+			notes.push(format!("{amount:.1} mm of {name} in the last hour"));
 		}
-	));
+	}
+
+	if notes.is_empty() {
+		return String::new();
+	}
 
-	params.window.get_contents_mut().update_as_texture(
-		weather_changed,
-		params.texture_pool,
-		&texture_creation_info,
-		inner_shared_state.fallback_texture_creation_info
+	notes.join(". ") + "."
+}
+
+// This looks like "3 PM ☁️ 34°F (60%)" (the chance of precipitation is only shown if there's some)
+fn make_hourly_forecast_text(forecast: &ForecastInfo, units: WeatherUnits, num_hours: usize) -> String {
+	let temperature_suffix = units.get_temperature_suffix();
+	let curr_timestamp = chrono::Utc::now().timestamp();
+
+	forecast.list.iter().filter(|entry| entry.dt > curr_timestamp).take(num_hours).map(|entry| {
+		let time = chrono::DateTime::from_timestamp(entry.dt, 0).map_or("?".to_string(),
+			|time| time.with_timezone(&chrono::Local).format("%-I %p").to_string());
+
+		let emoji = entry.weather.first().and_then(|conditions| conditions.icon.as_deref()).map_or("🌡️", get_icon_emoji);
+		let temp = entry.main.temp.map_or("?".to_string(), |temp| format!("{temp:.0}{temperature_suffix}"));
+
+		match entry.pop {
+			Some(pop) if pop >= 0.1 => format!("{time} {emoji} {temp} ({:.0}%)", pop * 100.0),
+			_ => format!("{time} {emoji} {temp}")
+		}
+	}).collect::<Vec<_>>().join("   ")
+}
+
+/* This looks like "Tue ⛅ 40°/28°F". The forecast entries are grouped by local day (starting with tomorrow),
+and each day's emoji comes from the entry that is closest to the early afternoon. */
+fn make_daily_forecast_text(forecast: &ForecastInfo, units: WeatherUnits, num_days: usize) -> String {
+	const REPRESENTATIVE_HOUR: i64 = 13;
+
+	type DayEntries<'a> = Vec<(chrono::DateTime<chrono::Local>, &'a ForecastEntry)>;
+
+	let today = chrono::Local::now().date_naive();
+	let mut days: Vec<(chrono::NaiveDate, DayEntries)> = Vec::new();
+
+	for entry in &forecast.list {
+		let Some(time) = chrono::DateTime::from_timestamp(entry.dt, 0) else {continue};
+		let local_time = time.with_timezone(&chrono::Local);
+		let date = local_time.date_naive();
+
+		if date <= today {continue;}
+
+		match days.last_mut() {
+			Some((last_date, entries)) if *last_date == date => entries.push((local_time, entry)),
+			_ => days.push((date, vec![(local_time, entry)]))
+		}
+	}
+
+	days.iter().take(num_days).map(|(date, entries)| {
+		use chrono::Timelike;
+
+		let max_temp = entries.iter().filter_map(|(_, entry)| entry.main.temp_max.or(entry.main.temp)).reduce(f32::max);
+		let min_temp = entries.iter().filter_map(|(_, entry)| entry.main.temp_min.or(entry.main.temp)).reduce(f32::min);
+
+		let emoji = entries.iter()
+			.min_by_key(|(time, _)| (i64::from(time.hour()) - REPRESENTATIVE_HOUR).abs())
+			.and_then(|(_, entry)| entry.weather.first())
+			.and_then(|conditions| conditions.icon.as_deref())
+			.map_or("🌡️", get_icon_emoji);
+
+		let temps = match (max_temp, min_temp) {
+			(Some(max_temp), Some(min_temp)) => format!("{max_temp:.0}°/{min_temp:.0}{}", units.get_temperature_suffix()),
+			_ => "?".to_string()
+		};
+
+		format!("{} {emoji} {temps}", date.format("%a"))
+	}).collect::<Vec<_>>().join("   ")
+}
+
+// This looks like "⚠️ Blizzard Warning (NWS Gray ME): Heavy snow and strong winds..."
+fn make_alert_text(alert: &WeatherAlert) -> String {
+	let mut text = format!("⚠️ {}", alert.event);
+
+	if let Some(sender_name) = &alert.sender_name {
+		text += &format!(" ({sender_name})");
+	}
+
+	if let Some(description) = &alert.description {
+		// Alert descriptions are often wrapped onto multiple lines
+		text += ": ";
+		text += &description.split_whitespace().collect::<Vec<_>>().join(" ");
+	}
+
+	text
+}
+
+//////////
+
+#[derive(Clone, Copy)]
+enum WeatherTextKind {
+	Current,
+	HourlyForecast {num_hours: usize},
+	DailyForecast {num_days: usize},
+	AlertBanner
+}
+
+#[derive(Clone)]
+struct WeatherTextWindowState {
+	kind: WeatherTextKind,
+	text: String
+}
+
+impl WeatherTextKind {
+	// This returns `None` if the window should be hidden
+	fn make_text(self, maybe_weather_state: Option<&WeatherState>) -> Option<String> {
+		let Some(weather_state) = maybe_weather_state else {
+			return matches!(self, Self::Current).then(|| "The weather is not configured.".to_string());
+		};
+
+		let units = weather_state.get_units();
+		let active_alerts = weather_state.get_active_alerts();
+
+		match self {
+			// Any alerts take priority over the normal weather text
+			Self::Current if !active_alerts.is_empty() => Some(
+				active_alerts.iter().map(|alert| format!("⚠️ {}", alert.event)).collect::<Vec<_>>().join(". ") + "!"
+			),
+
+			Self::Current => Some(match weather_state.get_curr_weather() {
+				Some(weather) => make_weather_text(weather, units),
+				None => "Getting the weather…".to_string()
+			}),
+
+			Self::HourlyForecast {num_hours} =>
+				weather_state.get_forecast().map(|forecast| make_hourly_forecast_text(forecast, units, num_hours)),
+
+			Self::DailyForecast {num_days} =>
+				weather_state.get_forecast().map(|forecast| make_daily_forecast_text(forecast, units, num_days)),
+
+			Self::AlertBanner => (!active_alerts.is_empty()).then(||
+				active_alerts.iter().map(|alert| make_alert_text(alert)).collect::<Vec<_>>().join("      ")
+			)
+		}
+	}
+}
+
+impl updatable_text_pattern::UpdatableTextWindowMethods for WeatherTextWindowState {
+	fn should_skip_update(updater_params: &mut WindowUpdaterParams) -> bool {
+		let inner_shared_state = updater_params.shared_window_state.get::<SharedWindowState>();
+
+		let wrapped_individual_state = updater_params.window.get_state_mut
+			::<updatable_text_pattern::UpdatableTextWindowFields<WeatherTextWindowState>>();
+
+		let weather_window_state = &mut wrapped_individual_state.inner;
+		let maybe_text = weather_window_state.kind.make_text(inner_shared_state.maybe_weather_state.as_ref());
+
+		let Some(text) = maybe_text.filter(|text| !text.is_empty()) else {
+			weather_window_state.text.clear();
+			updater_params.window.set_draw_skipping(true);
+			return true;
+		};
+
+		if text == weather_window_state.text {
+			return true;
+		}
+
+		weather_window_state.text = text;
+		updater_params.window.set_draw_skipping(false);
+		false
+	}
+
+	fn compute_within_updater<'a>(inner_shared_state: &'a SharedWindowState) -> updatable_text_pattern::ComputedInTextUpdater<'a> {
+		(Cow::Borrowed(inner_shared_state.font_info), " ")
+	}
+
+	fn extract_text(&self) -> Cow<'_, str> {
+		Cow::Borrowed(&self.text)
+	}
+
+	fn extract_texture_contents(window_contents: &mut WindowContents) -> &mut WindowContents {
+		let WindowContents::Many(all_contents) = window_contents
+		else {panic!("The weather window contents was expected to be a list!")};
+		&mut all_contents[1]
+	}
+}
+
+fn make_weather_text_window(kind: WeatherTextKind, top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
+	scroll_fn: TextTextureScrollFn, text_color: ColorSDL, maybe_background_color: Option<ColorSDL>) -> Window {
+
+	let fields = updatable_text_pattern::UpdatableTextWindowFields {
+		inner: WeatherTextWindowState {kind, text: String::new()},
+		text_color,
+		scroll_fn,
+		update_rate,
+		maybe_border_color: None
+	};
+
+	let background = maybe_background_color.map_or(WindowContents::Nothing, WindowContents::Color);
+
+	let mut window = updatable_text_pattern::make_window(
+		fields, top_left, size,
+		WindowContents::Many(vec![background, WindowContents::Nothing])
+	);
+
+	window.set_draw_skipping(true);
+	window
+}
+
+//////////
+
+// The weather itself comes from the shared window state (which is configured in the app config)
+pub fn make_weather_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
+	text_color: ColorSDL, background_color: ColorSDL) -> Window {
+
+	make_weather_text_window(
+		WeatherTextKind::Current, top_left, size, update_rate,
+
+		|seed, _| {
+			let repeat_rate_secs = 3.0;
+			let base_scroll = (seed % repeat_rate_secs) / repeat_rate_secs;
+			(1.0 - base_scroll, true)
+		},
+
+		text_color, Some(background_color)
 	)
 }
 
-// Note: the state code can be empty here!
-pub fn make_weather_window(
-	top_left: Vec2f, size: Vec2f,
-	update_rate_creator: UpdateRateCreator, api_key: &str,
-	city_name: &str, state_code: &str, country_code: &str) -> Window {
+// The next few hours are on the top row, and the next few days are on the bottom one
+pub fn make_weather_forecast_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
+	num_hours: usize, num_days: usize, text_color: ColorSDL, maybe_background_color: Option<ColorSDL>) -> Window {
 
-	const UPDATE_RATE_SECS: Seconds = 60.0 * 10.0; // Once every 10 minutes (this is how frequent the weather data is)
+	let scroll_fn = |seed: f64, _| (seed.sin() * 0.5 + 0.5, false);
 
-	let weather_update_rate = update_rate_creator.new_instance(UPDATE_RATE_SECS);
-	let location = [city_name, state_code, country_code].join(",");
+	let rows = [
+		WeatherTextKind::HourlyForecast {num_hours},
+		WeatherTextKind::DailyForecast {num_days}
+	].into_iter().enumerate().map(|(index, kind)|
+		make_weather_text_window(kind, Vec2f::new(0.0, index as f32 * 0.5), Vec2f::new(1.0, 0.5),
+			update_rate, scroll_fn, text_color, None)
+	).collect();
 
 	Window::new(
-		Some((weather_updater_fn, weather_update_rate)),
-		DynamicOptional::new(WeatherWindowState {api_key: api_key.to_string(), location}),
-		WindowContents::Color(ColorSDL::RGB(255, 0, 255)),
-		Some(ColorSDL::RED),
+		None,
+		DynamicOptional::NONE,
+		maybe_background_color.map_or(WindowContents::Nothing, WindowContents::Color),
+		None,
 		top_left,
 		size,
-		None
+		Some(rows)
+	)
+}
+
+// This is hidden unless there's an active alert (and it scrolls like a news ticker)
+pub fn make_weather_alert_banner_window(top_left: Vec2f, size: Vec2f, update_rate: UpdateRate,
+	text_color: ColorSDL, background_color: ColorSDL) -> Window {
+
+	make_weather_text_window(
+		WeatherTextKind::AlertBanner, top_left, size, update_rate,
+
+		|seed, text_fits_in_box| {
+			if text_fits_in_box {return (0.0, false);}
+
+			let repeat_rate_secs = 12.0;
+			((seed % repeat_rate_secs) / repeat_rate_secs, true)
+		},
+
+		text_color, Some(background_color)
 	)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::dashboard_defs::weather_mock_server::{self, WeatherMockServerConfig};
+
+	const MOCK_SERVER_ADDRESS: &str = "127.0.0.1:47181";
+
+	// This requests the weather from the mock server, serving from the given fixture directory
+	fn get_weather_from_fixture(fixture_name: &str) -> WeatherStateData {
+		let mock_server_config = WeatherMockServerConfig {
+			address: MOCK_SERVER_ADDRESS.to_string(),
+			fixture_dir: format!("assets/weather_fixtures/{fixture_name}")
+		};
+
+		weather_mock_server::start_or_update(&mock_server_config).unwrap();
+		let base_url = mock_server_config.get_base_url();
+
+		let mut data = WeatherStateData {
+			api_key: "test".to_string(),
+
+			config: WeatherConfig {
+				city_name: "Brunswick".to_string(),
+				state_code: "ME".to_string(),
+				country_code: "US".to_string(),
+				units: WeatherUnits::Imperial,
+				api_base_url: base_url.clone(),
+				check_alerts: true,
+				alerts_api_base_url: base_url
+			},
+
+			maybe_curr_weather: None,
+			maybe_forecast: None,
+			alerts: Vec::new(),
+			maybe_last_request_time: None
+		};
+
+		data.update(&()).unwrap();
+		data
+	}
+
+	// Both fixtures are checked in one test, since they share one mock server
+	#[test]
+	fn renders_the_mock_server_fixtures() {
+		let typical = get_weather_from_fixture("typical");
+
+		assert_eq!(
+			make_weather_text(typical.maybe_curr_weather.as_ref().unwrap(), WeatherUnits::Imperial),
+			"🌦️ Light rain. Feels like 28°F. Wind at 12 mph, gusting to 20 mph. 1.2 mm of rain in the last hour."
+		);
+
+		assert_eq!(
+			make_weather_text(typical.maybe_curr_weather.as_ref().unwrap(), WeatherUnits::Metric),
+			"🌦️ Light rain. Feels like 28°C. Wind at 12 m/s, gusting to 20 m/s. 1.2 mm of rain in the last hour."
+		);
+
+		assert_eq!(typical.maybe_forecast.as_ref().unwrap().list.len(), 32);
+		assert_eq!(typical.alerts.len(), 1);
+		assert!(make_alert_text(&typical.alerts[0]).starts_with("⚠️ Blizzard Warning (NWS Gray ME): ...BLIZZARD WARNING IN EFFECT"));
+
+		// There are no conditions, temperatures, wind, precipitation, coordinates (so no alerts), or forecast entries here
+		let edge_cases = get_weather_from_fixture("edge_cases");
+
+		assert_eq!(make_weather_text(edge_cases.maybe_curr_weather.as_ref().unwrap(), WeatherUnits::Imperial), "");
+		assert!(edge_cases.maybe_forecast.as_ref().unwrap().list.is_empty());
+		assert!(edge_cases.alerts.is_empty());
+	}
+}
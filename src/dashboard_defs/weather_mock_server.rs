@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+
+use regex::Regex;
+
+use crate::utility_types::{
+	generic_result::*,
+	http_server::{self, HttpRequest, HttpResponse}
+};
+
+/* This is a small local stand-in for the OpenWeatherMap API, which serves canned JSON from a fixture directory.
+It lets the weather be developed and tested without the real API (or without using up its quota).
+
+- The fixture directory has a `weather.json`, `forecast.json`, and `onecall.json`, each in the same form as an OpenWeatherMap response.
+- The fixture files are reread on every request, so they can be edited while the dashboard is running. Query params are ignored.
+- In the fixture files, `{unix_now}` (or `{unix_now+N}` / `{unix_now-N}`, for N seconds away from now) is replaced with a Unix timestamp. */
+
+#[derive(serde::Deserialize, Clone)]
+pub struct WeatherMockServerConfig {
+	pub address: String,
+	pub fixture_dir: String
+}
+
+lazy_static::lazy_static!(
+	static ref UNIX_TIME_PLACEHOLDER_REGEXP: Regex = Regex::new(r#"\{unix_now(?:([+-])(\d+))?\}"#).unwrap();
+);
+
+const ENDPOINTS: [&str; 3] = ["weather", "forecast", "onecall"];
+
+//////////
+
+impl WeatherMockServerConfig {
+	// This is used for both the weather and alerts base URLs
+	pub fn get_base_url(&self) -> String {
+		format!("http://{}", self.address)
+	}
+}
+
+/* This starts a server at the configured address. If one is already running there
+(since the dashboard can be rebuilt while running), it just switches its fixture directory. */
+pub fn start_or_update(config: &WeatherMockServerConfig) -> MaybeError {
+	let fixture_dir = http_server::start_or_reuse_server(&config.address, "mock OpenWeatherMap", handle_request)?;
+	*fixture_dir.lock().unwrap() = config.fixture_dir.clone();
+	Ok(())
+}
+
+//////////
+
+fn handle_request(fixture_dir: &Mutex<String>, request: &HttpRequest) -> HttpResponse {
+	let endpoint = request.get_path().trim_matches('/');
+
+	let maybe_fixture = ENDPOINTS.contains(&endpoint).then(|| {
+		let fixture_path = format!("{}/{endpoint}.json", fixture_dir.lock().unwrap());
+		std::fs::read_to_string(fixture_path).ok()
+	}).flatten();
+
+	match maybe_fixture {
+		Some(fixture) => HttpResponse::new("200 OK", "application/json", fill_in_placeholders(&fixture)),
+		None => HttpResponse::new("404 Not Found", "text/plain", "Not found")
+	}
+}
+
+fn fill_in_placeholders(fixture: &str) -> String {
+	let curr_time = chrono::Utc::now();
+
+	UNIX_TIME_PLACEHOLDER_REGEXP.replace_all(fixture, |captures: &regex::Captures| {
+		let offset_secs: i64 = captures.get(2).map_or(0, |secs| secs.as_str().parse().unwrap());
+		let sign = if captures.get(1).is_some_and(|sign| sign.as_str() == "-") {-1} else {1};
+		(curr_time + chrono::Duration::seconds(sign * offset_secs)).timestamp().to_string()
+	}).into_owned()
+}
@@ -5,10 +5,13 @@ mod window_tree;
 mod utility_types;
 mod dashboard_defs;
 
+#[cfg(test)]
+mod snapshot_testing;
+
 // Worked from this in the beginning: https://blog.logrocket.com/using-sdl2-bindings-rust/
 
 // https://gamedev.stackexchange.com/questions/137882/
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, PartialEq)]
 enum ScreenOption {
 	/* This runs it as a small app window, which can optionally
 	be borderless, and optionally be translucent too. */
@@ -21,7 +24,14 @@ enum ScreenOption {
 	/* This makes the OS change its output rendering resolution to one of
 	the officially supported ones (which you can find in your settings app).
 	You cannot exit from this window while the app is still running. */
-	Fullscreen
+	Fullscreen,
+
+	/* This renders offscreen with a software renderer (so no display or GPU is needed),
+	and writes PNG snapshots of the dashboard. The params are the width, height, FPS,
+	snapshot path, and the number of seconds between snapshots. If there is no number
+	of seconds between snapshots, one snapshot is written after a warmup period,
+	and then the app exits. Otherwise, the snapshot file is overwritten periodically. */
+	Headless(u32, u32, u32, String, Option<utility_types::update_rate::Seconds>)
 }
 
 #[derive(serde::Deserialize)]
@@ -33,9 +43,14 @@ struct AppConfig {
 	screen_option: ScreenOption,
 	hide_cursor: bool,
 	use_linear_filtering: bool,
-	background_color: (u8, u8, u8)
+	background_color: (u8, u8, u8),
+
+	#[serde(flatten)]
+	dashboard_config: dashboard_defs::dashboard::DashboardConfig
 }
 
+const APP_CONFIG_PATH: &str = "assets/app_config.json";
+
 fn get_fps(sdl_timer: &sdl2::TimerSubsystem,
 	sdl_prev_performance_counter: u64,
 	sdl_performance_frequency: u64) -> f64 {
@@ -44,6 +59,142 @@ fn get_fps(sdl_timer: &sdl2::TimerSubsystem,
 	sdl_performance_frequency as f64 / delta_time as f64
 }
 
+// This only affects textures made after it's called
+fn set_texture_filtering(use_linear_filtering: bool) {
+	// TODO: why is the top-right texture not linearly filtered?
+	let using_texture_filtering_option =
+		sdl2::hint::set_with_priority(
+			"SDL_RENDER_SCALE_QUALITY",
+			if use_linear_filtering {"1"} else {"0"},
+			&sdl2::hint::Hint::Override
+		);
+
+	assert!(using_texture_filtering_option);
+}
+
+type TopLevelWindowCreator = fn(&mut texture::TexturePool, utility_types::update_rate::UpdateRateCreator, &dashboard_defs::dashboard::DashboardConfig)
+	-> utility_types::generic_result::GenericResult<(
+		window_tree::Window,
+		utility_types::dynamic_optional::DynamicOptional,
+		window_tree::PossibleSharedWindowStateUpdater
+	)>;
+
+// This draws one frame of the dashboard (without presenting it), and runs the shared window state updater
+fn render_dashboard_frame<T: sdl2::render::RenderTarget>(top_level_window: &mut window_tree::Window,
+	rendering_params: &mut window_tree::PerFrameConstantRenderingParams<T>, background_color: (u8, u8, u8)) {
+
+	rendering_params.curr_time = chrono::Local::now().fixed_offset();
+
+	rendering_params.sdl_canvas.set_draw_color(background_color);
+	rendering_params.sdl_canvas.clear(); // TODO: make this work on fullscreen too
+
+	if let Err(err) = top_level_window.render(rendering_params) {
+		log::error!("An error arose during rendering: '{err}'."); // TODO: put this error in the red dialog on the screen (pass into the renderer)
+	}
+
+	if let Some((shared_window_state_updater, shared_update_rate)) = rendering_params.shared_window_state_updater {
+		if shared_update_rate.is_time_to_update(rendering_params.frame_counter) {
+			if let Err(err) = shared_window_state_updater(&mut rendering_params.shared_window_state, &mut rendering_params.texture_pool) {
+				log::error!("An error arose from the shared window state updater: '{err}'."); // TODO: put this error in the red dialog on the screen
+			}
+		}
+	}
+}
+
+////////// Headless rendering
+
+fn write_snapshot<T: sdl2::render::RenderTarget>(sdl_canvas: &window_tree::CanvasSDL<T>, path: &str) -> utility_types::generic_result::MaybeError {
+	use sdl2::image::SaveSurface;
+	use crate::utility_types::generic_result::ToGenericError;
+
+	let pixel_format = sdl2::pixels::PixelFormatEnum::RGBA32;
+	let (width, height) = sdl_canvas.output_size().to_generic()?;
+	let mut pixels = sdl_canvas.read_pixels(None, pixel_format).to_generic()?;
+	let pitch = width * pixel_format.byte_size_per_pixel() as u32;
+
+	let surface = sdl2::surface::Surface::from_data(&mut pixels, width, height, pitch, pixel_format).to_generic()?;
+
+	// Writing to a temporary file first, so that nothing reading the snapshot ever sees a partially written one
+	let temporary_path = format!("{path}.tmp");
+	surface.save(&temporary_path).to_generic()?;
+	std::fs::rename(&temporary_path, path)?;
+
+	log::info!("Wrote a dashboard snapshot to '{path}'.");
+	Ok(())
+}
+
+fn run_headless(app_config: &AppConfig, top_level_window_creator: TopLevelWindowCreator,
+	(width, height, fps): (u32, u32, u32), snapshot_path: &str,
+	maybe_seconds_between_snapshots: Option<utility_types::update_rate::Seconds>) -> utility_types::generic_result::MaybeError {
+
+	use crate::utility_types::generic_result::ToGenericError;
+
+	/* This is long enough for the shared window state to be updated
+	once after startup (so that things like text messages show up). */
+	const WARMUP_SECONDS_FOR_SINGLE_SNAPSHOT: utility_types::update_rate::Seconds = 16.0;
+
+	// The software renderer reports no max texture size, so this is used instead
+	const MAX_TEXTURE_SIZE: (u32, u32) = (16384, 16384);
+
+	//////////
+
+	let sdl_ttf_context = sdl2::ttf::init()?;
+
+	let surface = sdl2::surface::Surface::new(width, height, sdl2::pixels::PixelFormatEnum::RGBA32).to_generic()?;
+	let sdl_canvas = surface.into_canvas().to_generic()?;
+	let texture_creator = sdl_canvas.texture_creator();
+
+	set_texture_filtering(app_config.use_linear_filtering);
+
+	let mut rendering_params =
+		window_tree::PerFrameConstantRenderingParams {
+			sdl_canvas,
+			texture_pool: texture::TexturePool::new(&texture_creator, &sdl_ttf_context, MAX_TEXTURE_SIZE),
+			frame_counter: utility_types::update_rate::FrameCounter::new(),
+			curr_time: chrono::Local::now().fixed_offset(),
+			shared_window_state: utility_types::dynamic_optional::DynamicOptional::NONE,
+			shared_window_state_updater: None
+		};
+
+	let update_rate_creator = utility_types::update_rate::UpdateRateCreator::new(fps);
+
+	let (mut top_level_window, shared_window_state, shared_window_state_updater) = (top_level_window_creator)(
+		&mut rendering_params.texture_pool, update_rate_creator, &app_config.dashboard_config
+	)?;
+
+	rendering_params.shared_window_state = shared_window_state;
+	rendering_params.shared_window_state_updater = shared_window_state_updater;
+
+	//////////
+
+	let snapshot_update_rate = update_rate_creator.new_instance(
+		maybe_seconds_between_snapshots.unwrap_or(WARMUP_SECONDS_FOR_SINGLE_SNAPSHOT)
+	);
+
+	let frame_duration = std::time::Duration::from_secs_f64(1.0 / fps as f64);
+
+	log::info!("Rendering headlessly at {width}x{height}, at {fps} FPS.");
+
+	loop {
+		let frame_start = std::time::Instant::now();
+
+		render_dashboard_frame(&mut top_level_window, &mut rendering_params, app_config.background_color);
+		rendering_params.sdl_canvas.present();
+		rendering_params.frame_counter.tick();
+
+		// This is checked after ticking, so that no snapshot is taken of the very first frame
+		if snapshot_update_rate.is_time_to_update(rendering_params.frame_counter) {
+			write_snapshot(&rendering_params.sdl_canvas, snapshot_path)?;
+			if maybe_seconds_between_snapshots.is_none() {return Ok(());}
+		}
+
+		// There is no vsync here, so the frame rate is limited manually (update rates depend on it)
+		if let Some(time_left_in_frame) = frame_duration.checked_sub(frame_start.elapsed()) {
+			std::thread::sleep(time_left_in_frame);
+		}
+	}
+}
+
 /*
 fn check_for_texture_pool_memory_leak(initial_num_textures_in_pool: &mut Option<usize>, texture_pool: &texture::TexturePool) {
 	let num_textures_in_pool = texture_pool.size();
@@ -67,12 +218,18 @@ fn main() -> utility_types::generic_result::MaybeError {
 
 	log::info!("App launched!");
 
-	let app_config: AppConfig = utility_types::json_utils::load_from_file("assets/app_config.json")?;
-	let top_level_window_creator = dashboard_defs::dashboard::make_dashboard;
+	let mut app_config: AppConfig = utility_types::json_utils::load_from_file(APP_CONFIG_PATH)?;
+	let top_level_window_creator: TopLevelWindowCreator = dashboard_defs::dashboard::make_dashboard;
+
+	// This is checked before initializing the SDL video subsystem, since there may be no display
+	if let ScreenOption::Headless(width, height, fps, snapshot_path, maybe_seconds_between_snapshots) = &app_config.screen_option {
+		return run_headless(&app_config, top_level_window_creator,
+			(*width, *height, *fps), snapshot_path, *maybe_seconds_between_snapshots);
+	}
 
 	//////////
 
-	use crate::utility_types::generic_result::ToGenericError;
+	use crate::utility_types::generic_result::{Context, ToGenericError, error_msg};
 
 	let sdl_context = sdl2::init().to_generic()?;
 	let sdl_video_subsystem = sdl_context.video().to_generic()?;
@@ -102,7 +259,9 @@ fn main() -> utility_types::generic_result::MaybeError {
 				mode.w as u32, mode.h as u32,
 				WindowBuilder::fullscreen
 			)
-		}
+		},
+
+		ScreenOption::Headless(..) => unreachable!("Headless rendering is handled before making a window")
 	}?;
 
 	////////// Setting the window opacity and icon
@@ -115,7 +274,7 @@ fn main() -> utility_types::generic_result::MaybeError {
 	}
 
 	use sdl2::image::LoadSurface;
-	sdl_window.set_icon(sdl2::surface::Surface::from_file(app_config.icon_path).to_generic()?);
+	sdl_window.set_icon(sdl2::surface::Surface::from_file(&app_config.icon_path).to_generic()?);
 
 	//////////
 
@@ -127,15 +286,7 @@ fn main() -> utility_types::generic_result::MaybeError {
 
 	//////////
 
-	// TODO: why is the top-right texture not linearly filtered?
-	let using_texture_filtering_option =
-		sdl2::hint::set_with_priority(
-			"SDL_RENDER_SCALE_QUALITY",
-			if app_config.use_linear_filtering {"1"} else {"0"},
-			&sdl2::hint::Hint::Override
-		);
-
-	assert!(using_texture_filtering_option);
+	set_texture_filtering(app_config.use_linear_filtering);
 
 	if app_config.hide_cursor {
 		sdl_context.mouse().show_cursor(false);
@@ -159,12 +310,15 @@ fn main() -> utility_types::generic_result::MaybeError {
 			sdl_canvas,
 			texture_pool: texture::TexturePool::new(&texture_creator, &sdl_ttf_context, max_texture_size),
 			frame_counter: utility_types::update_rate::FrameCounter::new(),
+			curr_time: chrono::Local::now().fixed_offset(),
 			shared_window_state: utility_types::dynamic_optional::DynamicOptional::NONE,
 			shared_window_state_updater: None
 		};
 
+	let update_rate_creator = utility_types::update_rate::UpdateRateCreator::new(fps);
+
 	let core_init_info = (top_level_window_creator)(
-		&mut rendering_params.texture_pool, utility_types::update_rate::UpdateRateCreator::new(fps)
+		&mut rendering_params.texture_pool, update_rate_creator, &app_config.dashboard_config
 	);
 
 	let (mut top_level_window, shared_window_state, shared_window_state_updater) =
@@ -176,20 +330,33 @@ fn main() -> utility_types::generic_result::MaybeError {
 	rendering_params.shared_window_state = shared_window_state;
 	rendering_params.shared_window_state_updater = shared_window_state_updater;
 
+	////////// Watching the config files, so that the dashboard can be rebuilt when they change
+
+	let config_watching_update_rate = update_rate_creator.new_instance(1.0);
+
+	let make_config_file_watcher = |app_config: &AppConfig|
+		utility_types::file_watcher::FileWatcher::new(&[APP_CONFIG_PATH, &app_config.dashboard_config.layout_path]);
+
+	let mut config_file_watcher = make_config_file_watcher(&app_config);
+
 	//////////
 
 	let mut pausing_window = false;
+	let mut keys_pressed_this_frame = Vec::new();
 	// let mut initial_num_textures_in_pool = None;
 
 	log::info!("Finished setting up window. Canvas size: {:?}. Renderer info: {:?}.",
 		rendering_params.sdl_canvas.output_size().to_generic()?, sdl_renderer_info);
 
 	'running: loop {
+		keys_pressed_this_frame.clear();
+
 		for sdl_event in sdl_event_pump.poll_iter() {
 			use sdl2::{event::{self, Event}, keyboard::Keycode};
 
 			match sdl_event {
 				Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => break 'running,
+				Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => keys_pressed_this_frame.push(keycode),
 
 				Event::Window {win_event, ..} => {
 					match win_event {
@@ -215,17 +382,67 @@ fn main() -> utility_types::generic_result::MaybeError {
 		// TODO: should I put this before event polling?
 		let sdl_performance_counter_before = sdl_timer.performance_counter();
 
-		rendering_params.sdl_canvas.set_draw_color(app_config.background_color);
-		rendering_params.sdl_canvas.clear(); // TODO: make this work on fullscreen too
+		dashboard_defs::dashboard::set_keys_pressed_this_frame(&mut rendering_params.shared_window_state, &keys_pressed_this_frame);
+		render_dashboard_frame(&mut top_level_window, &mut rendering_params, app_config.background_color);
 
-		if let Err(err) = top_level_window.render(&mut rendering_params) {
-			log::error!("An error arose during rendering: '{err}'."); // TODO: put this error in the red dialog on the screen (pass into the renderer)
-		}
+		////////// Possibly reloading the config, and rebuilding the dashboard from it
+
+		if config_watching_update_rate.is_time_to_update(rendering_params.frame_counter) && config_file_watcher.any_changed() {
+			log::info!("Reloading the app config and the dashboard layout.");
+
+			/* Note: the textures from the previous dashboard are not freed here (since the
+			texture pool does not support deletion yet), so each reload leaks a bit of memory. */
+			let reload_result = utility_types::json_utils::load_from_file::<AppConfig>(APP_CONFIG_PATH).and_then(|new_app_config| {
+				// The window title and icon are checked before anything is swapped, so that a bad one just keeps the previous config
+				if new_app_config.title.contains('\0') {
+					return error_msg!("The window title '{}' has a null character in it", new_app_config.title.escape_debug());
+				}
+
+				let new_icon = sdl2::surface::Surface::from_file(&new_app_config.icon_path).to_generic()
+					.context(format!("Could not load the window icon at '{}'", new_app_config.icon_path))?;
+
+				set_texture_filtering(new_app_config.use_linear_filtering);
+
+				// Some invalid values (like out-of-range update rates) cause panics while building, so those are caught here
+				let core_init_info = std::panic::catch_unwind(std::panic::AssertUnwindSafe(||
+					(top_level_window_creator)(
+						&mut rendering_params.texture_pool, update_rate_creator, &new_app_config.dashboard_config
+					)
+				)).unwrap_or_else(|_| error_msg!("Building the dashboard panicked (see the logs for why)"));
+
+				if core_init_info.is_err() {
+					set_texture_filtering(app_config.use_linear_filtering);
+				}
+
+				Ok((new_app_config, new_icon, core_init_info?))
+			});
+
+			match reload_result {
+				Ok((new_app_config, new_icon, (new_top_level_window, new_shared_window_state, new_shared_window_state_updater))) => {
+					top_level_window = new_top_level_window;
+					rendering_params.shared_window_state = new_shared_window_state;
+					rendering_params.shared_window_state_updater = new_shared_window_state_updater;
+
+					if new_app_config.screen_option != app_config.screen_option {
+						log::warn!("The screen option changed in the app config, but that will only take effect after a restart.");
+					}
+
+					let sdl_window = rendering_params.sdl_canvas.window_mut();
+					sdl_window.set_title(&new_app_config.title).expect("The window title was checked for null characters already");
+					sdl_window.set_icon(new_icon);
+					sdl_context.mouse().show_cursor(!new_app_config.hide_cursor);
+
+					if new_app_config.dashboard_config.layout_path != app_config.dashboard_config.layout_path {
+						config_file_watcher = make_config_file_watcher(&new_app_config);
+					}
+
+					app_config = new_app_config;
+					log::info!("Finished reloading the app config and the dashboard layout.");
+				},
 
-		if let Some((shared_window_state_updater, shared_update_rate)) = shared_window_state_updater {
-			if shared_update_rate.is_time_to_update(rendering_params.frame_counter) {
-				if let Err(err) = shared_window_state_updater(&mut rendering_params.shared_window_state, &mut rendering_params.texture_pool) {
-					log::error!("An error arose from the shared window state updater: '{err}'."); // TODO: put this error in the red dialog on the screen
+				Err(err) => {
+					log::error!("Could not reload the app config and the dashboard layout, so keeping the previous ones. Error: '{err:#}'.");
+					dashboard_defs::dashboard::report_config_reload_error(&mut rendering_params.shared_window_state, &err);
 				}
 			}
 		}
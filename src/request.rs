@@ -19,13 +19,10 @@ pub fn build_url(base_url: &str, path_params: &[Cow<str>],
 	url
 }
 
-/* TODO: in order to effectively do request stuff, maybe eliminate this wrapper
-code altogether? Or just keep this wrapper layer as request submitting code? */
-pub fn get_with_maybe_header(url: &str, maybe_header: Option<(&str, &str)>) -> GenericResult<minreq::Response> {
-	const EXPECTED_STATUS_CODE: i32 = 200;
-	const DEFAULT_TIMEOUT_SECONDS: u64 = 20;
+fn send_with_maybe_header(mut request: minreq::Request, url: &str,
+	maybe_header: Option<(&str, &str)>, expected_status_code: i32) -> GenericResult<minreq::Response> {
 
-	let mut request = minreq::get(url);
+	const DEFAULT_TIMEOUT_SECONDS: u64 = 20;
 
 	if let Some(header) = maybe_header {
 		request = request.with_header(header.0, header.1);
@@ -33,17 +30,52 @@ pub fn get_with_maybe_header(url: &str, maybe_header: Option<(&str, &str)>) -> G
 
 	let response = request.with_timeout(DEFAULT_TIMEOUT_SECONDS).send()?;
 
-	if response.status_code == EXPECTED_STATUS_CODE {
+	if response.status_code == expected_status_code {
 		Ok(response)
 	}
 	else {
 		error_msg!(
-			"Response status code for URL '{url}' was not '{EXPECTED_STATUS_CODE}', \
+			"Response status code for URL '{url}' was not '{expected_status_code}', \
 			but '{}', with this reason: '{}'", response.status_code, response.reason_phrase
 		)
 	}
 }
 
+/* TODO: in order to effectively do request stuff, maybe eliminate this wrapper
+code altogether? Or just keep this wrapper layer as request submitting code? */
+pub fn get_with_maybe_header(url: &str, maybe_header: Option<(&str, &str)>) -> GenericResult<minreq::Response> {
+	send_with_maybe_header(minreq::get(url), url, maybe_header, 200)
+}
+
+// A successful deletion has no content
+pub fn delete_with_maybe_header(url: &str, maybe_header: Option<(&str, &str)>) -> GenericResult<minreq::Response> {
+	send_with_maybe_header(minreq::delete(url), url, maybe_header, 204)
+}
+
+// A successful creation returns a 201 (the params are URL-encoded here)
+pub fn post_form_with_maybe_header(url: &str, maybe_header: Option<(&str, &str)>,
+	form_params: &[(&str, &str)]) -> GenericResult<minreq::Response> {
+
+	let body = form_params.iter()
+		.map(|(name, value)| format!("{}={}", url_encode(name), url_encode(value)))
+		.collect::<Vec<_>>()
+		.join("&");
+
+	let request = minreq::post(url)
+		.with_header("Content-Type", "application/x-www-form-urlencoded")
+		.with_body(body);
+
+	send_with_maybe_header(request, url, maybe_header, 201)
+}
+
+// This percent-encodes everything but the unreserved characters
+fn url_encode(text: &str) -> String {
+	text.bytes().map(|byte| match byte {
+		b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+		_ => format!("%{byte:02X}")
+	}).collect()
+}
+
 pub fn get(url: &str) -> GenericResult<minreq::Response> {
 	get_with_maybe_header(url, None)
 }
@@ -0,0 +1,144 @@
+use sdl2::{
+	ttf,
+	surface::Surface,
+	image::LoadSurface,
+	pixels::PixelFormatEnum
+};
+
+use crate::{
+	texture::TexturePool,
+
+	window_tree::{
+		Window,
+		FrameTime,
+		PerFrameConstantRenderingParams
+	},
+
+	utility_types::{
+		generic_result::*,
+		update_rate::FrameCounter,
+		dynamic_optional::DynamicOptional
+	}
+};
+
+/* This is a harness for golden-image snapshot tests. A window tree is rendered once through
+the software renderer (at a frozen time), and then its pixels are compared against a checked-in
+golden PNG in `tests/golden_images`.
+
+- If a golden image is missing, it is written, and the test fails (so that it can be looked over and checked in).
+- Setting `UPDATE_GOLDEN_IMAGES=1` rewrites all golden images (for when a rendering change is intended).
+- When a comparison fails, the actual output is written to `target/snapshot_failures`. */
+
+const GOLDEN_IMAGE_DIR: &str = "tests/golden_images";
+const FAILED_SNAPSHOT_DIR: &str = "target/snapshot_failures";
+
+/* Font rasterization and line drawing can differ a tiny bit between SDL/FreeType versions,
+so a channel can be off by this much, and this fraction of the pixels can be off by more. */
+const MAX_CHANNEL_DIFFERENCE: u8 = 8;
+const MAX_FRACTION_OF_DIFFERING_PIXELS: f64 = 0.005;
+
+const PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::RGBA32;
+const BACKGROUND_COLOR: (u8, u8, u8) = (0, 0, 0);
+
+//////////
+
+// This is 10:08:30.250 AM, which puts all of the clock hands at different angles
+pub fn frozen_time() -> FrameTime {
+	chrono::DateTime::parse_from_rfc3339("2024-03-15T10:08:30.250+00:00").unwrap()
+}
+
+/* The window maker gets the texture pool, and returns a window and the shared window state that its
+updaters expect. The window's updaters are run once (at frame 0), and then it is drawn. */
+pub fn assert_matches_golden_image(name: &str, canvas_size: (u32, u32),
+	window_maker: impl FnOnce(&mut TexturePool) -> GenericResult<(Window, DynamicOptional)>) {
+
+	if let Err(err) = render_and_compare(name, canvas_size, window_maker) {
+		panic!("Snapshot test '{name}' failed: {err:#}");
+	}
+}
+
+//////////
+
+fn render_and_compare(name: &str, (width, height): (u32, u32),
+	window_maker: impl FnOnce(&mut TexturePool) -> GenericResult<(Window, DynamicOptional)>) -> MaybeError {
+
+	// The software renderer reports no max texture size, so this is used instead
+	const MAX_TEXTURE_SIZE: (u32, u32) = (16384, 16384);
+
+	let ttf_context = ttf::init()?;
+
+	let surface = Surface::new(width, height, PIXEL_FORMAT).to_generic()?;
+	let sdl_canvas = surface.into_canvas().to_generic()?;
+	let texture_creator = sdl_canvas.texture_creator();
+
+	let mut rendering_params = PerFrameConstantRenderingParams {
+		sdl_canvas,
+		texture_pool: TexturePool::new(&texture_creator, &ttf_context, MAX_TEXTURE_SIZE),
+		frame_counter: FrameCounter::new(),
+		curr_time: frozen_time(),
+		shared_window_state: DynamicOptional::NONE,
+		shared_window_state_updater: None
+	};
+
+	let (mut window, shared_window_state) = window_maker(&mut rendering_params.texture_pool)?;
+	rendering_params.shared_window_state = shared_window_state;
+
+	rendering_params.sdl_canvas.set_draw_color(BACKGROUND_COLOR);
+	rendering_params.sdl_canvas.clear();
+	window.render(&mut rendering_params)?;
+	rendering_params.sdl_canvas.present();
+
+	//////////
+
+	let golden_image_path = format!("{GOLDEN_IMAGE_DIR}/{name}.png");
+	let updating_golden_images = std::env::var("UPDATE_GOLDEN_IMAGES").is_ok_and(|value| value == "1");
+	let golden_image_exists = std::path::Path::new(&golden_image_path).exists();
+
+	if updating_golden_images || !golden_image_exists {
+		std::fs::create_dir_all(GOLDEN_IMAGE_DIR)?;
+		crate::write_snapshot(&rendering_params.sdl_canvas, &golden_image_path)?;
+
+		if !golden_image_exists && !updating_golden_images {
+			return error_msg!("there was no golden image, so one was written to '{golden_image_path}'. Look it over, and then check it in");
+		}
+
+		return Ok(());
+	}
+
+	//////////
+
+	let actual_pixels = rendering_params.sdl_canvas.read_pixels(None, PIXEL_FORMAT).to_generic()?;
+	let golden_image = Surface::from_file(&golden_image_path).to_generic()?.convert_format(PIXEL_FORMAT).to_generic()?;
+
+	if golden_image.size() != (width, height) {
+		return error_msg!("the golden image has a size of {:?}, but the canvas has a size of {:?}", golden_image.size(), (width, height));
+	}
+
+	let num_differing_pixels = golden_image.with_lock(|golden_pixels| {
+		// The pitch may include padding at the end of each row
+		let golden_pitch = golden_image.pitch() as usize;
+		let row_length = width as usize * PIXEL_FORMAT.byte_size_per_pixel();
+
+		(0..height as usize).map(|y| {
+			let golden_row = &golden_pixels[y * golden_pitch..][..row_length];
+			let actual_row = &actual_pixels[y * row_length..][..row_length];
+
+			golden_row.chunks_exact(4).zip(actual_row.chunks_exact(4)).filter(|(golden, actual)|
+				golden.iter().zip(actual.iter()).any(|(g, a)| g.abs_diff(*a) > MAX_CHANNEL_DIFFERENCE)
+			).count()
+		}).sum::<usize>()
+	});
+
+	let fraction_of_differing_pixels = num_differing_pixels as f64 / (width * height) as f64;
+
+	if fraction_of_differing_pixels > MAX_FRACTION_OF_DIFFERING_PIXELS {
+		let failed_snapshot_path = format!("{FAILED_SNAPSHOT_DIR}/{name}.png");
+		std::fs::create_dir_all(FAILED_SNAPSHOT_DIR)?;
+		crate::write_snapshot(&rendering_params.sdl_canvas, &failed_snapshot_path)?;
+
+		return error_msg!("{num_differing_pixels} pixels ({:.2}%) differed from the golden image at '{golden_image_path}' \
+			(the actual output was written to '{failed_snapshot_path}')", fraction_of_differing_pixels * 100.0);
+	}
+
+	Ok(())
+}
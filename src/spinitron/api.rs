@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 
+use chrono::{DateTime, Utc};
+
 use crate::{
 	request,
 	utility_types::generic_result::*,
@@ -11,14 +13,22 @@ use crate::{
 };
 
 /* TODO:
-- Later on, maybe set up a mock API, for the sake of testing
 - Would it be possible to show the current PSA on the dashboard?
 - Fix the mysterious Serde-Spinitron-API error (that arose from a portion of the logs on the studio dashboard)
 */
 
+pub const DEFAULT_BASE_URL: &str = "https://spinitron.com/api";
+
+// The base URL is configurable so that a mock API (like the one in `mock_server.rs`) can be used instead
+#[derive(Clone)]
+pub struct ApiAccess {
+	pub base_url: String,
+	pub api_key: String
+}
+
 fn get_json_from_spinitron_request<T: SpinitronModelWithProps>(
-	api_key: &str, possible_model_id: MaybeSpinitronModelId,
-	possible_item_count: Option<u16>
+	api_access: &ApiAccess, possible_model_id: MaybeSpinitronModelId,
+	possible_item_count: Option<u16>, extra_query_params: &[(&str, Cow<str>)]
 ) -> GenericResult<serde_json::Value> {
 
 	////////// Getting the API endpoint
@@ -55,7 +65,7 @@ fn get_json_from_spinitron_request<T: SpinitronModelWithProps>(
 	let mut path_params: Vec<Cow<str>> = vec![Cow::Owned(api_endpoint)];
 
 	let mut query_params: Vec<(&str, Cow<str>)> = vec![
-		("access-token", Cow::Owned(api_key.to_string())),
+		("access-token", Cow::Borrowed(&api_access.api_key)),
 		("fields", Cow::Borrowed(&joined_fields))
 	];
 
@@ -67,11 +77,13 @@ fn get_json_from_spinitron_request<T: SpinitronModelWithProps>(
 		query_params.push(("count", Cow::Owned(item_count.to_string())));
 	}
 
+	query_params.extend_from_slice(extra_query_params);
+
 	////////// Building a URL, submitting the request, and getting the response JSON
 
 	/* TODO: later on, cache this URL for the specific request (otherwise, a lot of time is spent rebuilding it).
 	Actually, don't do that, build the URL, and then cache the request itself (it will then be resent other times). */
-	let url = request::build_url("https://spinitron.com/api", &path_params, &query_params);
+	let url = request::build_url(&api_access.base_url, &path_params, &query_params);
 
 	request::as_type(request::get(&url))
 }
@@ -82,8 +94,8 @@ fn get_vec_from_spinitron_json<T: SpinitronModelWithProps>(json: &serde_json::Va
 }
 
 // This is a singular request
-fn do_request<T: SpinitronModelWithProps>(api_key: &str, possible_model_id: MaybeSpinitronModelId) -> GenericResult<T> {
-	let response_json = get_json_from_spinitron_request::<T>(api_key, possible_model_id, Some(1))?;
+fn do_request<T: SpinitronModelWithProps>(api_access: &ApiAccess, possible_model_id: MaybeSpinitronModelId) -> GenericResult<T> {
+	let response_json = get_json_from_spinitron_request::<T>(api_access, possible_model_id, Some(1), &[])?;
 
 	if possible_model_id.is_some() {
 		// If requesting a via model id, just a raw item will be returned
@@ -98,16 +110,33 @@ fn do_request<T: SpinitronModelWithProps>(api_key: &str, possible_model_id: Mayb
 	}
 }
 
-/*
-fn do_plural_request<T: SpinitronModelWithProps>(api_key: &str, possible_item_count: Option<u16>) -> GenericResult<Vec<T>> {
-	let response_json = get_json_from_spinitron_request::<T>(api_key, None, possible_item_count)?;
+fn do_plural_request<T: SpinitronModelWithProps>(api_access: &ApiAccess,
+	possible_item_count: Option<u16>, extra_query_params: &[(&str, Cow<str>)]) -> GenericResult<Vec<T>> {
+
+	let response_json = get_json_from_spinitron_request::<T>(api_access, None, possible_item_count, extra_query_params)?;
 	get_vec_from_spinitron_json(&response_json)
 }
-*/
 
 //////////
 
 // TODO: can I make `id` non-optional?
-pub fn get_model_from_id<T: SpinitronModelWithProps>(api_key: &str, id: MaybeSpinitronModelId) -> GenericResult<T> {
-	do_request(api_key, id) // TODO: stop using this as a wrapper?
+pub fn get_model_from_id<T: SpinitronModelWithProps>(api_access: &ApiAccess, id: MaybeSpinitronModelId) -> GenericResult<T> {
+	do_request(api_access, id) // TODO: stop using this as a wrapper?
+}
+
+// The models are returned newest first (like Spinitron does)
+pub fn get_most_recent_models<T: SpinitronModelWithProps>(api_access: &ApiAccess, count: u16) -> GenericResult<Vec<T>> {
+	do_plural_request(api_access, Some(count), &[])
+}
+
+// Spinitron returns the models that overlap with the time range, in order of their start times
+pub fn get_models_in_time_range<T: SpinitronModelWithProps>(api_access: &ApiAccess,
+	(start, end): (DateTime<Utc>, DateTime<Utc>), count: u16) -> GenericResult<Vec<T>> {
+
+	const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+	do_plural_request(api_access, Some(count), &[
+		("start", Cow::Owned(start.format(TIME_FORMAT).to_string())),
+		("end", Cow::Owned(end.format(TIME_FORMAT).to_string()))
+	])
 }
@@ -0,0 +1,196 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{
+	utility_types::generic_result::*,
+	spinitron::model::{Spin, Playlist, Persona, Show, NUM_SPINITRON_MODEL_TYPES}
+};
+
+/* This keeps the last successfully synced Spinitron models (and their precached texture bytes)
+on disk, so that they can be shown right away after a restart, while fresh data is being fetched. */
+
+#[derive(Deserialize, Clone)]
+pub struct CacheConfig {
+	pub dir: String,
+	pub max_age_hours: f64,
+
+	// If this is false, cached data older than the max age is not shown at all
+	pub show_when_stale: bool
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedModelsFile {
+	saved_at_unix_secs: i64,
+	spin: Spin,
+	playlist: Playlist,
+	persona: Persona,
+	show: Show
+}
+
+pub struct CachedModels {
+	pub spin: Spin,
+	pub playlist: Playlist,
+	pub persona: Persona,
+	pub show: Show,
+	pub texture_bytes: [Vec<u8>; NUM_SPINITRON_MODEL_TYPES],
+	pub is_stale: bool
+}
+
+const MODELS_FILE_NAME: &str = "models.json";
+
+// These are in the same order as the model indices (spin, playlist, persona, and show)
+const TEXTURE_FILE_NAMES: [&str; NUM_SPINITRON_MODEL_TYPES] = [
+	"spin_texture.bin", "playlist_texture.bin", "persona_texture.bin", "show_texture.bin"
+];
+
+//////////
+
+// This writes to a temporary file first, so that a crash midway through never leaves a partially written file
+fn write_atomically(path: &str, contents: &[u8]) -> MaybeError {
+	let temporary_path = format!("{path}.tmp");
+	std::fs::write(&temporary_path, contents)?;
+	std::fs::rename(&temporary_path, path)?;
+	Ok(())
+}
+
+pub fn save(dir: &str, (spin, playlist, persona, show): (&Spin, &Playlist, &Persona, &Show),
+	texture_bytes: &[Vec<u8>; NUM_SPINITRON_MODEL_TYPES]) -> MaybeError {
+
+	std::fs::create_dir_all(dir).context(format!("Could not make the Spinitron cache directory at '{dir}'"))?;
+
+	// The textures are written first, so that the models file is never newer than them
+	for (file_name, bytes) in TEXTURE_FILE_NAMES.iter().zip(texture_bytes) {
+		write_atomically(&format!("{dir}/{file_name}"), bytes)?;
+	}
+
+	let models_file = CachedModelsFile {
+		saved_at_unix_secs: chrono::Utc::now().timestamp(),
+		spin: spin.clone(), playlist: playlist.clone(),
+		persona: persona.clone(), show: show.clone()
+	};
+
+	write_atomically(&format!("{dir}/{MODELS_FILE_NAME}"), &serde_json::to_vec(&models_file)?)
+}
+
+// This returns `None` if there's nothing cached yet, or if the cached data is stale and should not be shown
+pub fn load(config: &CacheConfig) -> GenericResult<Option<CachedModels>> {
+	let models_path = format!("{}/{MODELS_FILE_NAME}", config.dir);
+
+	let unparsed_models_file = match std::fs::read(&models_path) {
+		Ok(unparsed_models_file) => unparsed_models_file,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+		Err(err) => return Err(err).context(format!("Could not read the Spinitron cache at '{models_path}'"))
+	};
+
+	let models_file: CachedModelsFile = serde_json::from_slice(&unparsed_models_file)
+		.context(format!("Could not parse the Spinitron cache at '{models_path}'"))?;
+
+	//////////
+
+	let age_secs = chrono::Utc::now().timestamp() - models_file.saved_at_unix_secs;
+	let is_stale = age_secs as f64 > config.max_age_hours * 3600.0;
+
+	if is_stale && !config.show_when_stale {
+		log::info!("The cached Spinitron data is {age_secs} seconds old, which is too stale to show.");
+		return Ok(None);
+	}
+
+	let mut texture_bytes: [Vec<u8>; NUM_SPINITRON_MODEL_TYPES] = Default::default();
+
+	for (file_name, bytes) in TEXTURE_FILE_NAMES.iter().zip(&mut texture_bytes) {
+		let texture_path = format!("{}/{file_name}", config.dir);
+		*bytes = std::fs::read(&texture_path).context(format!("Could not read the cached texture at '{texture_path}'"))?;
+	}
+
+	log::info!("Loaded cached Spinitron data from {age_secs} seconds ago.");
+
+	Ok(Some(CachedModels {
+		spin: models_file.spin, playlist: models_file.playlist,
+		persona: models_file.persona, show: models_file.show,
+		texture_bytes, is_stale
+	}))
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::spinitron::model::SpinitronModel;
+
+	const TEXTURE_BYTES: [&[u8]; NUM_SPINITRON_MODEL_TYPES] = [b"spin", b"playlist", b"persona", b"show"];
+
+	fn make_config(name: &str, max_age_hours: f64, show_when_stale: bool) -> CacheConfig {
+		let dir = std::env::temp_dir().join(format!("spinitron_cache_test_{name}_{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		CacheConfig {dir: dir.to_str().unwrap().to_string(), max_age_hours, show_when_stale}
+	}
+
+	fn save_test_models(config: &CacheConfig) {
+		let spin: Spin = serde_json::from_value(serde_json::json!({
+			"id": 1001, "artist": "Talking Heads", "song": "This Must Be the Place (Naive Melody)", "release": "Speaking in Tongues",
+			"duration": 296, "start": "2024-03-15T10:00:00+0000", "end": "2024-03-15T10:04:56+0000"
+		})).unwrap();
+
+		save(&config.dir, (&spin, &Playlist::default(), &Persona::default(), &Show::default()),
+			&TEXTURE_BYTES.map(|bytes| bytes.to_vec())).unwrap();
+	}
+
+	// This makes the cache look like it was saved this many hours ago
+	fn age_cache(config: &CacheConfig, hours: i64) {
+		let models_path = format!("{}/{MODELS_FILE_NAME}", config.dir);
+		let mut models_file: serde_json::Value = serde_json::from_slice(&std::fs::read(&models_path).unwrap()).unwrap();
+		models_file["saved_at_unix_secs"] = (chrono::Utc::now() - chrono::Duration::hours(hours)).timestamp().into();
+		std::fs::write(&models_path, models_file.to_string()).unwrap();
+	}
+
+	#[test]
+	fn loads_what_was_saved() {
+		let config = make_config("round_trip", 12.0, false);
+		assert!(load(&config).unwrap().is_none());
+
+		save_test_models(&config);
+		let cached = load(&config).unwrap().unwrap();
+
+		assert_eq!(cached.spin.get_id(), 1001);
+		assert_eq!(cached.spin.get_song(), "This Must Be the Place (Naive Melody)");
+		assert_eq!(cached.texture_bytes, TEXTURE_BYTES.map(|bytes| bytes.to_vec()));
+		assert!(!cached.is_stale);
+
+		std::fs::remove_dir_all(&config.dir).unwrap();
+	}
+
+	#[test]
+	fn stale_cache_is_only_shown_if_configured() {
+		let hidden_config = make_config("stale_hidden", 12.0, false);
+		save_test_models(&hidden_config);
+		age_cache(&hidden_config, 13);
+		assert!(load(&hidden_config).unwrap().is_none());
+
+		let shown_config = make_config("stale_shown", 12.0, true);
+		save_test_models(&shown_config);
+		age_cache(&shown_config, 13);
+		assert!(load(&shown_config).unwrap().unwrap().is_stale);
+
+		// Just under the max age, it's still fresh
+		age_cache(&shown_config, 11);
+		assert!(!load(&shown_config).unwrap().unwrap().is_stale);
+
+		std::fs::remove_dir_all(&hidden_config.dir).unwrap();
+		std::fs::remove_dir_all(&shown_config.dir).unwrap();
+	}
+
+	#[test]
+	fn corrupt_or_incomplete_cache_fails_to_load() {
+		let config = make_config("corrupt", 12.0, true);
+
+		save_test_models(&config);
+		std::fs::remove_file(format!("{}/{}", config.dir, TEXTURE_FILE_NAMES[2])).unwrap();
+		assert!(load(&config).is_err());
+
+		std::fs::write(format!("{}/{MODELS_FILE_NAME}", config.dir), b"{\"saved_at_unix_secs\": 1, \"spin\": {").unwrap();
+		assert!(load(&config).is_err());
+
+		std::fs::remove_dir_all(&config.dir).unwrap();
+	}
+}
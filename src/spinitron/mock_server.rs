@@ -0,0 +1,161 @@
+use std::sync::Mutex;
+
+use regex::Regex;
+
+use crate::utility_types::{
+	generic_result::*,
+	http_server::{self, HttpRequest, HttpResponse}
+};
+
+/* This is a small local stand-in for the Spinitron API, which serves canned JSON from a fixture directory.
+It lets the Spinitron pipeline be developed without hitting the real service (or using up the API quota).
+
+- The fixture directory has a `spins.json`, `playlists.json`, `personas.json`, and `shows.json`,
+each in the same form as a Spinitron response (an object with an `items` array).
+- The fixture files are reread on every request, so they can be edited while the dashboard is running.
+- In the fixture files, `{now}` (or `{now+N}` / `{now-N}`, for N seconds away from now) is replaced with a Spinitron-style
+timestamp, and `{server}` is replaced with the server's URL. Images can be served via `{server}/images/<file in assets>`.
+Anything in between `images` and the file name is ignored, so spin image URLs can have a size in them, like the real ones.
+- When listing items, the `count` query param is respected, and so are `start` and `end` (which keep the items that overlap with them). */
+
+#[derive(serde::Deserialize, Clone)]
+pub struct MockServerConfig {
+	pub address: String,
+	pub fixture_dir: String
+}
+
+#[derive(Default, Clone)]
+struct MockServerState {
+	fixture_dir: String,
+	server_url: String
+}
+
+lazy_static::lazy_static!(
+	static ref TIME_PLACEHOLDER_REGEXP: Regex = Regex::new(r#"\{now(?:([+-])(\d+))?\}"#).unwrap();
+);
+
+const ENDPOINTS: [&str; 4] = ["spins", "playlists", "personas", "shows"];
+const DEFAULT_ITEM_COUNT: usize = 20; // This is what Spinitron uses too
+const SPINITRON_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z"; // Spinitron timestamps look like this: 2024-03-15T10:08:30+0000
+
+//////////
+
+impl MockServerConfig {
+	pub fn get_base_url(&self) -> String {
+		format!("{}/api", self.get_server_url())
+	}
+
+	fn get_server_url(&self) -> String {
+		format!("http://{}", self.address)
+	}
+}
+
+/* This starts a server at the configured address. If one is already running there
+(since the dashboard can be rebuilt while running), it just switches its fixture directory. */
+pub fn start_or_update(config: &MockServerConfig) -> MaybeError {
+	let state = http_server::start_or_reuse_server(&config.address, "mock Spinitron", handle_request)?;
+
+	*state.lock().unwrap() = MockServerState {
+		fixture_dir: config.fixture_dir.clone(),
+		server_url: config.get_server_url()
+	};
+
+	Ok(())
+}
+
+//////////
+
+fn handle_request(state: &Mutex<MockServerState>, request: &HttpRequest) -> HttpResponse {
+	let MockServerState {fixture_dir, server_url} = state.lock().unwrap().clone();
+
+	match respond(request, &fixture_dir, &server_url) {
+		Ok(Some((content_type, body))) => HttpResponse::new("200 OK", content_type, body),
+		Ok(None) => HttpResponse::new("404 Not Found", "text/plain", "Not found"),
+
+		Err(err) => {
+			log::warn!("The mock Spinitron server failed on request '{} {}': '{err}'.", request.method, request.target);
+			HttpResponse::new("500 Internal Server Error", "text/plain", err.to_string())
+		}
+	}
+}
+
+// This returns a content type and body, or `None` if nothing was found
+fn respond(request: &HttpRequest, fixture_dir: &str, server_url: &str) -> GenericResult<Option<(&'static str, Vec<u8>)>> {
+	let query = request.get_query();
+	let segments: Vec<&str> = request.get_path().split('/').filter(|segment| !segment.is_empty()).collect();
+
+	match segments.as_slice() {
+		["images", .., file_name] => {
+			if file_name.contains("..") {return Ok(None);}
+			Ok(std::fs::read(format!("assets/{file_name}")).ok().map(|bytes| ("application/octet-stream", bytes)))
+		},
+
+		["api", endpoint, rest @ ..] if ENDPOINTS.contains(endpoint) => {
+			let fixture_path = format!("{fixture_dir}/{endpoint}.json");
+
+			let fixture = std::fs::read_to_string(&fixture_path)
+				.context(format!("Could not read the fixture at '{fixture_path}'"))?;
+
+			let fixture: serde_json::Value = serde_json::from_str(&fill_in_placeholders(&fixture, server_url))
+				.context(format!("Could not parse the fixture at '{fixture_path}'"))?;
+
+			let items = fixture["items"].as_array()
+				.context(format!("Expected an `items` array in the fixture at '{fixture_path}'"))?;
+
+			let get_query_param = |name: &str| query.split('&').find_map(|param| param.strip_prefix(name)?.strip_prefix('='));
+
+			let response = match rest {
+				// A single item is returned as-is when requesting it by id
+				[id] => {
+					let Ok(id) = id.parse::<u64>() else {return Ok(None)};
+					let Some(item) = items.iter().find(|item| item["id"].as_u64() == Some(id)) else {return Ok(None)};
+					item.clone()
+				},
+
+				[] => {
+					let count = get_query_param("count").and_then(|count| count.parse().ok()).unwrap_or(DEFAULT_ITEM_COUNT);
+
+					// Like with Spinitron, a time range only keeps the items that overlap with it
+					let items_in_range: Vec<&serde_json::Value> = match (get_query_param("start"), get_query_param("end")) {
+						(Some(range_start), Some(range_end)) => {
+							let range_start = chrono::DateTime::parse_from_rfc3339(range_start)?;
+							let range_end = chrono::DateTime::parse_from_rfc3339(range_end)?;
+
+							items.iter().filter(|item| {
+								let parse_item_time = |name: &str| item[name].as_str()
+									.and_then(|time| chrono::DateTime::parse_from_str(time, SPINITRON_TIME_FORMAT).ok());
+
+								match (parse_item_time("start"), parse_item_time("end")) {
+									(Some(item_start), Some(item_end)) => item_start < range_end && item_end > range_start,
+									_ => true
+								}
+							}).collect()
+						},
+
+						_ => items.iter().collect()
+					};
+
+					serde_json::json!({"items": &items_in_range[..count.min(items_in_range.len())]})
+				},
+
+				_ => return Ok(None)
+			};
+
+			Ok(Some(("application/json", response.to_string().into_bytes())))
+		},
+
+		_ => Ok(None)
+	}
+}
+
+fn fill_in_placeholders(fixture: &str, server_url: &str) -> String {
+	let curr_time = chrono::Utc::now();
+
+	let with_times = TIME_PLACEHOLDER_REGEXP.replace_all(fixture, |captures: &regex::Captures| {
+		let offset_secs: i64 = captures.get(2).map_or(0, |secs| secs.as_str().parse().unwrap());
+		let sign = if captures.get(1).is_some_and(|sign| sign.as_str() == "-") {-1} else {1};
+		(curr_time + chrono::Duration::seconds(sign * offset_secs)).format(SPINITRON_TIME_FORMAT).to_string()
+	});
+
+	with_times.replace("{server}", server_url)
+}
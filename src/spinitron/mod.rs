@@ -1,5 +1,10 @@
-mod api;
-mod wrapper_types;
+pub mod wrapper_types;
 
+pub mod api;
+pub mod cache;
+pub mod spin_log;
+pub mod now_playing;
 pub mod model;
 pub mod state;
+pub mod poll_schedule;
+pub mod mock_server;
@@ -11,7 +11,7 @@ use crate::{
 
 	spinitron::{
 		wrapper_types::*,
-		api::get_model_from_id
+		api::{ApiAccess, get_model_from_id, get_most_recent_models, get_models_in_time_range}
 	}
 };
 
@@ -19,7 +19,7 @@ pub const NUM_SPINITRON_MODEL_TYPES: usize = 4;
 
 lazy_static::lazy_static!(
 	static ref SPIN_IMAGE_SIZE_REGEXP: Regex = Regex::new(r#"\d+x\d+bb"#).unwrap();
-	static ref SPIN_IMAGE_REGEXP: Regex = Regex::new(r#"^https:\/\/.+\d+x\d+bb.+$"#).unwrap();
+	static ref SPIN_IMAGE_REGEXP: Regex = Regex::new(r#"^https?:\/\/.+\d+x\d+bb.+$"#).unwrap(); // HTTP is allowed for the mock API
 	static ref DEFAULT_PERSONA_AND_SHOW_IMAGE_REGEXP: Regex = Regex::new(r#"^https:\/\/farm\d.staticflickr\.com\/\d+\/.+\..+$"#).unwrap();
 
 	static ref SHOW_CATEGORY_EMOJIS_MAPPING: HashMap<&'static str, &'static str> = HashMap::from([
@@ -44,8 +44,31 @@ lazy_static::lazy_static!(
 		("Rock", "🎸"),
 		("Talk", "🗣️")
 	]);
+
+	// These are the default badge texts (a badge window can override them)
+	static ref SPIN_BADGE_TEXT_MAPPING: HashMap<SpinBadge, &'static str> = HashMap::from([
+		(SpinBadge::Local, "LOCAL"),
+		(SpinBadge::New, "NEW"),
+		(SpinBadge::Request, "REQUEST"),
+		(SpinBadge::VariousArtists, "V/A")
+	]);
 );
 
+// These are the attributes that a DJ can mark a spin with
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpinBadge {
+	Local, New, Request, VariousArtists
+}
+
+impl SpinBadge {
+	pub const ALL: [Self; 4] = [Self::Local, Self::New, Self::Request, Self::VariousArtists];
+
+	pub fn get_default_text(self) -> &'static str {
+		SPIN_BADGE_TEXT_MAPPING[&self]
+	}
+}
+
 ////////// This is a set of model-related traits
 
 pub type MaybeTextureCreationInfo<'a> = Option<TextureCreationInfo<'a>>;
@@ -178,14 +201,12 @@ impl SpinitronModel for Show {
 	fn to_string(&self) -> String {
 		let (mut show_emojis, mut spacing) = ("", "");
 
-		if let Some(category) = &self.category {
-			if let Some(emojis) = SHOW_CATEGORY_EMOJIS_MAPPING.get(category.as_str()) {
-				show_emojis = emojis;
-				spacing = " ";
-			}
-			else {
-				log::warn!("Unrecognized genre '{category}' for show with name '{}'", self.title);
-			}
+		if let Some(emojis) = self.get_category_emojis() {
+			show_emojis = emojis;
+			spacing = " ";
+		}
+		else if let Some(category) = &self.category {
+			log::warn!("Unrecognized genre '{category}' for show with name '{}'", self.title);
 		}
 		else {
 			log::warn!("No genre for show with name '{}'", self.title);
@@ -199,37 +220,137 @@ impl SpinitronModel for Show {
 	}
 }
 
+// Spinitron times look like `2024-03-15T10:08:30+0000`, which is almost RFC 3339 (the offset just needs a colon)
+fn parse_spinitron_time(time: &str) -> GenericResult<chrono::DateTime<chrono::Utc>> {
+	if time.len() < 2 {
+		return error_msg!("The Spinitron time '{time}' is too short to be valid");
+	}
+
+	let mut amended_time = time.to_string();
+	amended_time.insert(amended_time.len() - 2, ':');
+	Ok(chrono::DateTime::parse_from_rfc3339(&amended_time)?.into())
+}
+
 impl Spin {
 	// TODO: can I reduce the repetition on the `get`s?
-	pub fn get(api_key: &str) -> GenericResult<Self> {get_model_from_id(api_key, None)}
+	pub fn get(api_access: &ApiAccess) -> GenericResult<Self> {get_model_from_id(api_access, None)}
+
+	pub fn get_artist(&self) -> &str {&self.artist}
+	pub fn get_song(&self) -> &str {&self.song}
+	pub fn get_release(&self) -> &str {&self.release}
+	pub const fn get_duration_secs(&self) -> Uint {self.duration}
+
+	// These flags are `None` when the DJ didn't fill them in
+	pub const fn is_local(&self) -> MaybeBool {self.local}
+	pub const fn is_new(&self) -> MaybeBool {self.new}
+	pub const fn is_request(&self) -> MaybeBool {self.request}
+	pub const fn is_various_artists(&self) -> MaybeBool {self.va}
+
+	// This returns the badges that the spin was marked with (in the order of `SpinBadge::ALL`)
+	pub fn get_badges(&self) -> Vec<SpinBadge> {
+		SpinBadge::ALL.into_iter().filter(|badge| {
+			let maybe_flag = match badge {
+				SpinBadge::Local => self.local,
+				SpinBadge::New => self.new,
+				SpinBadge::Request => self.request,
+				SpinBadge::VariousArtists => self.va
+			};
+
+			maybe_flag == Some(true)
+		}).collect()
+	}
+
+	// This includes the current spin
+	pub fn get_most_recent(api_access: &ApiAccess, count: u16) -> GenericResult<Vec<Self>> {
+		get_most_recent_models(api_access, count)
+	}
+
+	// These are the spins that were logged ahead of time (starting within the next hour), in order of their start times
+	pub fn get_queued(api_access: &ApiAccess, count: u16) -> GenericResult<Vec<Self>> {
+		let curr_time = chrono::Utc::now();
+
+		// One more spin is requested, since the current spin overlaps with the time range too
+		let spins: Vec<Self> = get_models_in_time_range(api_access,
+			(curr_time, curr_time + chrono::Duration::hours(1)), count.saturating_add(1))?;
+
+		Ok(spins.into_iter()
+			.filter(|spin| spin.get_start_time().is_ok_and(|start_time| start_time > curr_time))
+			.take(count.into())
+			.collect())
+	}
+
+	pub fn get_start_time(&self) -> GenericResult<chrono::DateTime<chrono::Utc>> {
+		parse_spinitron_time(&self.start)
+	}
 
 	pub fn get_end_time(&self) -> GenericResult<chrono::DateTime<chrono::Utc>> {
-		let mut amended_end = self.end.to_string();
-		amended_end.insert(amended_end.len() - 2, ':');
-		Ok(chrono::DateTime::parse_from_rfc3339(&amended_end)?.into())
+		parse_spinitron_time(&self.end)
+	}
+
+	/* This returns how far into the spin the current time is, and how long the spin is.
+	The elapsed time is clamped to the spin duration (since DJs often log the next spin late). */
+	pub fn get_progress(&self, curr_time: chrono::DateTime<chrono::Utc>) -> GenericResult<(chrono::Duration, chrono::Duration)> {
+		let duration = chrono::Duration::seconds(self.duration.into());
+		let elapsed = curr_time.signed_duration_since(self.get_start_time()?);
+		Ok((elapsed.clamp(chrono::Duration::zero(), duration), duration))
 	}
 
 	pub const fn to_string_when_spin_is_expired() -> &'static str {
 		"No 😰 recent 😬 spins 😟❗"
 	}
 
+	// This is shown for all model text before Spinitron has been reached
+	pub const fn to_string_when_connecting() -> &'static str {
+		"Connecting to Spinitron…"
+	}
+
 	pub const fn get_texture_creation_info_when_spin_is_expired() -> TextureCreationInfo<'static> {
 		TextureCreationInfo::Path(Cow::Borrowed("assets/polar_headphones_logo.png"))
 	}
 }
 
 impl Playlist {
-	pub fn get(api_key: &str) -> GenericResult<Self> {get_model_from_id(api_key, None)}
+	pub fn get(api_access: &ApiAccess) -> GenericResult<Self> {get_model_from_id(api_access, None)}
+
+	pub fn get_end_time(&self) -> GenericResult<chrono::DateTime<chrono::Utc>> {
+		parse_spinitron_time(&self.end)
+	}
 }
 
 impl Persona {
-	pub fn get(api_key: &str, playlist: &Playlist) -> GenericResult<Self> {
-		get_model_from_id(api_key, Some(playlist.persona_id))
+	pub fn get(api_access: &ApiAccess, playlist: &Playlist) -> GenericResult<Self> {
+		get_model_from_id(api_access, Some(playlist.persona_id))
+	}
+
+	pub fn get_name(&self) -> &str {
+		&self.name
 	}
 }
 
 impl Show {
-	pub fn get(api_key: &str) -> GenericResult<Self> {get_model_from_id(api_key, None)}
+	pub fn get(api_access: &ApiAccess) -> GenericResult<Self> {get_model_from_id(api_access, None)}
+
+	// This includes the current show (if there is one)
+	pub fn get_upcoming(api_access: &ApiAccess, until: chrono::DateTime<chrono::Utc>, count: u16) -> GenericResult<Vec<Self>> {
+		get_models_in_time_range(api_access, (chrono::Utc::now(), until), count)
+	}
+
+	pub fn get_start_time(&self) -> GenericResult<chrono::DateTime<chrono::Utc>> {
+		parse_spinitron_time(&self.start)
+	}
+
+	pub fn get_end_time(&self) -> GenericResult<chrono::DateTime<chrono::Utc>> {
+		parse_spinitron_time(&self.end)
+	}
+
+	pub fn get_title(&self) -> &str {
+		&self.title
+	}
+
+	// This returns `None` if the show has no category, or if its category has no emojis
+	pub fn get_category_emojis(&self) -> Option<&'static str> {
+		self.category.as_ref().and_then(|category| SHOW_CATEGORY_EMOJIS_MAPPING.get(category.as_str()).copied())
+	}
 }
 
 impl SpinitronModelWithProps for Spin {}
@@ -239,7 +360,7 @@ impl SpinitronModelWithProps for Show {}
 
 ////////// These are the model definitions
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Deserialize)]
 pub enum SpinitronModelName {
 	Spin, Playlist, Persona, Show
 }
@@ -260,6 +381,7 @@ pub struct Spin {
 	// TODO: why is `time` not there?
 
 	duration: Uint,
+	start: String,
 	end: String,
 
 	request: MaybeBool,
@@ -277,7 +399,7 @@ pub struct Spin {
 	/*
 	- Ignoring "_links" for now.
 	- Also not  keeping the playlist ID here, since if someone doesn't come to their show, then the playlist ID will be invalid.
-	- TODO: add start, end, and label later (given the start, can I figure out where I am in the song?)
+	- TODO: add the label later
 	*/
 
 	id: SpinitronModelId,
@@ -329,6 +451,7 @@ derive_spinitron_model_props!(
 pub struct Show {
 	id: SpinitronModelId,
 
+	start: String,
 	end: String,
 	duration: Uint,
 	timezone: String,
@@ -0,0 +1,306 @@
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+	utility_types::{
+		generic_result::*,
+		http_server::{self, HttpRequest, HttpResponse}
+	},
+
+	spinitron::model::{Spin, Persona, Show}
+};
+
+/* This exports the now-playing data to other station systems (like OBS, the website's stream page, and RDS encoders).
+Whenever a model changes (or the current spin expires), the data is written to a JSON file, and to any number of text files
+(each with its own template), and the same JSON is served over HTTP on a local address.
+
+- Templates can have these placeholders: `{artist}`, `{song}`, `{release}`, `{show}`, and `{dj}`.
+For example, `{artist} - {song}` works for most RDS and Icecast metadata tools.
+- When the current spin is expired, each text file uses its `template_when_spin_is_expired` instead
+(which should only have the show and DJ placeholders), and the JSON has a null `spin`.
+- Files are written atomically (via a temporary file that is then renamed), so readers never see a partial file. */
+
+#[derive(Deserialize, Clone)]
+pub struct NowPlayingTextFile {
+	pub path: String,
+	pub template: String,
+	pub template_when_spin_is_expired: String
+}
+
+#[derive(Deserialize, Clone)]
+pub struct NowPlayingExportConfig {
+	pub text_files: Vec<NowPlayingTextFile>,
+	pub maybe_json_path: Option<String>,
+	pub maybe_http_address: Option<String> // This should be a local one, like `127.0.0.1:8136`
+}
+
+type SharedJson = Arc<Mutex<Option<String>>>;
+
+#[derive(Clone)]
+pub struct NowPlayingExporter {
+	config: NowPlayingExportConfig,
+	maybe_served_json: Option<SharedJson>
+}
+
+lazy_static::lazy_static!(
+	static ref TEMPLATE_PLACEHOLDER_REGEXP: Regex = Regex::new(r#"\{(\w+)\}"#).unwrap();
+);
+
+const TEMPLATE_PLACEHOLDERS: [&str; 5] = ["artist", "song", "release", "show", "dj"];
+
+//////////
+
+fn fill_in_template(template: &str, maybe_spin: Option<&Spin>, show: &Show, persona: &Persona) -> String {
+	TEMPLATE_PLACEHOLDER_REGEXP.replace_all(template, |captures: &regex::Captures| {
+		match (&captures[1], maybe_spin) {
+			("artist", Some(spin)) => spin.get_artist(),
+			("song", Some(spin)) => spin.get_song(),
+			("release", Some(spin)) => spin.get_release(),
+			("show", _) => show.get_title(),
+			("dj", _) => persona.get_name(),
+			_ => ""
+		}.to_string()
+	}).into_owned()
+}
+
+fn make_json(maybe_spin: Option<&Spin>, show: &Show, persona: &Persona) -> serde_json::Value {
+	let format_time = |maybe_time: GenericResult<chrono::DateTime<chrono::Utc>>|
+		maybe_time.ok().map(|time| time.to_rfc3339());
+
+	let spin_json = maybe_spin.map(|spin| serde_json::json!({
+		"artist": spin.get_artist(),
+		"song": spin.get_song(),
+		"release": spin.get_release(),
+		"duration_secs": spin.get_duration_secs(),
+		"start": format_time(spin.get_start_time()),
+		"end": format_time(spin.get_end_time()),
+		"local": spin.is_local(),
+		"new": spin.is_new(),
+		"request": spin.is_request(),
+		"various_artists": spin.is_various_artists()
+	}));
+
+	serde_json::json!({
+		"updated_at": chrono::Utc::now().to_rfc3339(),
+		"spin": spin_json,
+
+		"show": {
+			"title": show.get_title(),
+			"start": format_time(show.get_start_time()),
+			"end": format_time(show.get_end_time())
+		},
+
+		"dj": persona.get_name()
+	})
+}
+
+// The contents are written to a temporary file first, and then renamed over the old file
+fn write_atomically(path: &str, contents: &str) -> MaybeError {
+	if let Some(parent_dir) = std::path::Path::new(path).parent() {
+		std::fs::create_dir_all(parent_dir)?;
+	}
+
+	let temp_path = format!("{path}.tmp");
+	std::fs::write(&temp_path, contents)?;
+	std::fs::rename(&temp_path, path).context(format!("Could not move the now-playing file into place at '{path}'"))
+}
+
+//////////
+
+fn handle_request(served_json: &Mutex<Option<String>>, request: &HttpRequest) -> HttpResponse {
+	let response = match (request.method.as_str(), request.get_path()) {
+		("GET", "/" | "/now_playing.json") => match served_json.lock().unwrap().as_ref() {
+			Some(json) => HttpResponse::new("200 OK", "application/json", json.clone()),
+			None => HttpResponse::new("503 Service Unavailable", "text/plain", "Nothing is playing yet")
+		},
+
+		("GET", _) => HttpResponse::new("404 Not Found", "text/plain", "Not found"),
+		_ => HttpResponse::new("405 Method Not Allowed", "text/plain", "Only GET is supported")
+	};
+
+	// The CORS header lets the website's stream page fetch this directly
+	response.with_header("Access-Control-Allow-Origin", "*").with_header("Cache-Control", "no-store")
+}
+
+//////////
+
+impl NowPlayingExporter {
+	pub fn new(config: &NowPlayingExportConfig) -> GenericResult<Self> {
+		let all_templates = config.text_files.iter()
+			.flat_map(|text_file| [&text_file.template, &text_file.template_when_spin_is_expired]);
+
+		for template in all_templates {
+			for captures in TEMPLATE_PLACEHOLDER_REGEXP.captures_iter(template) {
+				let placeholder = &captures[1];
+
+				if !TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+					return error_msg!(
+						"Unknown placeholder '{{{placeholder}}}' in the now-playing template '{template}'. \
+						The known ones are: {TEMPLATE_PLACEHOLDERS:?}"
+					);
+				}
+			}
+		}
+
+		let maybe_served_json = config.maybe_http_address.as_deref()
+			.map(|address| http_server::start_or_reuse_server(address, "now-playing", handle_request))
+			.transpose()?;
+
+		Ok(Self {config: config.clone(), maybe_served_json})
+	}
+
+	// The spin should be `None` if it's expired
+	pub fn export(&self, maybe_spin: Option<&Spin>, show: &Show, persona: &Persona) -> MaybeError {
+		for text_file in &self.config.text_files {
+			let template = if maybe_spin.is_some() {&text_file.template} else {&text_file.template_when_spin_is_expired};
+			write_atomically(&text_file.path, &fill_in_template(template, maybe_spin, show, persona))?;
+		}
+
+		let json = make_json(maybe_spin, show, persona).to_string();
+
+		if let Some(json_path) = &self.config.maybe_json_path {
+			write_atomically(json_path, &json)?;
+		}
+
+		if let Some(served_json) = &self.maybe_served_json {
+			*served_json.lock().unwrap() = Some(json);
+		}
+
+		Ok(())
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		net::{TcpListener, TcpStream},
+		io::{Read, Write}
+	};
+
+	use super::*;
+	use crate::spinitron::model::SpinitronModelWithProps;
+
+	// This makes a default model with some of its fields changed
+	fn make_model<T: SpinitronModelWithProps>(fields: serde_json::Value) -> T {
+		let mut model = serde_json::to_value(T::default()).unwrap();
+
+		for (name, value) in fields.as_object().unwrap() {
+			model[name] = value.clone();
+		}
+
+		serde_json::from_value(model).unwrap()
+	}
+
+	fn make_models() -> (Spin, Show, Persona) {
+		(
+			make_model(serde_json::json!({"artist": "Stereolab", "song": "French Disko", "release": "Refried Ectoplasm"})),
+			make_model(serde_json::json!({"title": "Morning Drift"})),
+			make_model(serde_json::json!({"name": "DJ Tide"}))
+		)
+	}
+
+	fn make_text_file(path: &str, template: &str, template_when_spin_is_expired: &str) -> NowPlayingTextFile {
+		NowPlayingTextFile {
+			path: path.to_string(),
+			template: template.to_string(),
+			template_when_spin_is_expired: template_when_spin_is_expired.to_string()
+		}
+	}
+
+	// This returns the status line and body
+	fn send_request(address: &str, method: &str, path: &str) -> (String, String) {
+		let mut stream = TcpStream::connect(address).unwrap();
+		write!(stream, "{method} {path} HTTP/1.1\r\nHost: {address}\r\n\r\n").unwrap();
+
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+
+		let (head, body) = response.split_once("\r\n\r\n").unwrap();
+		(head.lines().next().unwrap().to_string(), body.to_string())
+	}
+
+	#[test]
+	fn fills_in_templates() {
+		let (spin, show, persona) = make_models();
+
+		assert_eq!(
+			fill_in_template("{artist} - {song} ({release}), on {show} with {dj}", Some(&spin), &show, &persona),
+			"Stereolab - French Disko (Refried Ectoplasm), on Morning Drift with DJ Tide"
+		);
+
+		// Once the spin is expired, its placeholders are left empty
+		assert_eq!(fill_in_template("{show} with {dj}", None, &show, &persona), "Morning Drift with DJ Tide");
+		assert_eq!(fill_in_template("[{artist}{song}{release}] {show}", None, &show, &persona), "[] Morning Drift");
+	}
+
+	#[test]
+	fn rejects_unknown_placeholders() {
+		let make_config = |template: &str| NowPlayingExportConfig {
+			text_files: vec![make_text_file("unused.txt", "{artist} - {song}", template)],
+			maybe_json_path: None,
+			maybe_http_address: None
+		};
+
+		assert!(NowPlayingExporter::new(&make_config("{show} with {dj}")).is_ok());
+
+		let Err(err) = NowPlayingExporter::new(&make_config("{show} with {host}")) else {
+			panic!("Expected an unknown placeholder to be rejected");
+		};
+
+		assert!(err.to_string().contains("'{host}'"));
+	}
+
+	#[test]
+	fn exports_to_text_files_and_serves_json() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap().to_string();
+
+		let served_json: SharedJson = Arc::new(Mutex::new(None));
+		http_server::serve(listener, "test now-playing", served_json.clone(), handle_request);
+
+		let text_path = std::env::temp_dir().join(format!("now_playing_test_{}.txt", std::process::id()));
+		let text_path = text_path.to_str().unwrap();
+
+		let exporter = NowPlayingExporter {
+			config: NowPlayingExportConfig {
+				text_files: vec![make_text_file(text_path, "{artist} - {song}", "{show}")],
+				maybe_json_path: None,
+				maybe_http_address: None
+			},
+
+			maybe_served_json: Some(served_json)
+		};
+
+		// Nothing is served until the first export
+		let (status, _) = send_request(&address, "GET", "/now_playing.json");
+		assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+
+		let (spin, show, persona) = make_models();
+		exporter.export(Some(&spin), &show, &persona).unwrap();
+		assert_eq!(std::fs::read_to_string(text_path).unwrap(), "Stereolab - French Disko");
+
+		for path in ["/", "/now_playing.json?cache_bust=1"] {
+			let (status, body) = send_request(&address, "GET", path);
+			let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+			assert_eq!(status, "HTTP/1.1 200 OK");
+			assert_eq!(json["spin"]["song"], "French Disko");
+			assert_eq!(json["dj"], "DJ Tide");
+		}
+
+		exporter.export(None, &show, &persona).unwrap();
+		assert_eq!(std::fs::read_to_string(text_path).unwrap(), "Morning Drift");
+
+		let (_, body) = send_request(&address, "GET", "/");
+		assert!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["spin"].is_null());
+
+		assert_eq!(send_request(&address, "GET", "/other.json").0, "HTTP/1.1 404 Not Found");
+		assert_eq!(send_request(&address, "POST", "/").0, "HTTP/1.1 405 Method Not Allowed");
+
+		std::fs::remove_file(text_path).unwrap();
+	}
+}
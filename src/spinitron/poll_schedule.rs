@@ -0,0 +1,127 @@
+use chrono::{DateTime, Duration, Utc};
+
+/* This decides when each Spinitron endpoint should be polled next, based on when its current model is expected to end.
+Near an expected transition, an endpoint is polled on every update; otherwise, it's only polled now and then.
+Since the checks compare against the current time (rather than looking for an exact minute), a transition that falls
+in between two updates is still caught on the next one. */
+
+type Timestamp = DateTime<Utc>;
+
+#[derive(Copy, Clone)]
+pub struct PollTiming {
+	// This is how long before an expected end to start polling on every update
+	lead_time: Duration,
+
+	// This is how long after an expected end to keep polling on every update (if no new model came up yet)
+	eager_duration_after_end: Duration,
+
+	// This is how often to poll when no transition is expected soon
+	relaxed_interval: Duration
+}
+
+impl PollTiming {
+	pub const fn new(lead_time: Duration, eager_duration_after_end: Duration, relaxed_interval: Duration) -> Self {
+		Self {lead_time, eager_duration_after_end, relaxed_interval}
+	}
+}
+
+#[derive(Clone)]
+pub struct PollSchedule {
+	timing: PollTiming,
+	next_poll_time: Timestamp
+}
+
+impl PollSchedule {
+	// The first poll happens right away
+	pub const fn new(timing: PollTiming) -> Self {
+		Self {timing, next_poll_time: DateTime::<Utc>::MIN_UTC}
+	}
+
+	pub fn is_time_to_poll(&self, curr_time: Timestamp) -> bool {
+		curr_time >= self.next_poll_time
+	}
+
+	/* This is called after polling. If the model has no valid end time (like a placeholder, or one so far out
+	that the eager window around it can't be represented), the next poll happens after the relaxed interval. */
+	pub fn schedule_next_poll(&mut self, curr_time: Timestamp, maybe_expected_end: Option<Timestamp>) {
+		let timing = &self.timing;
+		let relaxed_poll_time = curr_time + timing.relaxed_interval;
+
+		let maybe_eager_window = maybe_expected_end.and_then(|end| Some((
+			end.checked_sub_signed(timing.lead_time)?,
+			end.checked_add_signed(timing.eager_duration_after_end)?
+		)));
+
+		self.next_poll_time = match maybe_eager_window {
+			Some((eager_start, _)) if curr_time < eager_start => relaxed_poll_time.min(eager_start),
+			Some((_, eager_end)) if curr_time < eager_end => curr_time, // This means the next update
+			_ => relaxed_poll_time
+		};
+	}
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::spinitron::model::Spin;
+
+	const TIMING: PollTiming = PollTiming::new(Duration::seconds(10), Duration::minutes(5), Duration::minutes(2));
+
+	fn at(time: &str) -> Timestamp {
+		DateTime::parse_from_rfc3339(time).unwrap().into()
+	}
+
+	fn get_next_poll_time(curr_time: Timestamp, maybe_expected_end: Option<Timestamp>) -> Timestamp {
+		let mut schedule = PollSchedule::new(TIMING);
+		assert!(schedule.is_time_to_poll(curr_time));
+
+		schedule.schedule_next_poll(curr_time, maybe_expected_end);
+		schedule.next_poll_time
+	}
+
+	#[test]
+	fn polls_relaxed_until_the_lead_time_before_the_end() {
+		let end = at("2024-03-15T10:03:00Z");
+
+		// The end is far off, so the relaxed interval is used
+		assert_eq!(get_next_poll_time(at("2024-03-15T09:50:00Z"), Some(end)), at("2024-03-15T09:52:00Z"));
+
+		// The relaxed interval would overshoot the lead time, so the next poll is right at it
+		assert_eq!(get_next_poll_time(at("2024-03-15T10:02:00Z"), Some(end)), at("2024-03-15T10:02:50Z"));
+	}
+
+	#[test]
+	fn polls_on_every_update_in_the_eager_window() {
+		let end = at("2024-03-15T10:03:00Z");
+
+		for curr_time in ["2024-03-15T10:02:50Z", "2024-03-15T10:03:00Z", "2024-03-15T10:07:59Z"] {
+			let curr_time = at(curr_time);
+			assert_eq!(get_next_poll_time(curr_time, Some(end)), curr_time);
+		}
+	}
+
+	#[test]
+	fn polls_relaxed_after_the_eager_window() {
+		let end = at("2024-03-15T10:03:00Z");
+		assert_eq!(get_next_poll_time(at("2024-03-15T10:08:00Z"), Some(end)), at("2024-03-15T10:10:00Z"));
+		assert_eq!(get_next_poll_time(at("2024-03-15T12:00:00Z"), Some(end)), at("2024-03-15T12:02:00Z"));
+	}
+
+	#[test]
+	fn polls_relaxed_without_a_valid_end_time() {
+		let spin: Spin = serde_json::from_value(serde_json::json!({
+			"id": 1, "artist": "Someone", "song": "Some Song", "release": "Some Release",
+			"duration": 180, "start": "2024-03-15T10:00:00+0000", "end": "not a time"
+		})).unwrap();
+
+		let curr_time = at("2024-03-15T10:01:00Z");
+		assert_eq!(get_next_poll_time(curr_time, spin.get_end_time().ok()), at("2024-03-15T10:03:00Z"));
+		assert_eq!(get_next_poll_time(curr_time, None), at("2024-03-15T10:03:00Z"));
+
+		// The eager window can't be worked out for the earliest possible end time
+		assert_eq!(get_next_poll_time(curr_time, Some(DateTime::<Utc>::MIN_UTC)), at("2024-03-15T10:03:00Z"));
+	}
+}
@@ -0,0 +1,263 @@
+use std::{
+	io::Write,
+	fs::OpenOptions
+};
+
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+	utility_types::generic_result::*,
+
+	spinitron::{
+		wrapper_types::{MaybeBool, SpinitronModelId, Uint},
+		model::{Spin, Persona, Show, SpinitronModel}
+	}
+};
+
+/* This keeps an offline record of every spin (independent of Spinitron), by appending each new spin
+to a log file for the current local day. The records are written as JSON lines, and optionally as CSV
+too (with a header at the top of each file). Log files older than the retention period are deleted.
+
+A spin that's the same as the last one in the day's log is skipped (which happens on the first
+sync after a restart or a config reload, since the previous spin is then a placeholder). */
+
+#[derive(Deserialize, Clone)]
+pub struct SpinLogConfig {
+	pub dir: String,
+	pub write_csv: bool,
+	pub retention_days: u32
+}
+
+#[derive(Serialize)]
+struct SpinLogRecord<'a> {
+	logged_at: String,
+	spin_id: SpinitronModelId,
+
+	artist: &'a str,
+	song: &'a str,
+	release: &'a str,
+	duration_secs: Uint,
+
+	start: String,
+	end: String,
+
+	local: MaybeBool,
+	new: MaybeBool,
+	request: MaybeBool,
+	various_artists: MaybeBool,
+
+	show: &'a str,
+	persona: &'a str
+}
+
+const CSV_HEADER: &str = "logged_at,spin_id,artist,song,release,duration_secs,start,end,local,new,request,various_artists,show,persona";
+const LOG_FILE_PREFIX: &str = "spins_";
+
+lazy_static::lazy_static!(
+	static ref LOG_FILE_NAME_REGEXP: Regex = Regex::new(r#"^spins_(\d{4}-\d{2}-\d{2})\.(jsonl|csv)$"#).unwrap();
+);
+
+//////////
+
+impl SpinLogRecord<'_> {
+	fn to_csv_row(&self) -> String {
+		fn escape(field: &str) -> String {
+			if field.contains([',', '"', '\n', '\r']) {format!("\"{}\"", field.replace('"', "\"\""))}
+			else {field.to_string()}
+		}
+
+		fn flag(maybe_flag: MaybeBool) -> &'static str {
+			match maybe_flag {
+				Some(true) => "true",
+				Some(false) => "false",
+				None => ""
+			}
+		}
+
+		[
+			escape(&self.logged_at), self.spin_id.to_string(),
+			escape(self.artist), escape(self.song), escape(self.release), self.duration_secs.to_string(),
+			escape(&self.start), escape(&self.end),
+			flag(self.local).to_string(), flag(self.new).to_string(), flag(self.request).to_string(), flag(self.various_artists).to_string(),
+			escape(self.show), escape(self.persona)
+		].join(",")
+	}
+}
+
+// This appends a line to a file, and returns true if the file was just made
+fn append_line(path: &str, line: &str, maybe_header: Option<&str>) -> GenericResult<bool> {
+	let is_new_file = !std::path::Path::new(path).exists();
+	let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+	if let (true, Some(header)) = (is_new_file, maybe_header) {
+		writeln!(file, "{header}")?;
+	}
+
+	writeln!(file, "{line}")?;
+	Ok(is_new_file)
+}
+
+// This returns `None` if the file doesn't exist, or if its last line has no spin ID
+fn get_last_logged_spin_id(path: &str) -> Option<SpinitronModelId> {
+	let contents = std::fs::read_to_string(path).ok()?;
+	let last_line = contents.lines().rev().find(|line| !line.trim().is_empty())?;
+	let record: serde_json::Value = serde_json::from_str(last_line).ok()?;
+	record["spin_id"].as_u64()?.try_into().ok()
+}
+
+fn prune_old_log_files(config: &SpinLogConfig, today: chrono::NaiveDate) -> MaybeError {
+	let oldest_date_to_keep = today - chrono::Duration::days(config.retention_days.into());
+
+	for entry in std::fs::read_dir(&config.dir)? {
+		let entry = entry?;
+		let file_name = entry.file_name();
+
+		let Some(captures) = file_name.to_str().and_then(|file_name| LOG_FILE_NAME_REGEXP.captures(file_name)) else {continue};
+		let Ok(date) = chrono::NaiveDate::parse_from_str(&captures[1], "%Y-%m-%d") else {continue};
+
+		if date < oldest_date_to_keep {
+			log::info!("Deleting the old spin log at '{}'.", entry.path().display());
+			std::fs::remove_file(entry.path())?;
+		}
+	}
+
+	Ok(())
+}
+
+//////////
+
+// Old log files are pruned whenever a new day's file is started
+pub fn log_spin(config: &SpinLogConfig, spin: &Spin, show: &Show, persona: &Persona) -> MaybeError {
+	std::fs::create_dir_all(&config.dir).context(format!("Could not make the spin log directory at '{}'", config.dir))?;
+
+	let curr_time = chrono::Local::now();
+	let today = curr_time.date_naive();
+	let base_path = format!("{}/{LOG_FILE_PREFIX}{}", config.dir, today.format("%Y-%m-%d"));
+	let jsonl_path = format!("{base_path}.jsonl");
+
+	if get_last_logged_spin_id(&jsonl_path) == Some(spin.get_id()) {
+		return Ok(());
+	}
+
+	// Times that can't be parsed are left blank
+	let format_time = |maybe_time: GenericResult<chrono::DateTime<chrono::Utc>>| match maybe_time {
+		Ok(time) => time.with_timezone(&chrono::Local).to_rfc3339(),
+		Err(_) => String::new()
+	};
+
+	let record = SpinLogRecord {
+		logged_at: curr_time.to_rfc3339(),
+		spin_id: spin.get_id(),
+
+		artist: spin.get_artist(),
+		song: spin.get_song(),
+		release: spin.get_release(),
+		duration_secs: spin.get_duration_secs(),
+
+		start: format_time(spin.get_start_time()),
+		end: format_time(spin.get_end_time()),
+
+		local: spin.is_local(),
+		new: spin.is_new(),
+		request: spin.is_request(),
+		various_artists: spin.is_various_artists(),
+
+		show: show.get_title(),
+		persona: persona.get_name()
+	};
+
+	let started_new_file = append_line(&jsonl_path, &serde_json::to_string(&record)?, None)?;
+
+	if config.write_csv {
+		append_line(&format!("{base_path}.csv"), &record.to_csv_row(), Some(CSV_HEADER))?;
+	}
+
+	if started_new_file {
+		prune_old_log_files(config, today)?;
+	}
+
+	Ok(())
+}
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn make_temp_dir(name: &str) -> String {
+		let dir = std::env::temp_dir().join(format!("spin_log_test_{name}_{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir.to_str().unwrap().to_string()
+	}
+
+	fn make_record<'a>(artist: &'a str, song: &'a str, various_artists: MaybeBool) -> SpinLogRecord<'a> {
+		SpinLogRecord {
+			logged_at: "2024-03-15T10:00:00-04:00".to_string(),
+			spin_id: 42,
+			artist, song,
+			release: "Release",
+			duration_secs: 180,
+			start: String::new(),
+			end: String::new(),
+			local: Some(true),
+			new: Some(false),
+			request: None,
+			various_artists,
+			show: "Show",
+			persona: "DJ"
+		}
+	}
+
+	#[test]
+	fn csv_rows_escape_special_characters() {
+		assert_eq!(make_record("Plain", "Song", None).to_csv_row(),
+			"2024-03-15T10:00:00-04:00,42,Plain,Song,Release,180,,,true,false,,,Show,DJ");
+
+		assert_eq!(make_record("Crosby, Stills & Nash", "The \"Quoted\" Song", Some(true)).to_csv_row(),
+			"2024-03-15T10:00:00-04:00,42,\"Crosby, Stills & Nash\",\"The \"\"Quoted\"\" Song\",Release,180,,,true,false,,true,Show,DJ");
+
+		assert_eq!(make_record("Line\nBreak", "Song", None).to_csv_row(),
+			"2024-03-15T10:00:00-04:00,42,\"Line\nBreak\",Song,Release,180,,,true,false,,,Show,DJ");
+	}
+
+	#[test]
+	fn prunes_only_old_log_files() {
+		let dir = make_temp_dir("prune");
+		let config = SpinLogConfig {dir: dir.clone(), write_csv: true, retention_days: 7};
+
+		let file_names = [
+			"spins_2024-03-01.jsonl", "spins_2024-03-01.csv", // These are too old
+			"spins_2024-03-08.jsonl", "spins_2024-03-15.csv", // These are within the retention period
+			"notes.txt", "spins_not-a-date.jsonl" // And these are not spin logs
+		];
+
+		for file_name in file_names {
+			std::fs::write(format!("{dir}/{file_name}"), "").unwrap();
+		}
+
+		prune_old_log_files(&config, chrono::NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()).unwrap();
+
+		let mut remaining: Vec<String> = std::fs::read_dir(&dir).unwrap()
+			.map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+			.collect();
+
+		remaining.sort();
+		assert_eq!(remaining, ["notes.txt", "spins_2024-03-08.jsonl", "spins_2024-03-15.csv", "spins_not-a-date.jsonl"]);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn finds_the_last_logged_spin_id() {
+		let dir = make_temp_dir("last_id");
+		let path = format!("{dir}/spins_2024-03-15.jsonl");
+
+		assert_eq!(get_last_logged_spin_id(&path), None);
+
+		std::fs::write(&path, "{\"spin_id\": 1}\n{\"spin_id\": 2}\n\n").unwrap();
+		assert_eq!(get_last_logged_spin_id(&path), Some(2));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use chrono::Timelike;
+use chrono::Duration;
 
 use crate::{
 	request,
@@ -11,10 +11,19 @@ use crate::{
 		thread_task::{Updatable, ContinuallyUpdated}
 	},
 
-	spinitron::model::{
-		NUM_SPINITRON_MODEL_TYPES,
-		Spin, Playlist, Persona, Show,
-		SpinitronModel, SpinitronModelName
+	spinitron::{
+		api::ApiAccess,
+		cache::{self, CacheConfig},
+		spin_log::{self, SpinLogConfig},
+		now_playing::{NowPlayingExporter, NowPlayingExportConfig},
+		poll_schedule::{PollSchedule, PollTiming},
+		wrapper_types::SpinitronModelId,
+
+		model::{
+			NUM_SPINITRON_MODEL_TYPES,
+			Spin, Playlist, Persona, Show,
+			SpinitronModel, SpinitronModelName
+		}
 	}
 };
 
@@ -35,16 +44,14 @@ struct SpinExpiryData {
 }
 
 impl SpinExpiryData {
-	fn new(expiry_duration: chrono::Duration, spin: &Spin) -> GenericResult<Self> {
-		let mut data = Self {
+	// The placeholder spin is not marked as expired (that happens once a real spin is loaded)
+	const fn new(expiry_duration: chrono::Duration) -> Self {
+		Self {
 			expiry_duration,
 			end_time: chrono::DateTime::<chrono::Utc>::MIN_UTC,
 			marked_as_expired: false,
 			just_expired: false
-		};
-
-		data.mark_expiration(spin)?;
-		Ok(data)
+		}
 	}
 
 	fn mark_expiration(&mut self, spin: &Spin) -> MaybeError {
@@ -69,9 +76,17 @@ impl SpinExpiryData {
 
 //////////
 
+// This is where the current models came from
+#[derive(Copy, Clone, PartialEq)]
+enum ModelSource {
+	Placeholder, // Nothing was loaded yet (so that startup does not depend on the network)
+	Cache {is_stale: bool},
+	Spinitron
+}
+
 #[derive(Clone)]
 struct SpinitronStateData {
-	api_key: String,
+	api_access: ApiAccess,
 
 	spin: Spin,
 	playlist: Playlist,
@@ -84,43 +99,144 @@ struct SpinitronStateData {
 
 	/* The boolean at index `i` is true if the model at index `i` was recently
 	updated. Model indices are (in order) spin, playlist, persona, and show. */
-	update_statuses: [bool; NUM_SPINITRON_MODEL_TYPES]
+	update_statuses: [bool; NUM_SPINITRON_MODEL_TYPES],
+
+	/* These are set when a model is replaced, and only cleared once the work for it has succeeded
+	(so that a failing request or write is retried on the next update, instead of the model being skipped).
+	`sync_models` can fail partway through, after some models were already replaced. */
+	model_textures_are_outdated: [bool; NUM_SPINITRON_MODEL_TYPES],
+	cache_is_outdated: bool,
+	spin_log_is_outdated: bool,
+	now_playing_is_outdated: bool,
+
+	// This is the last spin that had a texture made (and its texture), so that the spin history can reuse it
+	maybe_prev_spin_texture: Option<(SpinitronModelId, Vec<u8>)>,
+
+	model_source: ModelSource,
+	maybe_cache_config: Option<CacheConfig>,
+	maybe_spin_log_config: Option<SpinLogConfig>,
+	maybe_now_playing_exporter: Option<NowPlayingExporter>,
+
+	/* These are the spins before the current one (newest first), and their precached texture bytes.
+	The history is resynced whenever the current spin changes. */
+	spin_history: Vec<Spin>,
+	spin_history_texture_bytes: Vec<Vec<u8>>,
+	spin_history_length: usize,
+	spin_history_is_outdated: bool,
+
+	/* These are the shows from the current one until the end of the day (in order).
+	The list is resynced whenever the current show changes. */
+	upcoming_shows: Vec<Show>,
+	max_num_upcoming_shows: usize,
+	upcoming_shows_are_outdated: bool,
+
+	/* These are the spins that were logged ahead of time (for the explicit content guard to check).
+	They're resynced whenever the current spin is polled, since they can be logged at any time. */
+	queued_spins: Vec<Spin>,
+	max_num_queued_spins: usize,
+	queued_spins_are_outdated: bool,
+
+	// The persona is polled along with the playlist, since it's based on the playlist's persona id
+	spin_poll_schedule: PollSchedule,
+	playlist_poll_schedule: PollSchedule,
+	show_poll_schedule: PollSchedule
 }
 
+/* Spins are polled eagerly from just before they're expected to end, until a few minutes after
+(since DJs often log the next spin late). Playlists and shows change on the schedule, so they're
+polled eagerly for less time around their ends, and rarely otherwise. */
+const SPIN_POLL_TIMING: PollTiming = PollTiming::new(Duration::seconds(10), Duration::minutes(5), Duration::minutes(2));
+const PLAYLIST_POLL_TIMING: PollTiming = PollTiming::new(Duration::seconds(30), Duration::minutes(3), Duration::minutes(5));
+const SHOW_POLL_TIMING: PollTiming = PollTiming::new(Duration::seconds(0), Duration::minutes(2), Duration::minutes(10));
+
 type WindowSize = (u32, u32);
 type SpinitronModels<'a> = [&'a dyn SpinitronModel; NUM_SPINITRON_MODEL_TYPES];
 
-// The third param is the fallback texture creation info, and the fourth one is the spin window size
-type SpinitronStateDataParams<'a> = (&'a str, chrono::Duration, &'static TextureCreationInfo<'static>, WindowSize);
+/* The third param is the fallback texture creation info, the fourth one is the spin window size,
+the fifth one is the cache config (if the models should be persisted to disk), the sixth one
+is the number of spins to keep in the spin history (besides the current one), the seventh
+one is the max number of upcoming shows to keep (including the current one), the eighth one is
+the max number of queued spins to keep, the ninth one is the spin log config (if new spins should be
+archived to disk), and the tenth one is the now-playing export config (if the current models should
+be shared with other systems). */
+type SpinitronStateDataParams<'a> = (&'a ApiAccess, chrono::Duration,
+	&'static TextureCreationInfo<'static>, WindowSize, Option<&'a CacheConfig>, usize, usize, usize,
+	Option<&'a SpinLogConfig>, Option<&'a NowPlayingExportConfig>);
 
 //////////
 
 impl SpinitronStateData {
-	fn new((api_key, spin_expiry_duration,
-		fallback_texture_creation_info, spin_window_size):
+	fn new((api_access, spin_expiry_duration,
+		fallback_texture_creation_info, spin_window_size, maybe_cache_config,
+		spin_history_length, max_num_upcoming_shows, max_num_queued_spins, maybe_spin_log_config,
+		maybe_now_playing_export_config):
 		SpinitronStateDataParams) -> GenericResult<Self> {
 
-		let spin = Spin::get(api_key)?;
-		let playlist = Playlist::get(api_key)?;
-		let persona =  Persona::get(api_key, &playlist)?;
-		let show = Show::get(api_key)?;
-
-		let spin_expiry_data = SpinExpiryData::new(spin_expiry_duration, &spin)?;
+		/* No requests are made here; the models start out as cached ones, or as placeholders with
+		fallback textures, and the continual updater fills them in once Spinitron can be reached. */
 
 		const INITIAL_PRECACHED: Vec<u8> = Vec::new();
 
 		let mut data = Self {
-			api_key: api_key.to_string(),
+			api_access: api_access.clone(),
 
-			spin, playlist, persona, show,
+			spin: Spin::default(),
+			playlist: Playlist::default(),
+			persona: Persona::default(),
+			show: Show::default(),
 
-			spin_expiry_data,
+			spin_expiry_data: SpinExpiryData::new(spin_expiry_duration),
 			precached_texture_bytes: [INITIAL_PRECACHED; NUM_SPINITRON_MODEL_TYPES],
 			fallback_texture_creation_info,
 
-			update_statuses: [false; NUM_SPINITRON_MODEL_TYPES]
+			update_statuses: [false; NUM_SPINITRON_MODEL_TYPES],
+
+			model_textures_are_outdated: [false; NUM_SPINITRON_MODEL_TYPES],
+			cache_is_outdated: false,
+			spin_log_is_outdated: false,
+			now_playing_is_outdated: false,
+			maybe_prev_spin_texture: None,
+
+			model_source: ModelSource::Placeholder,
+			maybe_cache_config: maybe_cache_config.cloned(),
+			maybe_spin_log_config: maybe_spin_log_config.cloned(),
+			maybe_now_playing_exporter: maybe_now_playing_export_config.map(NowPlayingExporter::new).transpose()?,
+
+			spin_history: Vec::new(),
+			spin_history_texture_bytes: Vec::new(),
+			spin_history_length,
+			spin_history_is_outdated: true,
+
+			upcoming_shows: Vec::new(),
+			max_num_upcoming_shows,
+			upcoming_shows_are_outdated: true,
+
+			queued_spins: Vec::new(),
+			max_num_queued_spins,
+			queued_spins_are_outdated: true,
+
+			spin_poll_schedule: PollSchedule::new(SPIN_POLL_TIMING),
+			playlist_poll_schedule: PollSchedule::new(PLAYLIST_POLL_TIMING),
+			show_poll_schedule: PollSchedule::new(SHOW_POLL_TIMING)
 		};
 
+		if let Some(cache_config) = maybe_cache_config {
+			match cache::load(cache_config) {
+				Ok(Some(cached)) => {
+					data.spin_expiry_data.mark_expiration(&cached.spin)?;
+
+					(data.spin, data.playlist, data.persona, data.show) = (cached.spin, cached.playlist, cached.persona, cached.show);
+					data.precached_texture_bytes = cached.texture_bytes;
+					data.model_source = ModelSource::Cache {is_stale: cached.is_stale};
+
+					return Ok(data);
+				},
+
+				Ok(None) => {},
+				Err(err) => log::warn!("Could not load the cached Spinitron data: '{err:#}'.")
+			}
+		}
+
 		data.precached_texture_bytes = data.get_models().map( // TODO: don't unwrap once `try_map` becomes stable
 			|model| data.get_model_texture_bytes(model, spin_window_size).unwrap()
 		);
@@ -128,6 +244,42 @@ impl SpinitronStateData {
 		Ok(data)
 	}
 
+	fn save_to_cache(&self) -> MaybeError {
+		let Some(cache_config) = &self.maybe_cache_config else {return Ok(())};
+		cache::save(&cache_config.dir, (&self.spin, &self.playlist, &self.persona, &self.show), &self.precached_texture_bytes)
+	}
+
+	fn log_spin(&self) -> MaybeError {
+		let Some(spin_log_config) = &self.maybe_spin_log_config else {return Ok(())};
+		spin_log::log_spin(spin_log_config, &self.spin, &self.show, &self.persona)
+	}
+
+	// The spin is left out once it's expired, so that other systems don't keep showing an old spin
+	fn export_now_playing(&self) -> MaybeError {
+		let Some(exporter) = &self.maybe_now_playing_exporter else {return Ok(())};
+		let maybe_spin = (!self.spin_expiry_data.marked_as_expired).then_some(&self.spin);
+		exporter.export(maybe_spin, &self.show, &self.persona)
+	}
+
+	// This marks everything that depends on the model at the given index as outdated (right after the model is replaced)
+	fn mark_model_as_replaced(&mut self, model_name: SpinitronModelName) {
+		self.model_textures_are_outdated[model_name as usize] = true;
+		self.cache_is_outdated = true;
+		self.now_playing_is_outdated = true;
+
+		match model_name {
+			SpinitronModelName::Spin => {
+				self.spin_log_is_outdated = true;
+				self.spin_history_is_outdated = true;
+			},
+
+			// The upcoming shows only change on show boundaries
+			SpinitronModelName::Show => self.upcoming_shows_are_outdated = true,
+
+			SpinitronModelName::Playlist | SpinitronModelName::Persona => {}
+		}
+	}
+
 	fn get_model_texture_bytes(&self, model: &dyn SpinitronModel, size_pixels: WindowSize) -> GenericResult<Vec<u8>> {
 		fn load_for_info(info: Cow<TextureCreationInfo>) -> GenericResult<Vec<u8>> {
 			/* I am doing this to speed up the loading of textures on the main
@@ -163,44 +315,125 @@ impl SpinitronStateData {
 		[&self.spin, &self.playlist, &self.persona, &self.show]
 	}
 
+	// Each model is only requested when its poll schedule says so (see `poll_schedule.rs`)
 	fn sync_models(&mut self) -> MaybeError {
-		let api_key = &self.api_key;
+		let curr_time = chrono::Utc::now();
 
 		// Step 1: get the current spin.
-		let maybe_new_spin = Spin::get(api_key)?;
+		if self.spin_poll_schedule.is_time_to_poll(curr_time) {
+			let maybe_new_spin = Spin::get(&self.api_access)?;
+			self.queued_spins_are_outdated = true;
+
+			if maybe_new_spin.get_id() != self.spin.get_id() {
+				// The texture of the spin being replaced is kept, unless it was never made
+				if !self.model_textures_are_outdated[SpinitronModelName::Spin as usize] && self.spin_history_length != 0 {
+					self.maybe_prev_spin_texture = Some((self.spin.get_id(),
+						self.precached_texture_bytes[SpinitronModelName::Spin as usize].clone()));
+				}
+
+				self.spin = maybe_new_spin;
+				self.mark_model_as_replaced(SpinitronModelName::Spin);
+			}
 
-		if maybe_new_spin.get_id() != self.spin.get_id() {
-			self.spin = maybe_new_spin;
+			self.spin_poll_schedule.schedule_next_poll(curr_time, self.spin.get_end_time().ok());
 		}
 
 		//////////
 
 		/* Step 2: get a maybe new playlist (don't base it on a spin ID,
 		since the spin may not belong to a playlist under automation). */
-		let maybe_new_playlist = Playlist::get(api_key)?;
+		if self.playlist_poll_schedule.is_time_to_poll(curr_time) {
+			let maybe_new_playlist = Playlist::get(&self.api_access)?;
+
+			if maybe_new_playlist.get_id() != self.playlist.get_id() {
+				/* Step 3: get the persona id based on the playlist id (since otherwise, you'll
+				just get some persona that's first in Spinitron's internal list of personas. */
+				let new_persona = Persona::get(&self.api_access, &maybe_new_playlist)?;
 
-		if maybe_new_playlist.get_id() != self.playlist.get_id() {
-			/* Step 3: get the persona id based on the playlist id (since otherwise, you'll
-			just get some persona that's first in Spinitron's internal list of personas. */
-			self.persona = Persona::get(api_key, &maybe_new_playlist)?;
-			self.playlist = maybe_new_playlist;
+				if new_persona.get_id() != self.persona.get_id() {
+					self.persona = new_persona;
+					self.mark_model_as_replaced(SpinitronModelName::Persona);
+				}
+
+				self.playlist = maybe_new_playlist;
+				self.mark_model_as_replaced(SpinitronModelName::Playlist);
+			}
+
+			self.playlist_poll_schedule.schedule_next_poll(curr_time, self.playlist.get_end_time().ok());
 		}
 
 		//////////
 
-		let curr_minutes = chrono::Local::now().minute();
+		/* Step 4: get the current show id (based on what's on the
+		schedule, irrespective of what show was last on).
+		This is not in the branch above, since the show should
+		change directly on schedule, not when a new playlist is made. */
+		if self.show_poll_schedule.is_time_to_poll(curr_time) {
+			let new_show = Show::get(&self.api_access)?;
+
+			if new_show.get_id() != self.show.get_id() {
+				self.show = new_show;
+				self.mark_model_as_replaced(SpinitronModelName::Show);
+			}
 
-		// Shows can only be scheduled under 30-minute intervals
-		if curr_minutes == 0 || curr_minutes == 30 {
-			/* Step 4: get the current show id (based on what's on the
-			schedule, irrespective of what show was last on).
-			This is not in the branch above, since the show should
-			change directly on schedule, not when a new playlist is made. */
-			self.show = Show::get(api_key)?;
+			self.show_poll_schedule.schedule_next_poll(curr_time, self.show.get_end_time().ok());
 		}
 
 		Ok(())
 	}
+
+	/* The texture bytes of spins that were already loaded are reused (this includes the
+	previous spin, which is passed in, since it's usually the newest one in the history now). */
+	fn sync_spin_history(&mut self, spin_window_size: WindowSize) -> MaybeError {
+
+		// One more spin is requested, since the current spin is included in the response
+		let recent_spins = Spin::get_most_recent(&self.api_access, (self.spin_history_length + 1).try_into()?)?;
+
+		let curr_spin_id = self.spin.get_id();
+
+		let new_history: Vec<Spin> = recent_spins.into_iter()
+			.filter(|spin| spin.get_id() != curr_spin_id)
+			.take(self.spin_history_length)
+			.collect();
+
+		let new_texture_bytes = new_history.iter().map(|spin| {
+			let spin_id = spin.get_id();
+
+			if let Some((prev_spin_id, prev_spin_texture_bytes)) = &self.maybe_prev_spin_texture {
+				if *prev_spin_id == spin_id {
+					return Ok(prev_spin_texture_bytes.clone());
+				}
+			}
+
+			match self.spin_history.iter().position(|old_spin| old_spin.get_id() == spin_id) {
+				Some(index) => Ok(self.spin_history_texture_bytes[index].clone()),
+				None => self.get_model_texture_bytes(spin, spin_window_size)
+			}
+		}).collect::<GenericResult<_>>()?;
+
+		self.spin_history = new_history;
+		self.spin_history_texture_bytes = new_texture_bytes;
+
+		Ok(())
+	}
+
+	// This gets the shows from now until the end of the local day
+	fn sync_upcoming_shows(&mut self) -> MaybeError {
+		let tomorrow = chrono::Local::now().date_naive().succ_opt().context("There is no date after today")?;
+
+		let end_of_today = tomorrow.and_time(chrono::NaiveTime::MIN).and_local_timezone(chrono::Local)
+			.earliest().context("Could not find the local time for the end of today")?;
+
+		self.upcoming_shows = Show::get_upcoming(&self.api_access,
+			end_of_today.into(), self.max_num_upcoming_shows.try_into()?)?;
+
+		Ok(())
+	}
+
+	fn sync_queued_spins(&mut self) -> MaybeError {
+		self.queued_spins = Spin::get_queued(&self.api_access, self.max_num_queued_spins.try_into()?)?;
+		Ok(())
+	}
 }
 
 impl Updatable for SpinitronStateData {
@@ -209,30 +442,100 @@ impl Updatable for SpinitronStateData {
 	fn update(&mut self, param: &Self::Param) -> MaybeError {
 		////////// Update the models
 
-		let get_model_ids = |data: &Self|
-			data.get_models().map(|model| model.get_id());
+		// Everything is refreshed on the first sync, since cached models may have the same ids as the synced ones
+		if self.model_source != ModelSource::Spinitron {
+			self.model_textures_are_outdated = [true; NUM_SPINITRON_MODEL_TYPES];
+			self.cache_is_outdated = true;
+			self.now_playing_is_outdated = true;
+			self.model_source = ModelSource::Spinitron;
+		}
 
-		let original_ids = get_model_ids(self);
 		self.sync_models()?;
-		let new_ids = get_model_ids(self);
 
 		////////// Update the model textures
 
-		// TODO: how to do this without all the indexing?
-		for i in 0..NUM_SPINITRON_MODEL_TYPES {
-			let updated = original_ids[i] != new_ids[i];
+		/* All of the textures are made before any of them are kept, so that if one fails, the update statuses
+		are not lost (the main thread only sees the data from updates that succeed). */
+		let models = self.get_models();
+
+		let new_texture_bytes = (0..NUM_SPINITRON_MODEL_TYPES)
+			.filter(|&i| self.model_textures_are_outdated[i])
+			.map(|i| Ok((i, self.get_model_texture_bytes(models[i], *param)?)))
+			.collect::<GenericResult<Vec<_>>>()?;
+
+		self.update_statuses = self.model_textures_are_outdated;
+		self.model_textures_are_outdated = [false; NUM_SPINITRON_MODEL_TYPES];
+
+		for (i, texture_bytes) in new_texture_bytes {
+			self.precached_texture_bytes[i] = texture_bytes;
+		}
+
+		/* From here on, failures are only logged (and whatever failed stays outdated, so that it's retried on the next update).
+		Returning them would make the main thread miss the model updates above, which have already happened here. */
+
+		////////// Update the cache and the spin log
+
+		if self.cache_is_outdated {
+			match self.save_to_cache() {
+				Ok(()) => self.cache_is_outdated = false,
+				Err(err) => log::warn!("Could not save the Spinitron data to the cache (retrying on the next update): '{err:#}'.")
+			}
+		}
+
+		if self.spin_log_is_outdated {
+			match self.log_spin() {
+				Ok(()) => self.spin_log_is_outdated = false,
+				Err(err) => log::warn!("Could not write the new spin to the spin log (retrying on the next update): '{err:#}'.")
+			}
+		}
+
+		////////// Update the spin history
 
-			if updated {
-				let model = self.get_models()[i];
-				self.precached_texture_bytes[i] = self.get_model_texture_bytes(model, *param)?;
+		if self.spin_history_is_outdated && self.spin_history_length != 0 {
+			match self.sync_spin_history(*param) {
+				Ok(()) => {
+					self.spin_history_is_outdated = false;
+					self.maybe_prev_spin_texture = None;
+				},
+
+				Err(err) => log::error!("Could not sync the spin history (retrying on the next update): '{err}'.")
 			}
+		}
 
-			self.update_statuses[i] = updated;
+		////////// Update the upcoming shows
+
+		if self.upcoming_shows_are_outdated && self.max_num_upcoming_shows != 0 {
+			match self.sync_upcoming_shows() {
+				Ok(()) => self.upcoming_shows_are_outdated = false,
+				Err(err) => log::error!("Could not sync the upcoming shows (retrying on the next update): '{err}'.")
+			}
+		}
+
+		////////// Update the queued spins
+
+		if self.queued_spins_are_outdated && self.max_num_queued_spins != 0 {
+			match self.sync_queued_spins() {
+				Ok(()) => self.queued_spins_are_outdated = false,
+				Err(err) => log::error!("Could not sync the queued spins (retrying on the next update): '{err}'.")
+			}
 		}
 
 		////////// Marking the expiration of the current spin
 
-		self.spin_expiry_data.mark_expiration(&self.spin)?;
+		if let Err(err) = self.spin_expiry_data.mark_expiration(&self.spin) {
+			log::error!("Could not mark the expiration of the current spin: '{err}'.");
+		}
+
+		////////// Exporting the now-playing data
+
+		self.now_playing_is_outdated |= self.spin_expiry_data.just_expired;
+
+		if self.now_playing_is_outdated {
+			match self.export_now_playing() {
+				Ok(()) => self.now_playing_is_outdated = false,
+				Err(err) => log::warn!("Could not export the now-playing data (retrying on the next update): '{err:#}'.")
+			}
+		}
 
 		Ok(())
 	}
@@ -269,6 +572,49 @@ impl SpinitronState {
 		}
 	}
 
+	pub const fn get_spin(&self) -> &Spin {
+		&self.continually_updated.get_data().spin
+	}
+
+	// These are the spins before the current one, newest first
+	pub fn get_spin_history(&self) -> &[Spin] {
+		&self.continually_updated.get_data().spin_history
+	}
+
+	// These start with the current show (if there is one), and go until the end of the day
+	pub fn get_upcoming_shows(&self) -> &[Show] {
+		&self.continually_updated.get_data().upcoming_shows
+	}
+
+	// These are the spins that were logged ahead of time, in order of their start times
+	pub fn get_queued_spins(&self) -> &[Spin] {
+		&self.continually_updated.get_data().queued_spins
+	}
+
+	// The index is into the spin history
+	pub fn get_cached_spin_history_texture_creation_info(&self, index: usize) -> TextureCreationInfo<'_> {
+		TextureCreationInfo::RawBytes(&self.continually_updated.get_data().spin_history_texture_bytes[index])
+	}
+
+	pub const fn get_show(&self) -> &Show {
+		&self.continually_updated.get_data().show
+	}
+
+	// This stays true for as long as the current spin is expired (unlike `is_spin_and_just_expired`)
+	pub const fn spin_is_expired(&self) -> bool {
+		self.continually_updated.get_data().spin_expiry_data.marked_as_expired
+	}
+
+	// This is true until the models are first loaded (from Spinitron, or from the cache)
+	pub fn is_connecting(&self) -> bool {
+		self.continually_updated.get_data().model_source == ModelSource::Placeholder
+	}
+
+	// This is true if the models came from a stale cache, and Spinitron has not been reached yet
+	pub fn is_showing_stale_cache(&self) -> bool {
+		self.continually_updated.get_data().model_source == ModelSource::Cache {is_stale: true}
+	}
+
 	pub const fn is_spin_and_just_expired(&self, model_name: SpinitronModelName) -> bool {
 		matches!(model_name, SpinitronModelName::Spin) && self.continually_updated.get_data().spin_expiry_data.just_expired
 	}
@@ -300,3 +646,32 @@ impl SpinitronState {
 		self.continually_updated.update(&self.saved_continually_updated_param)
 	}
 }
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn corrupt_cache_falls_back_to_placeholders() {
+		const FALLBACK_TEXTURE_CREATION_INFO: TextureCreationInfo<'static> =
+			TextureCreationInfo::Path(Cow::Borrowed("assets/no_texture_available.png"));
+
+		let cache_dir = std::env::temp_dir().join(format!("spinitron_state_test_corrupt_cache_{}", std::process::id()));
+		std::fs::create_dir_all(&cache_dir).unwrap();
+		std::fs::write(cache_dir.join("models.json"), "not json").unwrap();
+
+		let cache_config = CacheConfig {dir: cache_dir.to_str().unwrap().to_string(), max_age_hours: 12.0, show_when_stale: true};
+		let api_access = ApiAccess {base_url: "http://127.0.0.1:9".to_string(), api_key: String::new()};
+
+		let data = SpinitronStateData::new((&api_access, Duration::minutes(20), &FALLBACK_TEXTURE_CREATION_INFO,
+			(100, 100), Some(&cache_config), 0, 0, 0, None, None)).unwrap();
+
+		assert!(data.model_source == ModelSource::Placeholder);
+		assert_eq!(data.spin.get_id(), Spin::default().get_id());
+		assert!(data.precached_texture_bytes.iter().all(|bytes| !bytes.is_empty()));
+
+		std::fs::remove_dir_all(&cache_dir).unwrap();
+	}
+}
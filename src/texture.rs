@@ -8,12 +8,12 @@ use sdl2::{
 	rect::Rect,
 	surface::Surface,
 	image::LoadTexture,
-	render::{self, Texture}
+	render::{self, Texture, RenderTarget}
 };
 
 use crate::{
 	request,
-	window_tree::{CanvasSDL, ColorSDL},
+	window_tree::{CanvasSDL, ColorSDL, FrameTime},
 	utility_types::{generic_result::*, vec2f::assert_in_unit_interval}
 };
 
@@ -142,7 +142,6 @@ pub enum TextureCreationInfo<'a> {
 - TODO: perhaps when doing the remaking thing, pass the handle in as `mut`, even when the handle is not modified (would this help?). */
 
 type InnerTextureHandle = u16;
-type TextureCreator = render::TextureCreator<sdl2::video::WindowContext>;
 
 type FontPointSize = u16;
 
@@ -161,6 +160,28 @@ pub struct SideScrollingTextMetadata {
 	text: String
 }
 
+/* The texture creators for window canvases and surface canvases (the latter being used for
+headless rendering) have different types, so the texture pool goes through this trait instead. */
+pub trait TextureCreator {
+	fn load_texture_from_bytes(&self, bytes: &[u8]) -> Result<Texture<'_>, String>;
+	fn load_texture_from_path(&self, path: &str) -> Result<Texture<'_>, String>;
+	fn make_texture_from_surface(&self, surface: Surface<'_>) -> Result<Texture<'_>, String>;
+}
+
+impl<T> TextureCreator for render::TextureCreator<T> {
+	fn load_texture_from_bytes(&self, bytes: &[u8]) -> Result<Texture<'_>, String> {
+		self.load_texture_bytes(bytes)
+	}
+
+	fn load_texture_from_path(&self, path: &str) -> Result<Texture<'_>, String> {
+		self.load_texture(path)
+	}
+
+	fn make_texture_from_surface(&self, surface: Surface<'_>) -> Result<Texture<'_>, String> {
+		self.create_texture_from_surface(surface).map_err(|err| err.to_string())
+	}
+}
+
 /* TODO:
 - Later on, if I am using multiple texture pools,
 add an id to each texture handle that is meant to match the pool
@@ -174,7 +195,7 @@ the `unsafe_textures` feature help this?
 pub struct TexturePool<'a> {
 	max_texture_size: (u32, u32),
 	textures: Vec<Texture<'a>>,
-	texture_creator: &'a TextureCreator,
+	texture_creator: &'a dyn TextureCreator,
 
 	//////////
 
@@ -198,7 +219,7 @@ impl<'a> TexturePool<'a> {
 	const INITIAL_POINT_SIZE: FontPointSize = 100;
 	const BLANK_TEXT_DEFAULT: &'static str = "<BLANK TEXT>";
 
-	pub fn new(texture_creator: &'a TextureCreator,
+	pub fn new(texture_creator: &'a dyn TextureCreator,
 		ttf_context: &'a ttf::Sdl2TtfContext,
 		max_texture_size: (u32, u32)) -> Self {
 
@@ -290,8 +311,8 @@ impl<'a> TexturePool<'a> {
 	- Would it be possible to manipulate the canvas scale to be able to only pass normalized coordinates to the renderer?
 	- Use `copy_ex` eventually, and the special canvas functions for things like rounded rectangles
 	*/
-	pub fn draw_texture_to_canvas(&self, handle: &TextureHandle,
-		canvas: &mut CanvasSDL, screen_dest: Rect) -> MaybeError {
+	pub fn draw_texture_to_canvas<T: RenderTarget>(&self, handle: &TextureHandle,
+		canvas: &mut CanvasSDL<T>, screen_dest: Rect, curr_time: FrameTime) -> MaybeError {
 
 		let texture = self.get_texture_from_handle(handle);
 		let possible_text_metadata = self.text_metadata.get(handle);
@@ -305,11 +326,8 @@ impl<'a> TexturePool<'a> {
 		let text_metadata = possible_text_metadata.context("Expected text metadata")?;
 		let texture_size = text_metadata.size;
 
-		// TODO: compute the time since the unix epoch outside this fn, somehow (or, use the SDL timer)
-
 		let dest_width = screen_dest.width();
-		let time_since_unix_epoch = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
-		let time_seed = (time_since_unix_epoch.as_millis() as f64 / 1000.0) * (dest_width as f64 / texture_size.0 as f64);
+		let time_seed = (curr_time.timestamp_millis() as f64 / 1000.0) * (dest_width as f64 / texture_size.0 as f64);
 
 		let mut x = texture_size.0;
 
@@ -673,14 +691,14 @@ impl<'a> TexturePool<'a> {
 		match creation_info {
 			// Use this whenever possible (whenever you can preload data into byte form)!
 			TextureCreationInfo::RawBytes(bytes) =>
-				self.texture_creator.load_texture_bytes(bytes),
+				self.texture_creator.load_texture_from_bytes(bytes),
 
 			TextureCreationInfo::Path(path) =>
-				self.texture_creator.load_texture(path as &str),
+				self.texture_creator.load_texture_from_path(path),
 
 			TextureCreationInfo::Url(url) => {
 				let response = request::get(url)?;
-				self.texture_creator.load_texture_bytes(response.as_bytes())
+				self.texture_creator.load_texture_from_bytes(response.as_bytes())
 			}
 
 			TextureCreationInfo::Text((font_info, text_display_info)) => {
@@ -689,8 +707,69 @@ impl<'a> TexturePool<'a> {
 				assert!(surface.width() >= text_display_info.pixel_area.0);
 				assert!(surface.height() == text_display_info.pixel_area.1);
 
-				Ok(self.texture_creator.create_texture_from_surface(surface)?)
+				self.texture_creator.make_texture_from_surface(surface)
 			}
 		}.to_generic()
 	}
 }
+
+//////////
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::{
+		window_tree::{Window, WindowContents},
+		dashboard_defs::dashboard::FONT_INFO,
+		snapshot_testing::assert_matches_golden_image,
+		utility_types::{vec2f::Vec2f, dynamic_optional::DynamicOptional}
+	};
+
+	const TEXT_AREA: (u32, u32) = (320, 32);
+	const LONG_TEXT: &str = "This text is much too long to fit in its box, so it has to scroll to be read fully";
+
+	fn assert_text_matches_golden_image(name: &str, text: &str, scroll_fn: TextTextureScrollFn) {
+		assert_matches_golden_image(name, TEXT_AREA, |texture_pool| {
+			let texture = texture_pool.make_texture(&TextureCreationInfo::Text((
+				Cow::Borrowed(&FONT_INFO),
+
+				TextDisplayInfo {
+					text: DisplayText::new(text),
+					color: ColorSDL::RGB(249, 236, 210),
+					pixel_area: TEXT_AREA,
+					scroll_fn
+				}
+			)))?;
+
+			let window = Window::new(
+				None,
+				DynamicOptional::NONE,
+				WindowContents::Texture(texture),
+				None,
+				Vec2f::ZERO,
+				Vec2f::ONE,
+				None
+			);
+
+			Ok((window, DynamicOptional::NONE))
+		});
+	}
+
+	#[test]
+	fn text_that_fits_does_not_scroll() {
+		assert_text_matches_golden_image("text_fitting", "Short text", |_, _| (0.0, false));
+	}
+
+	// This scrolls far enough for the end of the text to spill over onto the left side of its box
+	#[test]
+	fn scrolling_text_wraps_around() {
+		assert_text_matches_golden_image("text_scrolling_wrapped", LONG_TEXT, |_, _| (0.9, true));
+	}
+
+	// This one depends on the time seed (which comes from the frozen time)
+	#[test]
+	fn scrolling_text_without_wrapping() {
+		assert_text_matches_golden_image("text_scrolling_unwrapped", LONG_TEXT, |seed, _| (seed % 1.0, false));
+	}
+}
@@ -0,0 +1,96 @@
+use std::{
+	sync::Weak,
+	io::{BufRead, BufReader, Write}
+};
+
+use interprocess::local_socket::{
+	ToFsName,
+	GenericFilePath,
+	ListenerOptions,
+	traits::{Listener, Stream},
+	ListenerNonblockingMode,
+	prelude::{LocalSocketListener, LocalSocketStream}
+};
+
+use crate::utility_types::generic_result::*;
+
+/* These are the local sockets that staff (or scripts) send commands to, with something like `echo list | nc -U <socket path>`.
+
+- A socket is overwritten if it already exists (e.g. if it was still around from a crash, or if the dashboard is being rebuilt
+after a config change). Its name is not reclaimed when its listener is dropped, since an old listener being dropped after
+a rebuild would otherwise delete the socket of the new listener.
+- Command listeners read one command per connection on their own thread, and write back one response. They stop
+once the state that they run commands on is dropped. */
+
+const COMMAND_SOCKET_POLL_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
+const COMMAND_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// The description goes in the error message (it's something like "surprise stream listener")
+pub fn make_listener(socket_path: &str, description: &str,
+	nonblocking_mode: ListenerNonblockingMode) -> GenericResult<LocalSocketListener> {
+
+	let options = ListenerOptions::new()
+		.name(socket_path.to_fs_name::<GenericFilePath>()?)
+		.try_overwrite(true)
+		.reclaim_name(false);
+
+	let listener = match options.create_sync() {
+		Ok(listener) => listener,
+
+		Err(err) => {
+			return error_msg!(
+				"Could not create a {description} at '{socket_path}'. \
+				Official error: '{err}'."
+			);
+		}
+	};
+
+	listener.set_nonblocking(nonblocking_mode)?;
+	Ok(listener)
+}
+
+fn handle_command_connection<T>(stream: LocalSocketStream, state: &T, run_command: fn(&T, &str) -> String) -> MaybeError {
+	// The listener thread keeps the state alive while it's blocked here, so an idle client must not block it for long
+	stream.set_recv_timeout(Some(COMMAND_READ_TIMEOUT))?;
+
+	let mut reader = BufReader::new(stream);
+	let mut command = String::new();
+	reader.read_line(&mut command)?;
+
+	let response = run_command(state, &command);
+
+	let mut stream = reader.into_inner();
+	writeln!(stream, "{response}")?;
+	Ok(())
+}
+
+// The name goes in the log messages (it's something like "texter moderation")
+pub fn start_command_listener<T: Send + Sync + 'static>(socket_path: &str, name: &'static str,
+	state: Weak<T>, run_command: fn(&T, &str) -> String) -> MaybeError {
+
+	// Only accepting is nonblocking, so that a command can be read in full once it's accepted
+	let listener = make_listener(socket_path, &format!("{name} command listener"), ListenerNonblockingMode::Accept)?;
+
+	std::thread::spawn(move || {
+		while let Some(state) = state.upgrade() {
+			match listener.accept() {
+				Ok(stream) => {
+					if let Err(err) = handle_command_connection(stream, state.as_ref(), run_command) {
+						log::warn!("Could not handle a {name} command: '{err}'.");
+					}
+				},
+
+				Err(err) => {
+					if err.kind() != std::io::ErrorKind::WouldBlock {
+						log::warn!("Could not accept a {name} command connection: '{err}'.");
+					}
+
+					drop(state);
+					std::thread::sleep(COMMAND_SOCKET_POLL_DURATION);
+				}
+			}
+		}
+	});
+
+	Ok(())
+}
@@ -0,0 +1,42 @@
+use std::time::SystemTime;
+
+//////////
+
+/* This polls the modification times of a set of files (rather than using OS file
+notifications), since it only needs to run every now and then from the main loop.
+A file that can't be read is given no modification time, so it counts as changed once
+it appears again. */
+pub struct FileWatcher {
+	paths_and_modification_times: Vec<(String, Option<SystemTime>)>
+}
+
+impl FileWatcher {
+	pub fn new(paths: &[&str]) -> Self {
+		Self {
+			paths_and_modification_times: paths.iter().map(|path|
+				(path.to_string(), Self::get_modification_time(path))
+			).collect()
+		}
+	}
+
+	fn get_modification_time(path: &str) -> Option<SystemTime> {
+		std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+	}
+
+	// This returns true if any of the files changed since the last call (or since the watcher was made)
+	pub fn any_changed(&mut self) -> bool {
+		let mut any_changed = false;
+
+		for (path, modification_time) in &mut self.paths_and_modification_times {
+			let curr_modification_time = Self::get_modification_time(path);
+
+			if curr_modification_time != *modification_time {
+				log::info!("The file at '{path}' changed.");
+				*modification_time = curr_modification_time;
+				any_changed = true;
+			}
+		}
+
+		any_changed
+	}
+}
@@ -0,0 +1,164 @@
+use std::{
+	any::Any,
+	sync::{Arc, Mutex},
+	collections::HashMap,
+	net::{TcpListener, TcpStream},
+	io::{BufRead, BufReader, Read, Write}
+};
+
+use crate::utility_types::generic_result::*;
+
+/* These are the small local HTTP/1.1 servers (like for the now-playing JSON, the Twilio webhook, and the mock APIs).
+
+- Each server has a state of its own, and a function that turns a request into a response with that state.
+- Since the dashboard can be rebuilt while running, a server is kept running at its address after it's started,
+and starting one at that address again just returns its state (which the caller can then update).
+- Connections are handled one at a time, with one request per connection. */
+
+pub struct HttpRequest {
+	pub method: String,
+	pub target: String, // This is the path, with the query string (if any)
+	headers: Vec<(String, String)>, // The header names are lowercase
+	pub body: Vec<u8>
+}
+
+pub struct HttpResponse {
+	status: &'static str,
+	content_type: &'static str,
+	extra_headers: Vec<(&'static str, &'static str)>,
+	body: Vec<u8>
+}
+
+type RequestHandler<T> = fn(&T, &HttpRequest) -> HttpResponse;
+
+lazy_static::lazy_static!(
+	// This maps the addresses of running servers to their states
+	static ref RUNNING_SERVER_STATES: Mutex<HashMap<String, Arc<dyn Any + Send + Sync>>> = Mutex::new(HashMap::new());
+);
+
+const MAX_REQUEST_BODY_SIZE: usize = 64 * 1024;
+const REQUEST_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+//////////
+
+impl HttpRequest {
+	pub fn get_path(&self) -> &str {
+		self.target.split_once('?').map_or(&self.target, |(path, _)| path)
+	}
+
+	pub fn get_query(&self) -> &str {
+		self.target.split_once('?').map_or("", |(_, query)| query)
+	}
+
+	pub fn get_header(&self, name: &str) -> Option<&str> {
+		self.headers.iter().find(|(header_name, _)| header_name.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+	}
+}
+
+impl HttpResponse {
+	pub fn new(status: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+		Self {status, content_type, extra_headers: Vec::new(), body: body.into()}
+	}
+
+	pub fn with_header(mut self, name: &'static str, value: &'static str) -> Self {
+		self.extra_headers.push((name, value));
+		self
+	}
+}
+
+//////////
+
+// This returns `None` if the request body is too big
+fn read_request(stream: &TcpStream) -> GenericResult<Option<HttpRequest>> {
+	let mut reader = BufReader::new(stream);
+
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+
+	let mut request_parts = request_line.split_whitespace();
+	let method = request_parts.next().unwrap_or_default().to_string();
+	let target = request_parts.next().unwrap_or_default().to_string();
+
+	let mut headers = Vec::new();
+
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {break;}
+
+		let Some((name, value)) = header.split_once(':') else {continue};
+		headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+	}
+
+	let mut request = HttpRequest {method, target, headers, body: Vec::new()};
+	let content_length: usize = request.get_header("content-length").map_or(Ok(0), str::parse)?;
+
+	if content_length > MAX_REQUEST_BODY_SIZE {
+		return Ok(None);
+	}
+
+	request.body = vec![0; content_length];
+	reader.read_exact(&mut request.body)?;
+
+	Ok(Some(request))
+}
+
+fn handle_connection<T>(mut stream: TcpStream, state: &T, handle_request: RequestHandler<T>) -> MaybeError {
+	// Connections are handled one at a time, so a client that sends nothing must not hold up the others
+	stream.set_read_timeout(Some(REQUEST_READ_TIMEOUT))?;
+
+	let response = match read_request(&stream)? {
+		Some(request) => handle_request(state, &request),
+		None => HttpResponse::new("413 Payload Too Large", "text/plain", "The request is too large")
+	};
+
+	write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n", response.status, response.content_type, response.body.len())?;
+
+	for (name, value) in &response.extra_headers {
+		write!(stream, "{name}: {value}\r\n")?;
+	}
+
+	write!(stream, "Connection: close\r\n\r\n")?;
+	stream.write_all(&response.body)?;
+
+	Ok(())
+}
+
+// The name goes in the log messages (it's something like "now-playing")
+pub fn serve<T: Send + Sync + 'static>(listener: TcpListener, name: &'static str, state: Arc<T>, handle_request: RequestHandler<T>) {
+	std::thread::spawn(move || {
+		for stream in listener.incoming() {
+			let result = match stream {
+				Ok(stream) => handle_connection(stream, state.as_ref(), handle_request),
+				Err(err) => Err(err.into())
+			};
+
+			if let Err(err) = result {
+				log::warn!("The {name} server could not handle a request: '{err}'.");
+			}
+		}
+	});
+}
+
+/* This starts a server at the given address (with a default state), or reuses the one that is already running there.
+Either way, its state is returned. */
+pub fn start_or_reuse_server<T: Default + Send + Sync + 'static>(address: &str, name: &'static str,
+	handle_request: RequestHandler<T>) -> GenericResult<Arc<T>> {
+
+	let mut running_servers = RUNNING_SERVER_STATES.lock().unwrap();
+
+	if let Some(state) = running_servers.get(address) {
+		return match state.clone().downcast() {
+			Ok(state) => Ok(state),
+			Err(_) => error_msg!("A different kind of server is already running at '{address}'")
+		};
+	}
+
+	let listener = TcpListener::bind(address).context(format!("Could not start the {name} server at '{address}'"))?;
+
+	let state = Arc::new(T::default());
+	running_servers.insert(address.to_string(), state.clone());
+	serve(listener, name, state.clone(), handle_request);
+
+	log::info!("Started the {name} server at 'http://{address}'.");
+	Ok(state)
+}
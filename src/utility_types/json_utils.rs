@@ -7,9 +7,26 @@ pub fn load_from_file<T: for <'de> serde::Deserialize<'de>>(path: &str) -> Gener
 		Ok(contents) => Ok(contents),
 
 		Err(err) => error_msg!(
-			"The API key file at path '{path}' could not be found. Official error: '{err}'."
+			"The file at path '{path}' could not be found. Official error: '{err}'."
 		)
 	}?;
 
-	serde_json::from_str(&file_contents).to_generic()
+	serde_json::from_str(&file_contents).context(format!("The file at path '{path}' could not be parsed"))
+}
+
+// This appends each entry as a line of JSON (making the file, and its parent directories, if needed)
+pub fn append_json_lines<'a>(path: &str, entries: impl IntoIterator<Item = &'a serde_json::Value>) -> MaybeError {
+	use std::io::Write;
+
+	if let Some(parent_dir) = std::path::Path::new(path).parent() {
+		std::fs::create_dir_all(parent_dir)?;
+	}
+
+	let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+	for entry in entries {
+		writeln!(file, "{entry}")?;
+	}
+
+	Ok(())
 }
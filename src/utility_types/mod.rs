@@ -1,5 +1,8 @@
 pub mod vec2f;
 pub mod json_utils;
+pub mod file_watcher;
+pub mod command_socket;
+pub mod http_server;
 pub mod update_rate;
 pub mod thread_task;
 pub mod generic_result;
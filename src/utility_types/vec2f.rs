@@ -5,7 +5,8 @@ That would then allow for things like closure under multiplication */
 type Component = f32;
 
 // A 0-1 normalized floating-point vec2
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(try_from = "(Component, Component)")]
 pub struct Vec2f {
 	x: Component,
 	y: Component
@@ -41,17 +42,19 @@ impl Vec2f {
 	pub const fn y(&self) -> Component {
 		self.y
 	}
+}
 
-	pub fn translate_x(&self, x: Component) -> Self {
-		Vec2f::new(self.x + x, self.y)
-	}
-
-	pub fn translate_y(&self, y: Component) -> Self {
-		Vec2f::new(self.x, self.y + y)
-	}
-
-	pub fn translate(&self, x: Component, y: Component) -> Self {
-		Vec2f::new(self.x + x, self.y + y)
+// This is used when deserializing, so that out-of-range components become errors rather than panics
+impl TryFrom<(Component, Component)> for Vec2f {
+	type Error = String;
+
+	fn try_from((x, y): (Component, Component)) -> Result<Self, Self::Error> {
+		if (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y) {
+			Ok(Self {x, y})
+		}
+		else {
+			Err(format!("The vector ({x}, {y}) has components outside of the unit interval"))
+		}
 	}
 }
 
@@ -1,4 +1,4 @@
-use sdl2::{self, rect::Rect};
+use sdl2::{self, rect::Rect, render::RenderTarget};
 
 use crate::{
 	utility_types::{
@@ -34,15 +34,18 @@ impl From<FRect> for Rect {
 
 // TODO: maybe put these in `utility_types`
 pub type ColorSDL = sdl2::pixels::Color;
-pub type CanvasSDL = sdl2::render::Canvas<sdl2::video::Window>;
+pub type CanvasSDL<T> = sdl2::render::Canvas<T>; // This is generic so that surfaces can be rendered to too
+
+/* This is the local time at the start of a frame. Every window gets the same one
+for a frame, and it can be frozen (which the snapshot tests rely on). */
+pub type FrameTime = chrono::DateTime<chrono::FixedOffset>;
 
-/* TODO: can I pass a current time parameter in here,
-in order to allow for timing-based effects like texture fade-in? */
 pub struct WindowUpdaterParams<'a, 'b, 'c, 'd> {
 	pub window: &'a mut Window,
 	pub texture_pool: &'b mut TexturePool<'c>,
 	pub shared_window_state: &'d mut DynamicOptional,
-	pub area_drawn_to_screen: (u32, u32)
+	pub area_drawn_to_screen: (u32, u32),
+	pub curr_time: FrameTime
 }
 
 // TODO: genericize these two over one typedef
@@ -58,10 +61,11 @@ pub type PossibleSharedWindowStateUpdater = Option<(
 )>;
 
 // This data remains constant over a recursive rendering call (TODO: make a constructor for this)
-pub struct PerFrameConstantRenderingParams<'a> {
-	pub sdl_canvas: CanvasSDL,
+pub struct PerFrameConstantRenderingParams<'a, T: RenderTarget> {
+	pub sdl_canvas: CanvasSDL<T>,
 	pub texture_pool: TexturePool<'a>,
 	pub frame_counter: FrameCounter,
+	pub curr_time: FrameTime,
 	pub shared_window_state: DynamicOptional,
 	pub shared_window_state_updater: PossibleSharedWindowStateUpdater
 }
@@ -219,6 +223,11 @@ impl Window {
 		&mut self.contents
 	}
 
+	// This is for updaters that need to read their state while writing to their contents
+	pub fn get_state_and_contents_mut<T: 'static>(&mut self) -> (&T, &mut WindowContents) {
+		(self.state.get(), &mut self.contents)
+	}
+
 	pub fn set_draw_skipping(&mut self, skip_drawing: bool) {
 		self.skip_drawing = skip_drawing;
 	}
@@ -231,9 +240,28 @@ impl Window {
 		self.skip_aspect_ratio_correction = skip_aspect_ratio_correction;
 	}
 
+	pub fn set_border_color(&mut self, maybe_border_color: Option<ColorSDL>) {
+		self.maybe_border_color = maybe_border_color;
+	}
+
+	// The top left and size are relative to the parent window, like the ones passed into `new`
+	pub fn set_top_left(&mut self, top_left: Vec2f) {
+		self.top_left = top_left;
+	}
+
+	pub fn set_size(&mut self, size: Vec2f) {
+		self.size = size;
+	}
+
+	// New children are drawn after (so over) the existing ones
+	pub fn add_children(&mut self, new_children: Vec<Self>) {
+		if new_children.is_empty() {return;}
+		self.children.get_or_insert_with(Vec::new).extend(new_children);
+	}
+
 	////////// These are the window rendering functions (both public and private)
 
-	pub fn render(&mut self, rendering_params: &mut PerFrameConstantRenderingParams) -> MaybeError {
+	pub fn render<T: RenderTarget>(&mut self, rendering_params: &mut PerFrameConstantRenderingParams<T>) -> MaybeError {
 		let output_size = rendering_params.sdl_canvas.output_size().to_generic()?;
 		let sdl_window_bounds = FRect {x: 0.0, y: 0.0, width: output_size.0 as f32, height: output_size.1 as f32};
 		self.inner_render(rendering_params, sdl_window_bounds)
@@ -243,8 +271,8 @@ impl Window {
 		(v.x() * parent_rect.width + parent_rect.x, v.y() * parent_rect.height + parent_rect.y)
 	}
 
-	fn inner_render(&mut self,
-		rendering_params: &mut PerFrameConstantRenderingParams,
+	fn inner_render<T: RenderTarget>(&mut self,
+		rendering_params: &mut PerFrameConstantRenderingParams<T>,
 		parent_rect: FRect) -> MaybeError {
 
 		////////// Getting the new pixel-space bounding box for this window
@@ -273,7 +301,8 @@ impl Window {
 					window: self,
 					texture_pool: &mut rendering_params.texture_pool,
 					shared_window_state: &mut rendering_params.shared_window_state,
-					area_drawn_to_screen: (screen_dest.width as u32, screen_dest.height as u32)
+					area_drawn_to_screen: (screen_dest.width as u32, screen_dest.height as u32),
+					curr_time: rendering_params.curr_time
 				})?;
 			}
 		}
@@ -293,8 +322,8 @@ impl Window {
 		Ok(())
 	}
 
-	fn draw_window_contents(&mut self,
-		rendering_params: &mut PerFrameConstantRenderingParams,
+	fn draw_window_contents<T: RenderTarget>(&mut self,
+		rendering_params: &mut PerFrameConstantRenderingParams<T>,
 		uncorrected_screen_dest: FRect) -> MaybeError {
 
 		//////////
@@ -314,9 +343,9 @@ impl Window {
 
 		////////// A function for drawing the contents passed to it
 
-		fn draw_contents(
+		fn draw_contents<T: RenderTarget>(
 			contents: &WindowContents,
-			rendering_params: &mut PerFrameConstantRenderingParams,
+			rendering_params: &mut PerFrameConstantRenderingParams<T>,
 			uncorrected_screen_dest: FRect,
 			skip_aspect_ratio_correction: bool) -> MaybeError {
 
@@ -353,7 +382,7 @@ impl Window {
 				the opaque areas of textures with alpha values */
 				WindowContents::Texture(texture) =>
 					rendering_params.texture_pool.draw_texture_to_canvas(
-						texture, sdl_canvas, maybe_corrected_screen_dest.into()
+						texture, sdl_canvas, maybe_corrected_screen_dest.into(), rendering_params.curr_time
 					)?,
 
 				WindowContents::Many(many) => {
@@ -372,8 +401,8 @@ impl Window {
 
 		////////// A function for drawing colors with transparency
 
-		fn possibly_draw_with_transparency(color: &ColorSDL, sdl_canvas: &mut CanvasSDL,
-			mut drawer: impl FnMut(&mut CanvasSDL) -> MaybeError) -> MaybeError {
+		fn possibly_draw_with_transparency<T: RenderTarget>(color: &ColorSDL, sdl_canvas: &mut CanvasSDL<T>,
+			mut drawer: impl FnMut(&mut CanvasSDL<T>) -> MaybeError) -> MaybeError {
 
 			use sdl2::render::BlendMode;
 
@@ -413,25 +442,100 @@ impl Window {
 				}
 			}
 		}
+	}
+}
 
-		////////// A function for making a rect within another one with a given aspect ratio
+////////// A function for making a rect within another one with a given aspect ratio
 
-		fn get_centered_subrect_with_aspect_ratio(orig_rect: FRect, desired_aspect_ratio: f32) -> FRect {
-			let orig_aspect_ratio = orig_rect.width / orig_rect.height;
+fn get_centered_subrect_with_aspect_ratio(orig_rect: FRect, desired_aspect_ratio: f32) -> FRect {
+	let orig_aspect_ratio = orig_rect.width / orig_rect.height;
 
-			let (width, height) = if desired_aspect_ratio > orig_aspect_ratio {
-				(orig_rect.width, orig_rect.width / desired_aspect_ratio)
-			}
-			else {
-				(orig_rect.height * desired_aspect_ratio, orig_rect.height)
-			};
+	let (width, height) = if desired_aspect_ratio > orig_aspect_ratio {
+		(orig_rect.width, orig_rect.width / desired_aspect_ratio)
+	}
+	else {
+		(orig_rect.height * desired_aspect_ratio, orig_rect.height)
+	};
+
+	FRect {
+		x: orig_rect.x + (orig_rect.width - width) * 0.5,
+		y: orig_rect.y + (orig_rect.height - height) * 0.5,
+		width,
+		height
+	}
+}
+
+//////////
 
-			FRect {
-				x: orig_rect.x + (orig_rect.width - width) * 0.5,
-				y: orig_rect.y + (orig_rect.height - height) * 0.5,
-				width,
-				height
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::snapshot_testing::assert_matches_golden_image;
+
+	fn assert_rects_match(rect: FRect, expected: (f32, f32, f32, f32)) {
+		let actual = (rect.x, rect.y, rect.width, rect.height);
+		assert!((actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4
+			&& (actual.2 - expected.2).abs() < 1e-4 && (actual.3 - expected.3).abs() < 1e-4,
+			"Expected a rect of {expected:?}, but got {actual:?}");
+	}
+
+	#[test]
+	fn subrect_for_wider_aspect_ratio_is_letterboxed() {
+		let rect = FRect {x: 10.0, y: 20.0, width: 100.0, height: 100.0};
+		assert_rects_match(get_centered_subrect_with_aspect_ratio(rect, 2.0), (10.0, 45.0, 100.0, 50.0));
+	}
+
+	#[test]
+	fn subrect_for_taller_aspect_ratio_is_pillarboxed() {
+		let rect = FRect {x: 0.0, y: 0.0, width: 200.0, height: 100.0};
+		assert_rects_match(get_centered_subrect_with_aspect_ratio(rect, 0.5), (75.0, 0.0, 50.0, 100.0));
+	}
+
+	#[test]
+	fn subrect_for_same_aspect_ratio_is_unchanged() {
+		let rect = FRect {x: 5.0, y: 5.0, width: 160.0, height: 90.0};
+		assert_rects_match(get_centered_subrect_with_aspect_ratio(rect, 16.0 / 9.0), (5.0, 5.0, 160.0, 90.0));
+	}
+
+	/* The first two textures are corrected to keep their aspect ratios
+	(one wide, and one tall), and the last one is stretched to fill its box. */
+	#[test]
+	fn aspect_ratio_correction() {
+		assert_matches_golden_image("aspect_ratio_correction", (450, 200), |texture_pool| {
+			let texture_paths_and_skipping = [
+				("assets/logo.png", false),
+				("assets/soup.png", false),
+				("assets/soup.png", true)
+			];
+
+			let mut children = Vec::new();
+
+			for (i, (path, skip_aspect_ratio_correction)) in texture_paths_and_skipping.into_iter().enumerate() {
+				let mut child = Window::new(
+					None,
+					DynamicOptional::NONE,
+					WindowContents::make_texture_contents(path, texture_pool)?,
+					Some(ColorSDL::RGB(255, 255, 255)),
+					Vec2f::new(i as f32 / 3.0, 0.0),
+					Vec2f::new(1.0 / 3.0, 1.0),
+					None
+				);
+
+				child.set_aspect_ratio_correction_skipping(skip_aspect_ratio_correction);
+				children.push(child);
 			}
-		}
+
+			let window = Window::new(
+				None,
+				DynamicOptional::NONE,
+				WindowContents::Nothing,
+				None,
+				Vec2f::ZERO,
+				Vec2f::ONE,
+				Some(children)
+			);
+
+			Ok((window, DynamicOptional::NONE))
+		});
 	}
 }